@@ -0,0 +1,121 @@
+use std::time::Duration;
+use structopt::StructOpt;
+
+use kafka::producer::{Producer, Record, RequiredAcks};
+
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::consumer::retry_topic;
+use rust_cqrses_bankaccount_mysql_example::deadletter::{MysqlDeadLetterStore, StoredDeadLetter};
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    env_logger::init();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let args = Args::from_args();
+
+    let store = MysqlDeadLetterStore::new(db::init_database_pool(&config.database_url));
+
+    match args.cmd {
+        Command::List => list(&store),
+        Command::Show{ id } => show(&store, id),
+        Command::Replay{ id } => replay(&store, &config, id),
+        Command::Discard{ id } => discard(&store, id),
+    };
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "dead_letter")]
+pub struct Args {
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    List,
+    Show {
+        id: u64,
+    },
+    Replay {
+        id: u64,
+    },
+    Discard {
+        id: u64,
+    },
+}
+
+fn find(store: &MysqlDeadLetterStore, id: u64) -> StoredDeadLetter {
+    match store.find(id).expect("dead letter store error occurred") {
+        Some(stored) => stored,
+        None => {
+            eprintln!("Dead letter {} not found", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn list(store: &MysqlDeadLetterStore) {
+    println!("{:<8} {:<24} {:<20} {:>9} {:>10} {:>7}  {}", "ID", "GROUP", "TOPIC", "PARTITION", "OFFSET", "RETRIES", "ERROR");
+    for stored in store.list().expect("dead letter store error occurred") {
+        let dead_letter = &stored.dead_letter;
+        println!("{:<8} {:<24} {:<20} {:>9} {:>10} {:>7}  {}",
+                 stored.id, dead_letter.group, dead_letter.topic, dead_letter.partition,
+                 dead_letter.offset, dead_letter.retries, dead_letter.error);
+    }
+}
+
+fn show(store: &MysqlDeadLetterStore, id: u64) {
+    let stored = find(store, id);
+    let dead_letter = &stored.dead_letter;
+    println!("id:         {}", stored.id);
+    println!("group:      {}", dead_letter.group);
+    println!("topic:      {}", dead_letter.topic);
+    println!("partition:  {}", dead_letter.partition);
+    println!("offset:     {}", dead_letter.offset);
+    println!("retries:    {}", dead_letter.retries);
+    println!("created_at: {}", stored.created_at.to_rfc3339());
+    println!("updated_at: {}", stored.updated_at.to_rfc3339());
+    println!("error:      {}", dead_letter.error);
+    println!("key:        {}", String::from_utf8_lossy(&dead_letter.key));
+    println!("payload:    {}", String::from_utf8_lossy(&dead_letter.payload));
+}
+
+fn replay(store: &MysqlDeadLetterStore, config: &Config, id: u64) {
+    let stored = find(store, id);
+    let dead_letter = &stored.dead_letter;
+    let topic = retry_topic(&dead_letter.group);
+
+    let result = Producer::from_hosts(config.kafka_brokers.clone())
+        .with_ack_timeout(Duration::from_secs(1))
+        .with_required_acks(RequiredAcks::One)
+        .create()
+        .and_then(|mut producer| {
+            producer.send(&Record::from_key_value(
+                    &topic, dead_letter.key.as_slice(), dead_letter.payload.as_slice()))
+        });
+
+    match result {
+        Ok(_) => {
+            let discarded = store.discard_message(dead_letter).expect("dead letter store error occurred");
+            println!("Replayed dead letter {} to {} ({} entries removed)", id, topic, discarded);
+        },
+        Err(err) => {
+            store.record_retry(id, err.to_string()).expect("dead letter store error occurred");
+            eprintln!("Replay dead letter {} failed: {}", id, err);
+            std::process::exit(1);
+        },
+    }
+}
+
+fn discard(store: &MysqlDeadLetterStore, id: u64) {
+    if store.discard(id).expect("dead letter store error occurred") {
+        println!("Discarded dead letter {}", id);
+    } else {
+        eprintln!("Dead letter {} not found", id);
+        std::process::exit(1);
+    }
+}
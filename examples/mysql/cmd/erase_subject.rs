@@ -0,0 +1,53 @@
+use std::sync::Arc;
+use structopt::StructOpt;
+
+use rust_cqrses_bankaccount::aggregate::{BankAccountAggregate, BankAccountId};
+use rust_cqrses_bankaccount::crypto::PiiCipher;
+use rust_cqrses_bankaccount::projector::BankAccountProjector;
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::dao::create_read_model_dao;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    env_logger::init();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let args = Args::from_args();
+
+    let bank_account_id = match BankAccountId::new(args.bank_account_id) {
+        Ok(id) => id,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let pool = db::init_database_pool(&config.database_url);
+
+    let cipher = PiiCipher::new(create_key_store(&config, pool.clone()));
+    cipher.erase_subject(&bank_account_id).expect("key store error occurred");
+    println!("{}: data key destroyed", bank_account_id);
+
+    let store = MysqlBankAccountEventStore::new(pool.clone());
+    let redacted = store.redact_plaintext_names(BankAccountAggregate::stream_id(&bank_account_id))
+        .expect("event store error occurred");
+    if redacted > 0 {
+        println!("{}: {} unencrypted names redacted", bank_account_id, redacted);
+    }
+
+    let projector = BankAccountProjector::new(create_read_model_dao(&config, Arc::new(db::Session::new(pool))));
+    if projector.redact(bank_account_id.clone()).expect("read model store error occurred") {
+        println!("{}: read model redacted", bank_account_id);
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "erase_subject")]
+pub struct Args {
+    bank_account_id: String,
+}
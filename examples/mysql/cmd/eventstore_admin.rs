@@ -0,0 +1,334 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::sync::Arc;
+use structopt::StructOpt;
+use tracing::info_span;
+
+use rust_cqrses_bankaccount::aggregate::{BankAccount, BankAccountAggregate, BankAccountEvent, BankAccountId};
+use rust_cqrses_bankaccount::crypto::PiiCipher;
+use rust_cqrses_bankaccount::encrypting_eventstore::EncryptingEventStore;
+use rust_cqrses_bankaccount::eventsourcing::{EventStore, EventPublisher};
+use rust_cqrses_bankaccount::hashchain::HashChainedEventStore;
+use rust_cqrses_bankaccount::inmemory_eventstore::StoredEvent;
+use rust_cqrses_bankaccount::jsonl::{export_streams, import_streams};
+use rust_cqrses_bankaccount::snapshotter::BankAccountAggregateSnapshotter;
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::constants;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::eventpublisher::KafkaBankAccountEventPublisher;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+use rust_cqrses_bankaccount_mysql_example::telemetry::{TraceContext, continue_trace, init_telemetry};
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let _telemetry = init_telemetry("eventstore_admin", config.trace_exporter);
+
+    let args = Args::from_args();
+
+    let pool = db::init_database_pool(&config.database_url);
+
+    let admin = Admin {
+        store: Arc::new(MysqlBankAccountEventStore::new(pool.clone())),
+        cipher: PiiCipher::new(create_key_store(&config, pool)),
+    };
+
+    match args.cmd {
+        Command::Streams => admin.streams(),
+        Command::Events{ bank_account_id, from, to, format } => admin.events(bank_account_id, from, to, format),
+        Command::State{ bank_account_id, version } => admin.state(bank_account_id, version),
+        Command::CheckSnapshot{ bank_account_id } => admin.check_snapshot(bank_account_id),
+        Command::Resnapshot{ bank_account_id } => admin.resnapshot(bank_account_id),
+        Command::Republish{ bank_account_id, from, to } => admin.republish(&config, bank_account_id, from, to),
+        Command::Export{ bank_account_ids, output } => admin.export(bank_account_ids, output),
+        Command::Import{ input, dry_run } => admin.import(input, dry_run),
+    };
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "eventstore_admin")]
+pub struct Args {
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    Streams,
+    Events {
+        bank_account_id: String,
+        #[structopt(long = "from", default_value = "1")]
+        from: u64,
+        #[structopt(long = "to")]
+        to: Option<u64>,
+        #[structopt(long = "format", default_value = "table", possible_values = &["table", "json"])]
+        format: String,
+    },
+    State {
+        bank_account_id: String,
+        #[structopt(long = "version")]
+        version: Option<u64>,
+    },
+    CheckSnapshot {
+        bank_account_id: Option<String>,
+    },
+    Resnapshot {
+        bank_account_id: Option<String>,
+    },
+    Republish {
+        bank_account_id: String,
+        #[structopt(long = "from", default_value = "1")]
+        from: u64,
+        #[structopt(long = "to")]
+        to: Option<u64>,
+    },
+    Export {
+        bank_account_ids: Vec<String>,
+        #[structopt(long = "output")]
+        output: Option<String>,
+    },
+    Import {
+        input: String,
+        #[structopt(long = "dry-run")]
+        dry_run: bool,
+    },
+}
+
+fn exit_with(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn parse_bank_account_id(value: String) -> BankAccountId {
+    BankAccountId::new(value).unwrap_or_else(|err| exit_with(err.to_string()))
+}
+
+struct Admin {
+    store: Arc<MysqlBankAccountEventStore>,
+    cipher: PiiCipher,
+}
+
+impl Admin {
+    fn bank_account_ids(&self, bank_account_id: Option<String>) -> Vec<BankAccountId> {
+        match bank_account_id {
+            Some(value) => vec![parse_bank_account_id(value)],
+            None => self.store.stream_ids().expect("event store error occurred")
+                .iter()
+                .filter_map(|stream_id| BankAccountAggregate::bank_account_id_of(stream_id))
+                .collect(),
+        }
+    }
+
+    fn stored_events(&self, bank_account_id: &BankAccountId, from: u64, to: Option<u64>)
+        -> Vec<(StoredEvent, BankAccountEvent)> {
+        self.store.stored_events(BankAccountAggregate::stream_id(bank_account_id))
+            .expect("event store error occurred")
+            .into_iter()
+            .filter(|stored| stored.stream_version() >= from && to.map_or(true, |to| stored.stream_version() <= to))
+            .map(|stored| {
+                let event = serde_json::from_str(stored.event_body()).unwrap_or_else(|err| {
+                    exit_with(format!("{}@{}: {}", stored.stream_id(), stored.stream_version(), err))
+                });
+                (stored, event)
+            })
+            .collect()
+    }
+
+    fn replay(&self, bank_account_id: &BankAccountId, version: Option<u64>) -> Result<BankAccountAggregate, String> {
+        let events = self.stored_events(bank_account_id, 1, version);
+        match events.first() {
+            None => return Err(format!("{} has no events", bank_account_id)),
+            Some((stored, _)) if stored.stream_version() != 1 =>
+                return Err(format!("{} has been purged before version {}", bank_account_id, stored.stream_version())),
+            _ => (),
+        }
+
+        let version = events.last().unwrap().0.stream_version();
+        let history = events.into_iter()
+            .map(|(_, event)| self.cipher.decrypt_event(event))
+            .collect::<Result<Vec<BankAccountEvent>, _>>()
+            .map_err(|err| err.to_string())?;
+        BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), history, version)
+            .map_err(|err| err.to_string())
+    }
+
+    fn streams(&self) {
+        for stream_id in self.store.stream_ids().expect("event store error occurred") {
+            let events = self.store.stored_events(stream_id.clone()).expect("event store error occurred");
+            let metadata = self.store.stream_metadata(stream_id.clone()).expect("event store error occurred");
+            println!("{}\tversion={}\tevents={}{}",
+                     stream_id,
+                     events.last().map(|stored| stored.stream_version()).unwrap_or(0),
+                     events.len(),
+                     if metadata.deleted { "\tdeleted" } else { "" });
+        }
+    }
+
+    fn events(&self, bank_account_id: String, from: u64, to: Option<u64>, format: String) {
+        let bank_account_id = parse_bank_account_id(bank_account_id);
+        for (stored, event) in self.stored_events(&bank_account_id, from, to) {
+            let event = self.cipher.decrypt_event(event).unwrap_or_else(|err| exit_with(err.to_string()));
+            if format == "json" {
+                println!("{}", serde_json::json!({
+                    "stream_id": stored.stream_id(),
+                    "stream_version": stored.stream_version(),
+                    "event_type": stored.event_type(),
+                    "event_occurred_at": stored.event_occurred_at(),
+                    "event_hash": stored.event_hash(),
+                    "event": event,
+                }));
+            } else {
+                println!("{:>6}  {}  {:<24}  {}",
+                         stored.stream_version(),
+                         stored.event_occurred_at().format("%Y-%m-%d %H:%M:%S"),
+                         stored.event_type(),
+                         describe(&event));
+            }
+        }
+    }
+
+    fn state(&self, bank_account_id: String, version: Option<u64>) {
+        let bank_account_id = parse_bank_account_id(bank_account_id);
+        let aggregate = self.replay(&bank_account_id, version).unwrap_or_else(|err| exit_with(err));
+        println!("version: {}", aggregate.version());
+        match aggregate.state() {
+            Some(state) => println!("{}", serde_json::to_string_pretty(state).unwrap()),
+            None => println!("no state"),
+        }
+    }
+
+    fn check_snapshot(&self, bank_account_id: Option<String>) {
+        let mut mismatches = 0;
+        for bank_account_id in self.bank_account_ids(bank_account_id) {
+            let snapshot = match self.store.read_snapshot(BankAccountAggregate::stream_id(&bank_account_id))
+                .expect("event store error occurred") {
+                Some(snapshot) => snapshot,
+                None => {
+                    println!("{}: no snapshot", bank_account_id);
+                    continue;
+                },
+            };
+
+            let latest = self.store.stored_events(BankAccountAggregate::stream_id(&bank_account_id))
+                .expect("event store error occurred")
+                .last()
+                .map(|stored| stored.stream_version())
+                .unwrap_or(0);
+            let replayed = match self.replay(&bank_account_id, Some(snapshot.stream_version())) {
+                Ok(aggregate) => aggregate,
+                Err(err) => {
+                    println!("{}: can't replay: {}", bank_account_id, err);
+                    continue;
+                },
+            };
+            let snapshotted: BankAccount = self.cipher.decrypt_snapshot(snapshot.snapshot().clone())
+                .unwrap_or_else(|err| exit_with(err.to_string()));
+
+            if replayed.version() != snapshot.stream_version() || replayed.state().as_ref() != Some(&snapshotted) {
+                mismatches += 1;
+                println!("{}: snapshot@{} differs from replay@{}", bank_account_id, snapshot.stream_version(), replayed.version());
+                println!("  snapshot: {}", serde_json::to_string(&snapshotted).unwrap());
+                println!("  replay:   {}", serde_json::to_string(replayed.state()).unwrap());
+            } else if snapshot.stream_version() < latest {
+                println!("{}: snapshot@{} matches, {} events behind", bank_account_id, snapshot.stream_version(),
+                         latest - snapshot.stream_version());
+            } else {
+                println!("{}: snapshot@{} matches", bank_account_id, snapshot.stream_version());
+            }
+        }
+        if mismatches > 0 {
+            std::process::exit(2);
+        }
+    }
+
+    fn resnapshot(&self, bank_account_id: Option<String>) {
+        let snapshotter = BankAccountAggregateSnapshotter::new(Box::new(self.decrypting_store()));
+        for bank_account_id in self.bank_account_ids(bank_account_id) {
+            match snapshotter.take_snapshot(bank_account_id.clone()) {
+                Ok(version) => println!("{}: snapshot@{}", bank_account_id, version),
+                Err(err) => println!("{}: {}", bank_account_id, err),
+            }
+        }
+    }
+
+    fn republish(&self, config: &Config, bank_account_id: String, from: u64, to: Option<u64>) {
+        let bank_account_id = parse_bank_account_id(bank_account_id);
+        let stored_events = self.stored_events(&bank_account_id, from, to);
+        if stored_events.is_empty() {
+            exit_with(format!("{} has no events in range", bank_account_id));
+        }
+
+        let count = stored_events.len();
+        let publisher = KafkaBankAccountEventPublisher::new(config.kafka_brokers.clone(), String::from(constants::TOPIC))
+            .expect("kafka producer build error occurred");
+        for (stored, event) in stored_events {
+            let trace_context: TraceContext = stored.event_metadata()
+                .and_then(|event_metadata| serde_json::from_str(event_metadata).ok())
+                .unwrap_or_default();
+            let span = info_span!("republish_event",
+                                  stream_id = %stored.stream_id(),
+                                  stream_version = stored.stream_version());
+            continue_trace(&span, &trace_context);
+            let _entered = span.enter();
+
+            publisher.publish(stored.stream_id().to_string(), stored.stream_version(), vec![event])
+                .expect("kafka publish error occurred");
+        }
+        println!("{}: {} events republished", bank_account_id, count);
+    }
+
+    fn decrypting_store(&self) -> EncryptingEventStore<Arc<MysqlBankAccountEventStore>> {
+        EncryptingEventStore::new(self.store.clone(), self.cipher.clone())
+    }
+
+    fn export(&self, bank_account_ids: Vec<String>, output: Option<String>) {
+        let stream_ids: Vec<String> = if bank_account_ids.is_empty() {
+            self.store.stream_ids().expect("event store error occurred")
+        } else {
+            bank_account_ids.into_iter()
+                .map(|value| BankAccountAggregate::stream_id(&parse_bank_account_id(value)))
+                .collect()
+        };
+
+        let mut writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(BufWriter::new(File::create(&path).unwrap_or_else(|err| exit_with(err.to_string())))),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        let summary = export_streams(&self.decrypting_store(), &stream_ids, &mut writer)
+            .unwrap_or_else(|err| exit_with(err.to_string()));
+
+        eprintln!("exported {} events from {} streams", summary.events, summary.streams);
+        for stream_id in summary.missing {
+            eprintln!("{}: not found", stream_id);
+        }
+    }
+
+    fn import(&self, input: String, dry_run: bool) {
+        let file = File::open(&input).unwrap_or_else(|err| exit_with(err.to_string()));
+        let summary = import_streams(&self.decrypting_store(), BufReader::new(file), dry_run)
+            .unwrap_or_else(|err| exit_with(err.to_string()));
+
+        println!("{}{} streams, {} events imported, {} already present, {} upcasted, {} streams failed",
+                 if dry_run { "dry run: " } else { "" },
+                 summary.streams, summary.imported, summary.skipped, summary.upcasted, summary.failed.len());
+        for (stream_id, reason) in summary.failed.iter() {
+            println!("{}: {}", stream_id, reason);
+        }
+        if !summary.failed.is_empty() {
+            std::process::exit(2);
+        }
+    }
+}
+
+fn describe(event: &BankAccountEvent) -> String {
+    match event {
+        BankAccountEvent::Opened{ name, .. } => format!("name={}", name),
+        BankAccountEvent::Updated{ name, .. } => format!("name={}", name),
+        BankAccountEvent::Deposited{ deposit, .. } => format!("deposit={}", deposit),
+        BankAccountEvent::Withdrawn{ withdraw, .. } => format!("withdraw={}", withdraw),
+        BankAccountEvent::Closed{ .. } => String::new(),
+    }
+}
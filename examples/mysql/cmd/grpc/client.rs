@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use structopt::StructOpt;
 
-use grpcio::{ChannelBuilder, EnvBuilder};
+use grpcio::{ChannelBuilder, EnvBuilder, Error as GrpcError};
 
 use protos::bank_account::{
     OpenBankAccountRequest,
@@ -14,6 +14,11 @@ use protos::bank_account::{
     DepositBankAccountRequest,
     WithdrawBankAccountRequest,
     CloseBankAccountRequest,
+    SubscribeAccountEventsRequest,
+    AccountEvent,
+    AccountEvent_oneof_event,
+    ErrorDetail,
+    Status,
 };
 
 use protos::bank_account_grpc::BankAccountServiceClient;
@@ -29,12 +34,19 @@ fn main() {
     let ch = ChannelBuilder::new(env).connect(&format!("{}:{}", args.host, args.port));
     let client = BankAccountServiceClient::new(ch);
 
+    let idempotency_key = args.idempotency_key.unwrap_or_default();
+
     match args.cmd {
-        Command::Open{ name } => open_bank_account(&client, name),
-        Command::Update{ bank_account_id, name } => update_bank_account(&client, bank_account_id, name),
-        Command::Deposit{ bank_account_id, deposit } => deposit_bank_account(&client, bank_account_id, deposit),
-        Command::Withdraw{ bank_account_id, withdraw } => withdraw_bank_account(&client, bank_account_id, withdraw),
-        Command::Close{ bank_account_id } => close_bank_account(&client, bank_account_id),
+        Command::Open{ name, bank_account_id } =>
+            open_bank_account(&client, name, bank_account_id, idempotency_key),
+        Command::Update{ bank_account_id, name } =>
+            update_bank_account(&client, bank_account_id, name, idempotency_key),
+        Command::Deposit{ bank_account_id, deposit } =>
+            deposit_bank_account(&client, bank_account_id, deposit, idempotency_key),
+        Command::Withdraw{ bank_account_id, withdraw } =>
+            withdraw_bank_account(&client, bank_account_id, withdraw, idempotency_key),
+        Command::Close{ bank_account_id } => close_bank_account(&client, bank_account_id, idempotency_key),
+        Command::Watch{ bank_account_id, from_version } => watch_bank_account(&client, bank_account_id, from_version),
     };
 }
 
@@ -47,6 +59,9 @@ pub struct Args {
     #[structopt(long, default_value="8080")]
     pub port: u16,
 
+    #[structopt(long)]
+    pub idempotency_key: Option<String>,
+
     #[structopt(subcommand)]
     cmd: Command,
 }
@@ -55,6 +70,8 @@ pub struct Args {
 pub enum Command {
     Open {
         name: String,
+        #[structopt(long = "bank-account-id")]
+        bank_account_id: Option<String>,
     },
     Update {
         bank_account_id: String,
@@ -71,62 +88,134 @@ pub enum Command {
     Close {
         bank_account_id: String,
     },
+    Watch {
+        bank_account_id: String,
+        #[structopt(long = "from-version", default_value = "1")]
+        from_version: u64,
+    },
 }
 
-fn open_bank_account(client: &BankAccountServiceClient, name: String) {
+fn exit_with_rpc_error(err: GrpcError) -> ! {
+    match err {
+        GrpcError::RpcFailure(status) => {
+            let detail = protobuf::parse_from_bytes::<Status>(status.details()).ok()
+                .and_then(|details| {
+                    details.get_details().iter()
+                        .find(|detail| detail.get_type_url().ends_with("/ErrorDetail"))
+                        .and_then(|detail| protobuf::parse_from_bytes::<ErrorDetail>(detail.get_value()).ok())
+                });
+            match detail {
+                Some(detail) => {
+                    eprintln!("{:?}: {:?}", status.code(), detail.get_reason());
+                    eprintln!("  message: {}", status.message());
+                    if !detail.get_bank_account_id().is_empty() {
+                        eprintln!("  bank_account_id: {}", detail.get_bank_account_id());
+                    }
+                    if detail.get_amount() != 0 {
+                        eprintln!("  amount: {}", detail.get_amount());
+                    }
+                },
+                None => eprintln!("{:?}: {}", status.code(), status.message()),
+            }
+        },
+        err => eprintln!("rpc error: {}", err),
+    }
+    std::process::exit(1);
+}
+
+fn open_bank_account(client: &BankAccountServiceClient, name: String, bank_account_id: Option<String>,
+                     idempotency_key: String) {
     let mut req = OpenBankAccountRequest::default();
     req.set_name(name);
+    req.set_bank_account_id(bank_account_id.unwrap_or_default());
+    req.set_idempotency_key(idempotency_key);
 
     info!("Send request: {:?}", &req);
 
-    let reply = client.open(&req).expect("rpc");
+    let reply = client.open(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
 
     info!("Response received: {:?}", &reply);
 }
 
-fn update_bank_account(client: &BankAccountServiceClient, bank_account_id: String, name: String) {
+fn update_bank_account(client: &BankAccountServiceClient, bank_account_id: String, name: String,
+                       idempotency_key: String) {
     let mut req = UpdateBankAccountRequest::default();
     req.set_bank_account_id(bank_account_id);
     req.set_name(name);
+    req.set_idempotency_key(idempotency_key);
 
     info!("Send request: {:?}", &req);
 
-    let reply = client.update(&req).expect("rpc");
+    let reply = client.update(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
 
     info!("Response received: {:?}", &reply);
 }
 
-fn deposit_bank_account(client: &BankAccountServiceClient, bank_account_id: String, deposit: i32) {
+fn deposit_bank_account(client: &BankAccountServiceClient, bank_account_id: String, deposit: i32,
+                        idempotency_key: String) {
     let mut req = DepositBankAccountRequest::default();
     req.set_bank_account_id(bank_account_id);
     req.set_deposit(deposit);
+    req.set_idempotency_key(idempotency_key);
 
     info!("Send request: {:?}", &req);
 
-    let reply = client.deposit(&req).expect("rpc");
+    let reply = client.deposit(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
 
     info!("Response received: {:?}", &reply);
 }
 
-fn withdraw_bank_account(client: &BankAccountServiceClient, bank_account_id: String, withdraw: i32) {
+fn withdraw_bank_account(client: &BankAccountServiceClient, bank_account_id: String, withdraw: i32,
+                         idempotency_key: String) {
     let mut req = WithdrawBankAccountRequest::default();
     req.set_bank_account_id(bank_account_id);
     req.set_withdraw(withdraw);
+    req.set_idempotency_key(idempotency_key);
 
     info!("Send request: {:?}", &req);
 
-    let reply = client.withdraw(&req).expect("rpc");
+    let reply = client.withdraw(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
 
     info!("Response received: {:?}", &reply);
 }
 
-fn close_bank_account(client: &BankAccountServiceClient, bank_account_id: String) {
+fn close_bank_account(client: &BankAccountServiceClient, bank_account_id: String, idempotency_key: String) {
     let mut req = CloseBankAccountRequest::default();
     req.set_bank_account_id(bank_account_id);
+    req.set_idempotency_key(idempotency_key);
 
     info!("Send request: {:?}", &req);
 
-    let reply = client.close(&req).expect("rpc");
+    let reply = client.close(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
 
     info!("Response received: {:?}", &reply);
 }
+
+fn print_account_event(event: &AccountEvent) {
+    let detail = match event.event {
+        Some(AccountEvent_oneof_event::opened(ref opened)) => format!("opened name={}", opened.get_name()),
+        Some(AccountEvent_oneof_event::updated(ref updated)) => format!("updated name={}", updated.get_name()),
+        Some(AccountEvent_oneof_event::deposited(ref deposited)) =>
+            format!("deposited deposit={}", deposited.get_deposit()),
+        Some(AccountEvent_oneof_event::withdrawn(ref withdrawn)) =>
+            format!("withdrawn withdraw={}", withdrawn.get_withdraw()),
+        Some(AccountEvent_oneof_event::closed(_)) => String::from("closed"),
+        None => String::from("unknown"),
+    };
+    println!("{:>6} {} {}", event.get_stream_version(), event.get_occurred_at(), detail);
+}
+
+fn watch_bank_account(client: &BankAccountServiceClient, bank_account_id: String, from_version: u64) {
+    let mut req = SubscribeAccountEventsRequest::default();
+    req.set_bank_account_id(bank_account_id);
+    req.set_from_version(from_version);
+
+    info!("Send request: {:?}", &req);
+
+    let events = client.subscribe_account_events(&req).unwrap_or_else(|err| exit_with_rpc_error(err));
+
+    for event in futures::executor::block_on_stream(events) {
+        let event = event.unwrap_or_else(|err| exit_with_rpc_error(err));
+        print_account_event(&event);
+    }
+}
@@ -30,6 +30,8 @@ const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_2_8_1;
 pub struct OpenBankAccountRequest {
     // message fields
     pub name: ::std::string::String,
+    pub bank_account_id: ::std::string::String,
+    pub idempotency_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -71,6 +73,58 @@ impl OpenBankAccountRequest {
     pub fn take_name(&mut self) -> ::std::string::String {
         ::std::mem::replace(&mut self.name, ::std::string::String::new())
     }
+
+    // string bank_account_id = 2;
+
+
+    pub fn get_bank_account_id(&self) -> &str {
+        &self.bank_account_id
+    }
+    pub fn clear_bank_account_id(&mut self) {
+        self.bank_account_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bank_account_id(&mut self, v: ::std::string::String) {
+        self.bank_account_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bank_account_id(&mut self) -> &mut ::std::string::String {
+        &mut self.bank_account_id
+    }
+
+    // Take field
+    pub fn take_bank_account_id(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bank_account_id, ::std::string::String::new())
+    }
+
+    // string idempotency_key = 3;
+
+
+    pub fn get_idempotency_key(&self) -> &str {
+        &self.idempotency_key
+    }
+    pub fn clear_idempotency_key(&mut self) {
+        self.idempotency_key.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idempotency_key(&mut self, v: ::std::string::String) {
+        self.idempotency_key = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_idempotency_key(&mut self) -> &mut ::std::string::String {
+        &mut self.idempotency_key
+    }
+
+    // Take field
+    pub fn take_idempotency_key(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.idempotency_key, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for OpenBankAccountRequest {
@@ -85,6 +139,12 @@ impl ::protobuf::Message for OpenBankAccountRequest {
                 1 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
                 },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bank_account_id)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.idempotency_key)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -100,6 +160,12 @@ impl ::protobuf::Message for OpenBankAccountRequest {
         if !self.name.is_empty() {
             my_size += ::protobuf::rt::string_size(1, &self.name);
         }
+        if !self.bank_account_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.bank_account_id);
+        }
+        if !self.idempotency_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.idempotency_key);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -109,6 +175,12 @@ impl ::protobuf::Message for OpenBankAccountRequest {
         if !self.name.is_empty() {
             os.write_string(1, &self.name)?;
         }
+        if !self.bank_account_id.is_empty() {
+            os.write_string(2, &self.bank_account_id)?;
+        }
+        if !self.idempotency_key.is_empty() {
+            os.write_string(3, &self.idempotency_key)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -156,6 +228,16 @@ impl ::protobuf::Message for OpenBankAccountRequest {
                     |m: &OpenBankAccountRequest| { &m.name },
                     |m: &mut OpenBankAccountRequest| { &mut m.name },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "bank_account_id",
+                    |m: &OpenBankAccountRequest| { &m.bank_account_id },
+                    |m: &mut OpenBankAccountRequest| { &mut m.bank_account_id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "idempotency_key",
+                    |m: &OpenBankAccountRequest| { &m.idempotency_key },
+                    |m: &mut OpenBankAccountRequest| { &mut m.idempotency_key },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<OpenBankAccountRequest>(
                     "OpenBankAccountRequest",
                     fields,
@@ -179,6 +261,8 @@ impl ::protobuf::Message for OpenBankAccountRequest {
 impl ::protobuf::Clear for OpenBankAccountRequest {
     fn clear(&mut self) {
         self.name.clear();
+        self.bank_account_id.clear();
+        self.idempotency_key.clear();
         self.unknown_fields.clear();
     }
 }
@@ -369,6 +453,7 @@ pub struct UpdateBankAccountRequest {
     // message fields
     pub bank_account_id: ::std::string::String,
     pub name: ::std::string::String,
+    pub idempotency_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -436,6 +521,32 @@ impl UpdateBankAccountRequest {
     pub fn take_name(&mut self) -> ::std::string::String {
         ::std::mem::replace(&mut self.name, ::std::string::String::new())
     }
+
+    // string idempotency_key = 3;
+
+
+    pub fn get_idempotency_key(&self) -> &str {
+        &self.idempotency_key
+    }
+    pub fn clear_idempotency_key(&mut self) {
+        self.idempotency_key.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idempotency_key(&mut self, v: ::std::string::String) {
+        self.idempotency_key = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_idempotency_key(&mut self) -> &mut ::std::string::String {
+        &mut self.idempotency_key
+    }
+
+    // Take field
+    pub fn take_idempotency_key(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.idempotency_key, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for UpdateBankAccountRequest {
@@ -453,6 +564,9 @@ impl ::protobuf::Message for UpdateBankAccountRequest {
                 2 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
                 },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.idempotency_key)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -471,6 +585,9 @@ impl ::protobuf::Message for UpdateBankAccountRequest {
         if !self.name.is_empty() {
             my_size += ::protobuf::rt::string_size(2, &self.name);
         }
+        if !self.idempotency_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.idempotency_key);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -483,6 +600,9 @@ impl ::protobuf::Message for UpdateBankAccountRequest {
         if !self.name.is_empty() {
             os.write_string(2, &self.name)?;
         }
+        if !self.idempotency_key.is_empty() {
+            os.write_string(3, &self.idempotency_key)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -535,6 +655,11 @@ impl ::protobuf::Message for UpdateBankAccountRequest {
                     |m: &UpdateBankAccountRequest| { &m.name },
                     |m: &mut UpdateBankAccountRequest| { &mut m.name },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "idempotency_key",
+                    |m: &UpdateBankAccountRequest| { &m.idempotency_key },
+                    |m: &mut UpdateBankAccountRequest| { &mut m.idempotency_key },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<UpdateBankAccountRequest>(
                     "UpdateBankAccountRequest",
                     fields,
@@ -559,6 +684,7 @@ impl ::protobuf::Clear for UpdateBankAccountRequest {
     fn clear(&mut self) {
         self.bank_account_id.clear();
         self.name.clear();
+        self.idempotency_key.clear();
         self.unknown_fields.clear();
     }
 }
@@ -706,6 +832,7 @@ pub struct DepositBankAccountRequest {
     // message fields
     pub bank_account_id: ::std::string::String,
     pub deposit: i32,
+    pub idempotency_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -762,6 +889,32 @@ impl DepositBankAccountRequest {
     pub fn set_deposit(&mut self, v: i32) {
         self.deposit = v;
     }
+
+    // string idempotency_key = 3;
+
+
+    pub fn get_idempotency_key(&self) -> &str {
+        &self.idempotency_key
+    }
+    pub fn clear_idempotency_key(&mut self) {
+        self.idempotency_key.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idempotency_key(&mut self, v: ::std::string::String) {
+        self.idempotency_key = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_idempotency_key(&mut self) -> &mut ::std::string::String {
+        &mut self.idempotency_key
+    }
+
+    // Take field
+    pub fn take_idempotency_key(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.idempotency_key, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for DepositBankAccountRequest {
@@ -783,6 +936,9 @@ impl ::protobuf::Message for DepositBankAccountRequest {
                     let tmp = is.read_int32()?;
                     self.deposit = tmp;
                 },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.idempotency_key)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -801,6 +957,9 @@ impl ::protobuf::Message for DepositBankAccountRequest {
         if self.deposit != 0 {
             my_size += ::protobuf::rt::value_size(2, self.deposit, ::protobuf::wire_format::WireTypeVarint);
         }
+        if !self.idempotency_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.idempotency_key);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -813,6 +972,9 @@ impl ::protobuf::Message for DepositBankAccountRequest {
         if self.deposit != 0 {
             os.write_int32(2, self.deposit)?;
         }
+        if !self.idempotency_key.is_empty() {
+            os.write_string(3, &self.idempotency_key)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -865,6 +1027,11 @@ impl ::protobuf::Message for DepositBankAccountRequest {
                     |m: &DepositBankAccountRequest| { &m.deposit },
                     |m: &mut DepositBankAccountRequest| { &mut m.deposit },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "idempotency_key",
+                    |m: &DepositBankAccountRequest| { &m.idempotency_key },
+                    |m: &mut DepositBankAccountRequest| { &mut m.idempotency_key },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<DepositBankAccountRequest>(
                     "DepositBankAccountRequest",
                     fields,
@@ -889,6 +1056,7 @@ impl ::protobuf::Clear for DepositBankAccountRequest {
     fn clear(&mut self) {
         self.bank_account_id.clear();
         self.deposit = 0;
+        self.idempotency_key.clear();
         self.unknown_fields.clear();
     }
 }
@@ -1036,6 +1204,7 @@ pub struct WithdrawBankAccountRequest {
     // message fields
     pub bank_account_id: ::std::string::String,
     pub withdraw: i32,
+    pub idempotency_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -1092,6 +1261,32 @@ impl WithdrawBankAccountRequest {
     pub fn set_withdraw(&mut self, v: i32) {
         self.withdraw = v;
     }
+
+    // string idempotency_key = 3;
+
+
+    pub fn get_idempotency_key(&self) -> &str {
+        &self.idempotency_key
+    }
+    pub fn clear_idempotency_key(&mut self) {
+        self.idempotency_key.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idempotency_key(&mut self, v: ::std::string::String) {
+        self.idempotency_key = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_idempotency_key(&mut self) -> &mut ::std::string::String {
+        &mut self.idempotency_key
+    }
+
+    // Take field
+    pub fn take_idempotency_key(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.idempotency_key, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for WithdrawBankAccountRequest {
@@ -1113,6 +1308,9 @@ impl ::protobuf::Message for WithdrawBankAccountRequest {
                     let tmp = is.read_int32()?;
                     self.withdraw = tmp;
                 },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.idempotency_key)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1131,6 +1329,9 @@ impl ::protobuf::Message for WithdrawBankAccountRequest {
         if self.withdraw != 0 {
             my_size += ::protobuf::rt::value_size(2, self.withdraw, ::protobuf::wire_format::WireTypeVarint);
         }
+        if !self.idempotency_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.idempotency_key);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1143,6 +1344,9 @@ impl ::protobuf::Message for WithdrawBankAccountRequest {
         if self.withdraw != 0 {
             os.write_int32(2, self.withdraw)?;
         }
+        if !self.idempotency_key.is_empty() {
+            os.write_string(3, &self.idempotency_key)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1195,6 +1399,11 @@ impl ::protobuf::Message for WithdrawBankAccountRequest {
                     |m: &WithdrawBankAccountRequest| { &m.withdraw },
                     |m: &mut WithdrawBankAccountRequest| { &mut m.withdraw },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "idempotency_key",
+                    |m: &WithdrawBankAccountRequest| { &m.idempotency_key },
+                    |m: &mut WithdrawBankAccountRequest| { &mut m.idempotency_key },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<WithdrawBankAccountRequest>(
                     "WithdrawBankAccountRequest",
                     fields,
@@ -1219,6 +1428,7 @@ impl ::protobuf::Clear for WithdrawBankAccountRequest {
     fn clear(&mut self) {
         self.bank_account_id.clear();
         self.withdraw = 0;
+        self.idempotency_key.clear();
         self.unknown_fields.clear();
     }
 }
@@ -1365,6 +1575,7 @@ impl ::protobuf::reflect::ProtobufValue for WithdrawBankAccountResponse {
 pub struct CloseBankAccountRequest {
     // message fields
     pub bank_account_id: ::std::string::String,
+    pub idempotency_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -1406,6 +1617,32 @@ impl CloseBankAccountRequest {
     pub fn take_bank_account_id(&mut self) -> ::std::string::String {
         ::std::mem::replace(&mut self.bank_account_id, ::std::string::String::new())
     }
+
+    // string idempotency_key = 2;
+
+
+    pub fn get_idempotency_key(&self) -> &str {
+        &self.idempotency_key
+    }
+    pub fn clear_idempotency_key(&mut self) {
+        self.idempotency_key.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idempotency_key(&mut self, v: ::std::string::String) {
+        self.idempotency_key = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_idempotency_key(&mut self) -> &mut ::std::string::String {
+        &mut self.idempotency_key
+    }
+
+    // Take field
+    pub fn take_idempotency_key(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.idempotency_key, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for CloseBankAccountRequest {
@@ -1420,6 +1657,9 @@ impl ::protobuf::Message for CloseBankAccountRequest {
                 1 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bank_account_id)?;
                 },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.idempotency_key)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1435,6 +1675,9 @@ impl ::protobuf::Message for CloseBankAccountRequest {
         if !self.bank_account_id.is_empty() {
             my_size += ::protobuf::rt::string_size(1, &self.bank_account_id);
         }
+        if !self.idempotency_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.idempotency_key);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1444,6 +1687,9 @@ impl ::protobuf::Message for CloseBankAccountRequest {
         if !self.bank_account_id.is_empty() {
             os.write_string(1, &self.bank_account_id)?;
         }
+        if !self.idempotency_key.is_empty() {
+            os.write_string(2, &self.idempotency_key)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1491,6 +1737,11 @@ impl ::protobuf::Message for CloseBankAccountRequest {
                     |m: &CloseBankAccountRequest| { &m.bank_account_id },
                     |m: &mut CloseBankAccountRequest| { &mut m.bank_account_id },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "idempotency_key",
+                    |m: &CloseBankAccountRequest| { &m.idempotency_key },
+                    |m: &mut CloseBankAccountRequest| { &mut m.idempotency_key },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<CloseBankAccountRequest>(
                     "CloseBankAccountRequest",
                     fields,
@@ -1514,6 +1765,7 @@ impl ::protobuf::Message for CloseBankAccountRequest {
 impl ::protobuf::Clear for CloseBankAccountRequest {
     fn clear(&mut self) {
         self.bank_account_id.clear();
+        self.idempotency_key.clear();
         self.unknown_fields.clear();
     }
 }
@@ -1656,26 +1908,2342 @@ impl ::protobuf::reflect::ProtobufValue for CloseBankAccountResponse {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct SubscribeAccountEventsRequest {
+    // message fields
+    pub bank_account_id: ::std::string::String,
+    pub from_version: u64,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a SubscribeAccountEventsRequest {
+    fn default() -> &'a SubscribeAccountEventsRequest {
+        <SubscribeAccountEventsRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SubscribeAccountEventsRequest {
+    pub fn new() -> SubscribeAccountEventsRequest {
+        ::std::default::Default::default()
+    }
+
+    // string bank_account_id = 1;
+
+
+    pub fn get_bank_account_id(&self) -> &str {
+        &self.bank_account_id
+    }
+    pub fn clear_bank_account_id(&mut self) {
+        self.bank_account_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bank_account_id(&mut self, v: ::std::string::String) {
+        self.bank_account_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bank_account_id(&mut self) -> &mut ::std::string::String {
+        &mut self.bank_account_id
+    }
+
+    // Take field
+    pub fn take_bank_account_id(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bank_account_id, ::std::string::String::new())
+    }
+
+    // uint64 from_version = 2;
+
+
+    pub fn get_from_version(&self) -> u64 {
+        self.from_version
+    }
+    pub fn clear_from_version(&mut self) {
+        self.from_version = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_from_version(&mut self, v: u64) {
+        self.from_version = v;
+    }
+}
+
+impl ::protobuf::Message for SubscribeAccountEventsRequest {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bank_account_id)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.from_version = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.bank_account_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.bank_account_id);
+        }
+        if self.from_version != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.from_version, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.bank_account_id.is_empty() {
+            os.write_string(1, &self.bank_account_id)?;
+        }
+        if self.from_version != 0 {
+            os.write_uint64(2, self.from_version)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> SubscribeAccountEventsRequest {
+        SubscribeAccountEventsRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "bank_account_id",
+                    |m: &SubscribeAccountEventsRequest| { &m.bank_account_id },
+                    |m: &mut SubscribeAccountEventsRequest| { &mut m.bank_account_id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "from_version",
+                    |m: &SubscribeAccountEventsRequest| { &m.from_version },
+                    |m: &mut SubscribeAccountEventsRequest| { &mut m.from_version },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<SubscribeAccountEventsRequest>(
+                    "SubscribeAccountEventsRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static SubscribeAccountEventsRequest {
+        static mut instance: ::protobuf::lazy::Lazy<SubscribeAccountEventsRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const SubscribeAccountEventsRequest,
+        };
+        unsafe {
+            instance.get(SubscribeAccountEventsRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for SubscribeAccountEventsRequest {
+    fn clear(&mut self) {
+        self.bank_account_id.clear();
+        self.from_version = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for SubscribeAccountEventsRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SubscribeAccountEventsRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct BankAccountOpened {
+    // message fields
+    pub name: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a BankAccountOpened {
+    fn default() -> &'a BankAccountOpened {
+        <BankAccountOpened as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl BankAccountOpened {
+    pub fn new() -> BankAccountOpened {
+        ::std::default::Default::default()
+    }
+
+    // string name = 1;
+
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn clear_name(&mut self) {
+        self.name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_name(&mut self, v: ::std::string::String) {
+        self.name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_name(&mut self) -> &mut ::std::string::String {
+        &mut self.name
+    }
+
+    // Take field
+    pub fn take_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.name, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for BankAccountOpened {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> BankAccountOpened {
+        BankAccountOpened::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "name",
+                    |m: &BankAccountOpened| { &m.name },
+                    |m: &mut BankAccountOpened| { &mut m.name },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<BankAccountOpened>(
+                    "BankAccountOpened",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static BankAccountOpened {
+        static mut instance: ::protobuf::lazy::Lazy<BankAccountOpened> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BankAccountOpened,
+        };
+        unsafe {
+            instance.get(BankAccountOpened::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for BankAccountOpened {
+    fn clear(&mut self) {
+        self.name.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BankAccountOpened {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BankAccountOpened {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct BankAccountUpdated {
+    // message fields
+    pub name: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a BankAccountUpdated {
+    fn default() -> &'a BankAccountUpdated {
+        <BankAccountUpdated as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl BankAccountUpdated {
+    pub fn new() -> BankAccountUpdated {
+        ::std::default::Default::default()
+    }
+
+    // string name = 1;
+
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn clear_name(&mut self) {
+        self.name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_name(&mut self, v: ::std::string::String) {
+        self.name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_name(&mut self) -> &mut ::std::string::String {
+        &mut self.name
+    }
+
+    // Take field
+    pub fn take_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.name, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for BankAccountUpdated {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> BankAccountUpdated {
+        BankAccountUpdated::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "name",
+                    |m: &BankAccountUpdated| { &m.name },
+                    |m: &mut BankAccountUpdated| { &mut m.name },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<BankAccountUpdated>(
+                    "BankAccountUpdated",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static BankAccountUpdated {
+        static mut instance: ::protobuf::lazy::Lazy<BankAccountUpdated> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BankAccountUpdated,
+        };
+        unsafe {
+            instance.get(BankAccountUpdated::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for BankAccountUpdated {
+    fn clear(&mut self) {
+        self.name.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BankAccountUpdated {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BankAccountUpdated {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct BankAccountDeposited {
+    // message fields
+    pub deposit: i32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a BankAccountDeposited {
+    fn default() -> &'a BankAccountDeposited {
+        <BankAccountDeposited as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl BankAccountDeposited {
+    pub fn new() -> BankAccountDeposited {
+        ::std::default::Default::default()
+    }
+
+    // int32 deposit = 1;
+
+
+    pub fn get_deposit(&self) -> i32 {
+        self.deposit
+    }
+    pub fn clear_deposit(&mut self) {
+        self.deposit = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_deposit(&mut self, v: i32) {
+        self.deposit = v;
+    }
+}
+
+impl ::protobuf::Message for BankAccountDeposited {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.deposit = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.deposit != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.deposit, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.deposit != 0 {
+            os.write_int32(1, self.deposit)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> BankAccountDeposited {
+        BankAccountDeposited::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                    "deposit",
+                    |m: &BankAccountDeposited| { &m.deposit },
+                    |m: &mut BankAccountDeposited| { &mut m.deposit },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<BankAccountDeposited>(
+                    "BankAccountDeposited",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static BankAccountDeposited {
+        static mut instance: ::protobuf::lazy::Lazy<BankAccountDeposited> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BankAccountDeposited,
+        };
+        unsafe {
+            instance.get(BankAccountDeposited::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for BankAccountDeposited {
+    fn clear(&mut self) {
+        self.deposit = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BankAccountDeposited {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BankAccountDeposited {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct BankAccountWithdrawn {
+    // message fields
+    pub withdraw: i32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a BankAccountWithdrawn {
+    fn default() -> &'a BankAccountWithdrawn {
+        <BankAccountWithdrawn as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl BankAccountWithdrawn {
+    pub fn new() -> BankAccountWithdrawn {
+        ::std::default::Default::default()
+    }
+
+    // int32 withdraw = 1;
+
+
+    pub fn get_withdraw(&self) -> i32 {
+        self.withdraw
+    }
+    pub fn clear_withdraw(&mut self) {
+        self.withdraw = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_withdraw(&mut self, v: i32) {
+        self.withdraw = v;
+    }
+}
+
+impl ::protobuf::Message for BankAccountWithdrawn {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.withdraw = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.withdraw != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.withdraw, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.withdraw != 0 {
+            os.write_int32(1, self.withdraw)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> BankAccountWithdrawn {
+        BankAccountWithdrawn::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                    "withdraw",
+                    |m: &BankAccountWithdrawn| { &m.withdraw },
+                    |m: &mut BankAccountWithdrawn| { &mut m.withdraw },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<BankAccountWithdrawn>(
+                    "BankAccountWithdrawn",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static BankAccountWithdrawn {
+        static mut instance: ::protobuf::lazy::Lazy<BankAccountWithdrawn> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BankAccountWithdrawn,
+        };
+        unsafe {
+            instance.get(BankAccountWithdrawn::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for BankAccountWithdrawn {
+    fn clear(&mut self) {
+        self.withdraw = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BankAccountWithdrawn {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BankAccountWithdrawn {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct BankAccountClosed {
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a BankAccountClosed {
+    fn default() -> &'a BankAccountClosed {
+        <BankAccountClosed as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl BankAccountClosed {
+    pub fn new() -> BankAccountClosed {
+        ::std::default::Default::default()
+    }
+}
+
+impl ::protobuf::Message for BankAccountClosed {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> BankAccountClosed {
+        BankAccountClosed::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let fields = ::std::vec::Vec::new();
+                ::protobuf::reflect::MessageDescriptor::new::<BankAccountClosed>(
+                    "BankAccountClosed",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static BankAccountClosed {
+        static mut instance: ::protobuf::lazy::Lazy<BankAccountClosed> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BankAccountClosed,
+        };
+        unsafe {
+            instance.get(BankAccountClosed::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for BankAccountClosed {
+    fn clear(&mut self) {
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BankAccountClosed {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BankAccountClosed {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct AccountEvent {
+    // message fields
+    pub bank_account_id: ::std::string::String,
+    pub stream_version: u64,
+    pub occurred_at: ::std::string::String,
+    // message oneof groups
+    pub event: ::std::option::Option<AccountEvent_oneof_event>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a AccountEvent {
+    fn default() -> &'a AccountEvent {
+        <AccountEvent as ::protobuf::Message>::default_instance()
+    }
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub enum AccountEvent_oneof_event {
+    opened(BankAccountOpened),
+    updated(BankAccountUpdated),
+    deposited(BankAccountDeposited),
+    withdrawn(BankAccountWithdrawn),
+    closed(BankAccountClosed),
+}
+
+impl AccountEvent {
+    pub fn new() -> AccountEvent {
+        ::std::default::Default::default()
+    }
+
+    // string bank_account_id = 1;
+
+
+    pub fn get_bank_account_id(&self) -> &str {
+        &self.bank_account_id
+    }
+    pub fn clear_bank_account_id(&mut self) {
+        self.bank_account_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bank_account_id(&mut self, v: ::std::string::String) {
+        self.bank_account_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bank_account_id(&mut self) -> &mut ::std::string::String {
+        &mut self.bank_account_id
+    }
+
+    // Take field
+    pub fn take_bank_account_id(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bank_account_id, ::std::string::String::new())
+    }
+
+    // uint64 stream_version = 2;
+
+
+    pub fn get_stream_version(&self) -> u64 {
+        self.stream_version
+    }
+    pub fn clear_stream_version(&mut self) {
+        self.stream_version = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_stream_version(&mut self, v: u64) {
+        self.stream_version = v;
+    }
+
+    // string occurred_at = 3;
+
+
+    pub fn get_occurred_at(&self) -> &str {
+        &self.occurred_at
+    }
+    pub fn clear_occurred_at(&mut self) {
+        self.occurred_at.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_occurred_at(&mut self, v: ::std::string::String) {
+        self.occurred_at = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_occurred_at(&mut self) -> &mut ::std::string::String {
+        &mut self.occurred_at
+    }
+
+    // Take field
+    pub fn take_occurred_at(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.occurred_at, ::std::string::String::new())
+    }
+
+    // .BankAccountOpened opened = 4;
+
+
+    pub fn get_opened(&self) -> &BankAccountOpened {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::opened(ref v)) => v,
+            _ => <BankAccountOpened as ::protobuf::Message>::default_instance(),
+        }
+    }
+    pub fn clear_opened(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_opened(&self) -> bool {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::opened(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_opened(&mut self, v: BankAccountOpened) {
+        self.event = ::std::option::Option::Some(AccountEvent_oneof_event::opened(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_opened(&mut self) -> &mut BankAccountOpened {
+        if let ::std::option::Option::Some(AccountEvent_oneof_event::opened(_)) = self.event {
+        } else {
+            self.event = ::std::option::Option::Some(AccountEvent_oneof_event::opened(BankAccountOpened::new()));
+        }
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::opened(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_opened(&mut self) -> BankAccountOpened {
+        if self.has_opened() {
+            match self.event.take() {
+                ::std::option::Option::Some(AccountEvent_oneof_event::opened(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            BankAccountOpened::new()
+        }
+    }
+
+    // .BankAccountUpdated updated = 5;
+
+
+    pub fn get_updated(&self) -> &BankAccountUpdated {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::updated(ref v)) => v,
+            _ => <BankAccountUpdated as ::protobuf::Message>::default_instance(),
+        }
+    }
+    pub fn clear_updated(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_updated(&self) -> bool {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::updated(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_updated(&mut self, v: BankAccountUpdated) {
+        self.event = ::std::option::Option::Some(AccountEvent_oneof_event::updated(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_updated(&mut self) -> &mut BankAccountUpdated {
+        if let ::std::option::Option::Some(AccountEvent_oneof_event::updated(_)) = self.event {
+        } else {
+            self.event = ::std::option::Option::Some(AccountEvent_oneof_event::updated(BankAccountUpdated::new()));
+        }
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::updated(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_updated(&mut self) -> BankAccountUpdated {
+        if self.has_updated() {
+            match self.event.take() {
+                ::std::option::Option::Some(AccountEvent_oneof_event::updated(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            BankAccountUpdated::new()
+        }
+    }
+
+    // .BankAccountDeposited deposited = 6;
+
+
+    pub fn get_deposited(&self) -> &BankAccountDeposited {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::deposited(ref v)) => v,
+            _ => <BankAccountDeposited as ::protobuf::Message>::default_instance(),
+        }
+    }
+    pub fn clear_deposited(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_deposited(&self) -> bool {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::deposited(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_deposited(&mut self, v: BankAccountDeposited) {
+        self.event = ::std::option::Option::Some(AccountEvent_oneof_event::deposited(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_deposited(&mut self) -> &mut BankAccountDeposited {
+        if let ::std::option::Option::Some(AccountEvent_oneof_event::deposited(_)) = self.event {
+        } else {
+            self.event = ::std::option::Option::Some(AccountEvent_oneof_event::deposited(BankAccountDeposited::new()));
+        }
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::deposited(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_deposited(&mut self) -> BankAccountDeposited {
+        if self.has_deposited() {
+            match self.event.take() {
+                ::std::option::Option::Some(AccountEvent_oneof_event::deposited(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            BankAccountDeposited::new()
+        }
+    }
+
+    // .BankAccountWithdrawn withdrawn = 7;
+
+
+    pub fn get_withdrawn(&self) -> &BankAccountWithdrawn {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(ref v)) => v,
+            _ => <BankAccountWithdrawn as ::protobuf::Message>::default_instance(),
+        }
+    }
+    pub fn clear_withdrawn(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_withdrawn(&self) -> bool {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_withdrawn(&mut self, v: BankAccountWithdrawn) {
+        self.event = ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_withdrawn(&mut self) -> &mut BankAccountWithdrawn {
+        if let ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(_)) = self.event {
+        } else {
+            self.event = ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(BankAccountWithdrawn::new()));
+        }
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_withdrawn(&mut self) -> BankAccountWithdrawn {
+        if self.has_withdrawn() {
+            match self.event.take() {
+                ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            BankAccountWithdrawn::new()
+        }
+    }
+
+    // .BankAccountClosed closed = 8;
+
+
+    pub fn get_closed(&self) -> &BankAccountClosed {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::closed(ref v)) => v,
+            _ => <BankAccountClosed as ::protobuf::Message>::default_instance(),
+        }
+    }
+    pub fn clear_closed(&mut self) {
+        self.event = ::std::option::Option::None;
+    }
+
+    pub fn has_closed(&self) -> bool {
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::closed(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_closed(&mut self, v: BankAccountClosed) {
+        self.event = ::std::option::Option::Some(AccountEvent_oneof_event::closed(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_closed(&mut self) -> &mut BankAccountClosed {
+        if let ::std::option::Option::Some(AccountEvent_oneof_event::closed(_)) = self.event {
+        } else {
+            self.event = ::std::option::Option::Some(AccountEvent_oneof_event::closed(BankAccountClosed::new()));
+        }
+        match self.event {
+            ::std::option::Option::Some(AccountEvent_oneof_event::closed(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_closed(&mut self) -> BankAccountClosed {
+        if self.has_closed() {
+            match self.event.take() {
+                ::std::option::Option::Some(AccountEvent_oneof_event::closed(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            BankAccountClosed::new()
+        }
+    }
+}
+
+impl ::protobuf::Message for AccountEvent {
+    fn is_initialized(&self) -> bool {
+        if let Some(AccountEvent_oneof_event::opened(ref v)) = self.event {
+            if !v.is_initialized() {
+                return false;
+            }
+        }
+        if let Some(AccountEvent_oneof_event::updated(ref v)) = self.event {
+            if !v.is_initialized() {
+                return false;
+            }
+        }
+        if let Some(AccountEvent_oneof_event::deposited(ref v)) = self.event {
+            if !v.is_initialized() {
+                return false;
+            }
+        }
+        if let Some(AccountEvent_oneof_event::withdrawn(ref v)) = self.event {
+            if !v.is_initialized() {
+                return false;
+            }
+        }
+        if let Some(AccountEvent_oneof_event::closed(ref v)) = self.event {
+            if !v.is_initialized() {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bank_account_id)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.stream_version = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.occurred_at)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.event = ::std::option::Option::Some(AccountEvent_oneof_event::opened(is.read_message()?));
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.event = ::std::option::Option::Some(AccountEvent_oneof_event::updated(is.read_message()?));
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.event = ::std::option::Option::Some(AccountEvent_oneof_event::deposited(is.read_message()?));
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.event = ::std::option::Option::Some(AccountEvent_oneof_event::withdrawn(is.read_message()?));
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.event = ::std::option::Option::Some(AccountEvent_oneof_event::closed(is.read_message()?));
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.bank_account_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.bank_account_id);
+        }
+        if self.stream_version != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.stream_version, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.occurred_at.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.occurred_at);
+        }
+        if let ::std::option::Option::Some(ref v) = self.event {
+            match v {
+                &AccountEvent_oneof_event::opened(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+                },
+                &AccountEvent_oneof_event::updated(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+                },
+                &AccountEvent_oneof_event::deposited(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+                },
+                &AccountEvent_oneof_event::withdrawn(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+                },
+                &AccountEvent_oneof_event::closed(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.bank_account_id.is_empty() {
+            os.write_string(1, &self.bank_account_id)?;
+        }
+        if self.stream_version != 0 {
+            os.write_uint64(2, self.stream_version)?;
+        }
+        if !self.occurred_at.is_empty() {
+            os.write_string(3, &self.occurred_at)?;
+        }
+        if let ::std::option::Option::Some(ref v) = self.event {
+            match v {
+                &AccountEvent_oneof_event::opened(ref v) => {
+                    os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+                    os.write_raw_varint32(v.get_cached_size())?;
+                    v.write_to_with_cached_sizes(os)?;
+                },
+                &AccountEvent_oneof_event::updated(ref v) => {
+                    os.write_tag(5, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+                    os.write_raw_varint32(v.get_cached_size())?;
+                    v.write_to_with_cached_sizes(os)?;
+                },
+                &AccountEvent_oneof_event::deposited(ref v) => {
+                    os.write_tag(6, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+                    os.write_raw_varint32(v.get_cached_size())?;
+                    v.write_to_with_cached_sizes(os)?;
+                },
+                &AccountEvent_oneof_event::withdrawn(ref v) => {
+                    os.write_tag(7, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+                    os.write_raw_varint32(v.get_cached_size())?;
+                    v.write_to_with_cached_sizes(os)?;
+                },
+                &AccountEvent_oneof_event::closed(ref v) => {
+                    os.write_tag(8, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+                    os.write_raw_varint32(v.get_cached_size())?;
+                    v.write_to_with_cached_sizes(os)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> AccountEvent {
+        AccountEvent::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "bank_account_id",
+                    |m: &AccountEvent| { &m.bank_account_id },
+                    |m: &mut AccountEvent| { &mut m.bank_account_id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "stream_version",
+                    |m: &AccountEvent| { &m.stream_version },
+                    |m: &mut AccountEvent| { &mut m.stream_version },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "occurred_at",
+                    |m: &AccountEvent| { &m.occurred_at },
+                    |m: &mut AccountEvent| { &mut m.occurred_at },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_message_accessor::<_, BankAccountOpened>(
+                    "opened",
+                    AccountEvent::has_opened,
+                    AccountEvent::get_opened,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_message_accessor::<_, BankAccountUpdated>(
+                    "updated",
+                    AccountEvent::has_updated,
+                    AccountEvent::get_updated,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_message_accessor::<_, BankAccountDeposited>(
+                    "deposited",
+                    AccountEvent::has_deposited,
+                    AccountEvent::get_deposited,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_message_accessor::<_, BankAccountWithdrawn>(
+                    "withdrawn",
+                    AccountEvent::has_withdrawn,
+                    AccountEvent::get_withdrawn,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_message_accessor::<_, BankAccountClosed>(
+                    "closed",
+                    AccountEvent::has_closed,
+                    AccountEvent::get_closed,
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<AccountEvent>(
+                    "AccountEvent",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static AccountEvent {
+        static mut instance: ::protobuf::lazy::Lazy<AccountEvent> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const AccountEvent,
+        };
+        unsafe {
+            instance.get(AccountEvent::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for AccountEvent {
+    fn clear(&mut self) {
+        self.bank_account_id.clear();
+        self.stream_version = 0;
+        self.occurred_at.clear();
+        self.event = ::std::option::Option::None;
+        self.event = ::std::option::Option::None;
+        self.event = ::std::option::Option::None;
+        self.event = ::std::option::Option::None;
+        self.event = ::std::option::Option::None;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for AccountEvent {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for AccountEvent {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct ErrorDetail {
+    // message fields
+    pub reason: ErrorReason,
+    pub message: ::std::string::String,
+    pub bank_account_id: ::std::string::String,
+    pub amount: i32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ErrorDetail {
+    fn default() -> &'a ErrorDetail {
+        <ErrorDetail as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ErrorDetail {
+    pub fn new() -> ErrorDetail {
+        ::std::default::Default::default()
+    }
+
+    // .ErrorReason reason = 1;
+
+
+    pub fn get_reason(&self) -> ErrorReason {
+        self.reason
+    }
+    pub fn clear_reason(&mut self) {
+        self.reason = ErrorReason::UNKNOWN;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reason(&mut self, v: ErrorReason) {
+        self.reason = v;
+    }
+
+    // string message = 2;
+
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+    pub fn clear_message(&mut self) {
+        self.message.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_message(&mut self, v: ::std::string::String) {
+        self.message = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_message(&mut self) -> &mut ::std::string::String {
+        &mut self.message
+    }
+
+    // Take field
+    pub fn take_message(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.message, ::std::string::String::new())
+    }
+
+    // string bank_account_id = 3;
+
+
+    pub fn get_bank_account_id(&self) -> &str {
+        &self.bank_account_id
+    }
+    pub fn clear_bank_account_id(&mut self) {
+        self.bank_account_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bank_account_id(&mut self, v: ::std::string::String) {
+        self.bank_account_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bank_account_id(&mut self) -> &mut ::std::string::String {
+        &mut self.bank_account_id
+    }
+
+    // Take field
+    pub fn take_bank_account_id(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bank_account_id, ::std::string::String::new())
+    }
+
+    // int32 amount = 4;
+
+
+    pub fn get_amount(&self) -> i32 {
+        self.amount
+    }
+    pub fn clear_amount(&mut self) {
+        self.amount = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_amount(&mut self, v: i32) {
+        self.amount = v;
+    }
+}
+
+impl ::protobuf::Message for ErrorDetail {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_proto3_enum_with_unknown_fields_into(wire_type, is, &mut self.reason, 1, &mut self.unknown_fields)?
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.message)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bank_account_id)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.amount = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.reason != ErrorReason::UNKNOWN {
+            my_size += ::protobuf::rt::enum_size(1, self.reason);
+        }
+        if !self.message.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.message);
+        }
+        if !self.bank_account_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.bank_account_id);
+        }
+        if self.amount != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.amount, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.reason != ErrorReason::UNKNOWN {
+            os.write_enum(1, self.reason.value())?;
+        }
+        if !self.message.is_empty() {
+            os.write_string(2, &self.message)?;
+        }
+        if !self.bank_account_id.is_empty() {
+            os.write_string(3, &self.bank_account_id)?;
+        }
+        if self.amount != 0 {
+            os.write_int32(4, self.amount)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ErrorDetail {
+        ErrorDetail::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeEnum<ErrorReason>>(
+                    "reason",
+                    |m: &ErrorDetail| { &m.reason },
+                    |m: &mut ErrorDetail| { &mut m.reason },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "message",
+                    |m: &ErrorDetail| { &m.message },
+                    |m: &mut ErrorDetail| { &mut m.message },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "bank_account_id",
+                    |m: &ErrorDetail| { &m.bank_account_id },
+                    |m: &mut ErrorDetail| { &mut m.bank_account_id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                    "amount",
+                    |m: &ErrorDetail| { &m.amount },
+                    |m: &mut ErrorDetail| { &mut m.amount },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<ErrorDetail>(
+                    "ErrorDetail",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static ErrorDetail {
+        static mut instance: ::protobuf::lazy::Lazy<ErrorDetail> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ErrorDetail,
+        };
+        unsafe {
+            instance.get(ErrorDetail::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for ErrorDetail {
+    fn clear(&mut self) {
+        self.reason = ErrorReason::UNKNOWN;
+        self.message.clear();
+        self.bank_account_id.clear();
+        self.amount = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ErrorDetail {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ErrorDetail {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct Status {
+    // message fields
+    pub code: i32,
+    pub message: ::std::string::String,
+    pub details: ::protobuf::RepeatedField<::protobuf::well_known_types::Any>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a Status {
+    fn default() -> &'a Status {
+        <Status as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Status {
+    pub fn new() -> Status {
+        ::std::default::Default::default()
+    }
+
+    // int32 code = 1;
+
+
+    pub fn get_code(&self) -> i32 {
+        self.code
+    }
+    pub fn clear_code(&mut self) {
+        self.code = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_code(&mut self, v: i32) {
+        self.code = v;
+    }
+
+    // string message = 2;
+
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+    pub fn clear_message(&mut self) {
+        self.message.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_message(&mut self, v: ::std::string::String) {
+        self.message = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_message(&mut self) -> &mut ::std::string::String {
+        &mut self.message
+    }
+
+    // Take field
+    pub fn take_message(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.message, ::std::string::String::new())
+    }
+
+    // repeated .google.protobuf.Any details = 3;
+
+
+    pub fn get_details(&self) -> &[::protobuf::well_known_types::Any] {
+        &self.details
+    }
+    pub fn clear_details(&mut self) {
+        self.details.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_details(&mut self, v: ::protobuf::RepeatedField<::protobuf::well_known_types::Any>) {
+        self.details = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_details(&mut self) -> &mut ::protobuf::RepeatedField<::protobuf::well_known_types::Any> {
+        &mut self.details
+    }
+
+    // Take field
+    pub fn take_details(&mut self) -> ::protobuf::RepeatedField<::protobuf::well_known_types::Any> {
+        ::std::mem::replace(&mut self.details, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for Status {
+    fn is_initialized(&self) -> bool {
+        for v in &self.details {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.code = tmp;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.message)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.details)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.code != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.code, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.message.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.message);
+        }
+        for value in &self.details {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.code != 0 {
+            os.write_int32(1, self.code)?;
+        }
+        if !self.message.is_empty() {
+            os.write_string(2, &self.message)?;
+        }
+        for v in &self.details {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> Status {
+        Status::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                    "code",
+                    |m: &Status| { &m.code },
+                    |m: &mut Status| { &mut m.code },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "message",
+                    |m: &Status| { &m.message },
+                    |m: &mut Status| { &mut m.message },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Any>>(
+                    "details",
+                    |m: &Status| { &m.details },
+                    |m: &mut Status| { &mut m.details },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<Status>(
+                    "Status",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static Status {
+        static mut instance: ::protobuf::lazy::Lazy<Status> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const Status,
+        };
+        unsafe {
+            instance.get(Status::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for Status {
+    fn clear(&mut self) {
+        self.code = 0;
+        self.message.clear();
+        self.details.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for Status {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Status {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum ErrorReason {
+    UNKNOWN = 0,
+    INVALID_BANK_ACCOUNT_ID = 1,
+    INVALID_BANK_ACCOUNT_NAME = 2,
+    BANK_ACCOUNT_NOT_FOUND = 3,
+    BANK_ACCOUNT_DELETED = 4,
+    ALREADY_OPENED = 5,
+    ALREADY_CLOSED = 6,
+    DEPOSIT_ZERO = 7,
+    NEGATIVE_DEPOSIT = 8,
+    NEGATIVE_WITHDRAW = 9,
+    INSUFFICIENT_FUNDS = 10,
+    BALANCE_OVERFLOW = 11,
+    INVALID_STATE = 12,
+    CONCURRENT_MODIFICATION = 13,
+    EVENT_STORE_UNAVAILABLE = 14,
+    EVENT_STORE_FAILURE = 15,
+    IDEMPOTENCY_KEY_REUSED = 16,
+    COMMAND_IN_PROGRESS = 17,
+    IDEMPOTENCY_STORE_UNAVAILABLE = 18,
+    EVENTS_NOT_PUBLISHED = 19,
+    ENCRYPTION_FAILURE = 20,
+}
+
+impl ::protobuf::ProtobufEnum for ErrorReason {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<ErrorReason> {
+        match value {
+            0 => ::std::option::Option::Some(ErrorReason::UNKNOWN),
+            1 => ::std::option::Option::Some(ErrorReason::INVALID_BANK_ACCOUNT_ID),
+            2 => ::std::option::Option::Some(ErrorReason::INVALID_BANK_ACCOUNT_NAME),
+            3 => ::std::option::Option::Some(ErrorReason::BANK_ACCOUNT_NOT_FOUND),
+            4 => ::std::option::Option::Some(ErrorReason::BANK_ACCOUNT_DELETED),
+            5 => ::std::option::Option::Some(ErrorReason::ALREADY_OPENED),
+            6 => ::std::option::Option::Some(ErrorReason::ALREADY_CLOSED),
+            7 => ::std::option::Option::Some(ErrorReason::DEPOSIT_ZERO),
+            8 => ::std::option::Option::Some(ErrorReason::NEGATIVE_DEPOSIT),
+            9 => ::std::option::Option::Some(ErrorReason::NEGATIVE_WITHDRAW),
+            10 => ::std::option::Option::Some(ErrorReason::INSUFFICIENT_FUNDS),
+            11 => ::std::option::Option::Some(ErrorReason::BALANCE_OVERFLOW),
+            12 => ::std::option::Option::Some(ErrorReason::INVALID_STATE),
+            13 => ::std::option::Option::Some(ErrorReason::CONCURRENT_MODIFICATION),
+            14 => ::std::option::Option::Some(ErrorReason::EVENT_STORE_UNAVAILABLE),
+            15 => ::std::option::Option::Some(ErrorReason::EVENT_STORE_FAILURE),
+            16 => ::std::option::Option::Some(ErrorReason::IDEMPOTENCY_KEY_REUSED),
+            17 => ::std::option::Option::Some(ErrorReason::COMMAND_IN_PROGRESS),
+            18 => ::std::option::Option::Some(ErrorReason::IDEMPOTENCY_STORE_UNAVAILABLE),
+            19 => ::std::option::Option::Some(ErrorReason::EVENTS_NOT_PUBLISHED),
+            20 => ::std::option::Option::Some(ErrorReason::ENCRYPTION_FAILURE),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [ErrorReason] = &[
+            ErrorReason::UNKNOWN,
+            ErrorReason::INVALID_BANK_ACCOUNT_ID,
+            ErrorReason::INVALID_BANK_ACCOUNT_NAME,
+            ErrorReason::BANK_ACCOUNT_NOT_FOUND,
+            ErrorReason::BANK_ACCOUNT_DELETED,
+            ErrorReason::ALREADY_OPENED,
+            ErrorReason::ALREADY_CLOSED,
+            ErrorReason::DEPOSIT_ZERO,
+            ErrorReason::NEGATIVE_DEPOSIT,
+            ErrorReason::NEGATIVE_WITHDRAW,
+            ErrorReason::INSUFFICIENT_FUNDS,
+            ErrorReason::BALANCE_OVERFLOW,
+            ErrorReason::INVALID_STATE,
+            ErrorReason::CONCURRENT_MODIFICATION,
+            ErrorReason::EVENT_STORE_UNAVAILABLE,
+            ErrorReason::EVENT_STORE_FAILURE,
+            ErrorReason::IDEMPOTENCY_KEY_REUSED,
+            ErrorReason::COMMAND_IN_PROGRESS,
+            ErrorReason::IDEMPOTENCY_STORE_UNAVAILABLE,
+            ErrorReason::EVENTS_NOT_PUBLISHED,
+            ErrorReason::ENCRYPTION_FAILURE,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::EnumDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                ::protobuf::reflect::EnumDescriptor::new("ErrorReason", file_descriptor_proto())
+            })
+        }
+    }
+}
+
+impl ::std::marker::Copy for ErrorReason {
+}
+
+impl ::std::default::Default for ErrorReason {
+    fn default() -> Self {
+        ErrorReason::UNKNOWN
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ErrorReason {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Enum(self.descriptor())
+    }
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\x12bank_account.proto\",\n\x16OpenBankAccountRequest\x12\x12\n\x04nam\
-    e\x18\x01\x20\x01(\tR\x04name\"A\n\x17OpenBankAccountResponse\x12&\n\x0f\
-    bank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\"V\n\x18UpdateBankAcc\
-    ountRequest\x12&\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\
-    \x12\x12\n\x04name\x18\x02\x20\x01(\tR\x04name\"\x1b\n\x19UpdateBankAcco\
-    untResponse\"]\n\x19DepositBankAccountRequest\x12&\n\x0fbank_account_id\
-    \x18\x01\x20\x01(\tR\rbankAccountId\x12\x18\n\x07deposit\x18\x02\x20\x01\
-    (\x05R\x07deposit\"\x1c\n\x1aDepositBankAccountResponse\"`\n\x1aWithdraw\
-    BankAccountRequest\x12&\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAc\
-    countId\x12\x1a\n\x08withdraw\x18\x02\x20\x01(\x05R\x08withdraw\"\x1d\n\
-    \x1bWithdrawBankAccountResponse\"A\n\x17CloseBankAccountRequest\x12&\n\
-    \x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\"\x1a\n\x18CloseB\
-    ankAccountResponse2\xd9\x02\n\x12BankAccountService\x129\n\x04open\x12\
-    \x17.OpenBankAccountRequest\x1a\x18.OpenBankAccountResponse\x12?\n\x06up\
-    date\x12\x19.UpdateBankAccountRequest\x1a\x1a.UpdateBankAccountResponse\
-    \x12B\n\x07deposit\x12\x1a.DepositBankAccountRequest\x1a\x1b.DepositBank\
-    AccountResponse\x12E\n\x08withdraw\x12\x1b.WithdrawBankAccountRequest\
-    \x1a\x1c.WithdrawBankAccountResponse\x12<\n\x05close\x12\x18.CloseBankAc\
-    countRequest\x1a\x19.CloseBankAccountResponseb\x06proto3\
+    \n\x12bank_account.proto\x1a\x19google/protobuf/any.proto\"}\n\x16OpenBa\
+    nkAccountRequest\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04name\x12&\n\
+    \x0fbank_account_id\x18\x02\x20\x01(\tR\rbankAccountId\x12'\n\x0fidempot\
+    ency_key\x18\x03\x20\x01(\tR\x0eidempotencyKey\"A\n\x17OpenBankAccountRe\
+    sponse\x12&\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\"\
+    \x7f\n\x18UpdateBankAccountRequest\x12&\n\x0fbank_account_id\x18\x01\x20\
+    \x01(\tR\rbankAccountId\x12\x12\n\x04name\x18\x02\x20\x01(\tR\x04name\
+    \x12'\n\x0fidempotency_key\x18\x03\x20\x01(\tR\x0eidempotencyKey\"\x1b\n\
+    \x19UpdateBankAccountResponse\"\x86\x01\n\x19DepositBankAccountRequest\
+    \x12&\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\x12\x18\n\
+    \x07deposit\x18\x02\x20\x01(\x05R\x07deposit\x12'\n\x0fidempotency_key\
+    \x18\x03\x20\x01(\tR\x0eidempotencyKey\"\x1c\n\x1aDepositBankAccountResp\
+    onse\"\x89\x01\n\x1aWithdrawBankAccountRequest\x12&\n\x0fbank_account_id\
+    \x18\x01\x20\x01(\tR\rbankAccountId\x12\x1a\n\x08withdraw\x18\x02\x20\
+    \x01(\x05R\x08withdraw\x12'\n\x0fidempotency_key\x18\x03\x20\x01(\tR\x0e\
+    idempotencyKey\"\x1d\n\x1bWithdrawBankAccountResponse\"j\n\x17CloseBankA\
+    ccountRequest\x12&\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccount\
+    Id\x12'\n\x0fidempotency_key\x18\x02\x20\x01(\tR\x0eidempotencyKey\"\x1a\
+    \n\x18CloseBankAccountResponse\"j\n\x1dSubscribeAccountEventsRequest\x12\
+    &\n\x0fbank_account_id\x18\x01\x20\x01(\tR\rbankAccountId\x12!\n\x0cfrom\
+    _version\x18\x02\x20\x01(\x04R\x0bfromVersion\"'\n\x11BankAccountOpened\
+    \x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04name\"(\n\x12BankAccountUpdate\
+    d\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04name\"0\n\x14BankAccountDepos\
+    ited\x12\x18\n\x07deposit\x18\x01\x20\x01(\x05R\x07deposit\"2\n\x14BankA\
+    ccountWithdrawn\x12\x1a\n\x08withdraw\x18\x01\x20\x01(\x05R\x08withdraw\
+    \"\x13\n\x11BankAccountClosed\"\x82\x03\n\x0cAccountEvent\x12&\n\x0fbank\
+    _account_id\x18\x01\x20\x01(\tR\rbankAccountId\x12%\n\x0estream_version\
+    \x18\x02\x20\x01(\x04R\rstreamVersion\x12\x1f\n\x0boccurred_at\x18\x03\
+    \x20\x01(\tR\noccurredAt\x12,\n\x06opened\x18\x04\x20\x01(\x0b2\x12.Bank\
+    AccountOpenedH\0R\x06opened\x12/\n\x07updated\x18\x05\x20\x01(\x0b2\x13.\
+    BankAccountUpdatedH\0R\x07updated\x125\n\tdeposited\x18\x06\x20\x01(\x0b\
+    2\x15.BankAccountDepositedH\0R\tdeposited\x125\n\twithdrawn\x18\x07\x20\
+    \x01(\x0b2\x15.BankAccountWithdrawnH\0R\twithdrawn\x12,\n\x06closed\x18\
+    \x08\x20\x01(\x0b2\x12.BankAccountClosedH\0R\x06closedB\x07\n\x05event\"\
+    \x8d\x01\n\x0bErrorDetail\x12$\n\x06reason\x18\x01\x20\x01(\x0e2\x0c.Err\
+    orReasonR\x06reason\x12\x18\n\x07message\x18\x02\x20\x01(\tR\x07message\
+    \x12&\n\x0fbank_account_id\x18\x03\x20\x01(\tR\rbankAccountId\x12\x16\n\
+    \x06amount\x18\x04\x20\x01(\x05R\x06amount\"f\n\x06Status\x12\x12\n\x04c\
+    ode\x18\x01\x20\x01(\x05R\x04code\x12\x18\n\x07message\x18\x02\x20\x01(\
+    \tR\x07message\x12.\n\x07details\x18\x03\x20\x03(\x0b2\x14.google.protob\
+    uf.AnyR\x07details*\x91\x04\n\x0bErrorReason\x12\x0b\n\x07UNKNOWN\x10\0\
+    \x12\x1b\n\x17INVALID_BANK_ACCOUNT_ID\x10\x01\x12\x1d\n\x19INVALID_BANK_\
+    ACCOUNT_NAME\x10\x02\x12\x1a\n\x16BANK_ACCOUNT_NOT_FOUND\x10\x03\x12\x18\
+    \n\x14BANK_ACCOUNT_DELETED\x10\x04\x12\x12\n\x0eALREADY_OPENED\x10\x05\
+    \x12\x12\n\x0eALREADY_CLOSED\x10\x06\x12\x10\n\x0cDEPOSIT_ZERO\x10\x07\
+    \x12\x14\n\x10NEGATIVE_DEPOSIT\x10\x08\x12\x15\n\x11NEGATIVE_WITHDRAW\
+    \x10\t\x12\x16\n\x12INSUFFICIENT_FUNDS\x10\n\x12\x14\n\x10BALANCE_OVERFL\
+    OW\x10\x0b\x12\x11\n\rINVALID_STATE\x10\x0c\x12\x1b\n\x17CONCURRENT_MODI\
+    FICATION\x10\r\x12\x1b\n\x17EVENT_STORE_UNAVAILABLE\x10\x0e\x12\x17\n\
+    \x13EVENT_STORE_FAILURE\x10\x0f\x12\x1a\n\x16IDEMPOTENCY_KEY_REUSED\x10\
+    \x10\x12\x17\n\x13COMMAND_IN_PROGRESS\x10\x11\x12!\n\x1dIDEMPOTENCY_STOR\
+    E_UNAVAILABLE\x10\x12\x12\x18\n\x14EVENTS_NOT_PUBLISHED\x10\x13\x12\x16\
+    \n\x12ENCRYPTION_FAILURE\x10\x142\xa6\x03\n\x12BankAccountService\x129\n\
+    \x04open\x12\x17.OpenBankAccountRequest\x1a\x18.OpenBankAccountResponse\
+    \x12?\n\x06update\x12\x19.UpdateBankAccountRequest\x1a\x1a.UpdateBankAcc\
+    ountResponse\x12B\n\x07deposit\x12\x1a.DepositBankAccountRequest\x1a\x1b\
+    .DepositBankAccountResponse\x12E\n\x08withdraw\x12\x1b.WithdrawBankAccou\
+    ntRequest\x1a\x1c.WithdrawBankAccountResponse\x12<\n\x05close\x12\x18.Cl\
+    oseBankAccountRequest\x1a\x19.CloseBankAccountResponse\x12K\n\x18subscri\
+    be_account_events\x12\x1e.SubscribeAccountEventsRequest\x1a\r.AccountEve\
+    nt0\x01b\x06proto3\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
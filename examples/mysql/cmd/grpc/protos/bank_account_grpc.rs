@@ -53,9 +53,16 @@ const METHOD_BANK_ACCOUNT_SERVICE_CLOSE: ::grpcio::Method<super::bank_account::C
     resp_mar: ::grpcio::Marshaller { ser: ::grpcio::pb_ser, de: ::grpcio::pb_de },
 };
 
+const METHOD_BANK_ACCOUNT_SERVICE_SUBSCRIBE_ACCOUNT_EVENTS: ::grpcio::Method<super::bank_account::SubscribeAccountEventsRequest, super::bank_account::AccountEvent> = ::grpcio::Method {
+    ty: ::grpcio::MethodType::ServerStreaming,
+    name: "/BankAccountService/subscribe_account_events",
+    req_mar: ::grpcio::Marshaller { ser: ::grpcio::pb_ser, de: ::grpcio::pb_de },
+    resp_mar: ::grpcio::Marshaller { ser: ::grpcio::pb_ser, de: ::grpcio::pb_de },
+};
+
 #[derive(Clone)]
 pub struct BankAccountServiceClient {
-    client: ::grpcio::Client,
+    pub client: ::grpcio::Client,
 }
 
 impl BankAccountServiceClient {
@@ -144,17 +151,38 @@ impl BankAccountServiceClient {
     pub fn close_async(&self, req: &super::bank_account::CloseBankAccountRequest) -> ::grpcio::Result<::grpcio::ClientUnaryReceiver<super::bank_account::CloseBankAccountResponse>> {
         self.close_async_opt(req, ::grpcio::CallOption::default())
     }
-    pub fn spawn<F>(&self, f: F) where F: ::futures::Future<Item = (), Error = ()> + Send + 'static {
+
+    pub fn subscribe_account_events_opt(&self, req: &super::bank_account::SubscribeAccountEventsRequest, opt: ::grpcio::CallOption) -> ::grpcio::Result<::grpcio::ClientSStreamReceiver<super::bank_account::AccountEvent>> {
+        self.client.server_streaming(&METHOD_BANK_ACCOUNT_SERVICE_SUBSCRIBE_ACCOUNT_EVENTS, req, opt)
+    }
+
+    pub fn subscribe_account_events(&self, req: &super::bank_account::SubscribeAccountEventsRequest) -> ::grpcio::Result<::grpcio::ClientSStreamReceiver<super::bank_account::AccountEvent>> {
+        self.subscribe_account_events_opt(req, ::grpcio::CallOption::default())
+    }
+    pub fn spawn<F>(&self, f: F) where F: ::std::future::Future<Output = ()> + Send + 'static {
         self.client.spawn(f)
     }
 }
 
 pub trait BankAccountService {
-    fn open(&mut self, ctx: ::grpcio::RpcContext, req: super::bank_account::OpenBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::OpenBankAccountResponse>);
-    fn update(&mut self, ctx: ::grpcio::RpcContext, req: super::bank_account::UpdateBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::UpdateBankAccountResponse>);
-    fn deposit(&mut self, ctx: ::grpcio::RpcContext, req: super::bank_account::DepositBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::DepositBankAccountResponse>);
-    fn withdraw(&mut self, ctx: ::grpcio::RpcContext, req: super::bank_account::WithdrawBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::WithdrawBankAccountResponse>);
-    fn close(&mut self, ctx: ::grpcio::RpcContext, req: super::bank_account::CloseBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::CloseBankAccountResponse>);
+    fn open(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::OpenBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::OpenBankAccountResponse>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
+    fn update(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::UpdateBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::UpdateBankAccountResponse>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
+    fn deposit(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::DepositBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::DepositBankAccountResponse>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
+    fn withdraw(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::WithdrawBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::WithdrawBankAccountResponse>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
+    fn close(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::CloseBankAccountRequest, sink: ::grpcio::UnarySink<super::bank_account::CloseBankAccountResponse>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
+    fn subscribe_account_events(&mut self, ctx: ::grpcio::RpcContext, _req: super::bank_account::SubscribeAccountEventsRequest, sink: ::grpcio::ServerStreamingSink<super::bank_account::AccountEvent>) {
+        grpcio::unimplemented_call!(ctx, sink)
+    }
 }
 
 pub fn create_bank_account_service<S: BankAccountService + Send + Clone + 'static>(s: S) -> ::grpcio::Service {
@@ -175,9 +203,13 @@ pub fn create_bank_account_service<S: BankAccountService + Send + Clone + 'stati
     builder = builder.add_unary_handler(&METHOD_BANK_ACCOUNT_SERVICE_WITHDRAW, move |ctx, req, resp| {
         instance.withdraw(ctx, req, resp)
     });
-    let mut instance = s;
+    let mut instance = s.clone();
     builder = builder.add_unary_handler(&METHOD_BANK_ACCOUNT_SERVICE_CLOSE, move |ctx, req, resp| {
         instance.close(ctx, req, resp)
     });
+    let mut instance = s;
+    builder = builder.add_server_streaming_handler(&METHOD_BANK_ACCOUNT_SERVICE_SUBSCRIBE_ACCOUNT_EVENTS, move |ctx, req, resp| {
+        instance.subscribe_account_events(ctx, req, resp)
+    });
     builder.build()
 }
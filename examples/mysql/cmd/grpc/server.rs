@@ -1,19 +1,26 @@
 mod protos;
+mod status;
 
 use std::sync::Arc;
+use chrono::Duration;
 use log::{error, info, debug};
-use futures::Future;
+use futures::{Future, SinkExt, TryFutureExt};
+use tokio::runtime::{Runtime, Handle};
 use chan::chan_select;
 use chan_signal::{kill_this, Signal};
 use structopt::StructOpt;
+use tracing::{Instrument, Span, info_span};
 
 use grpcio::{
     RpcContext,
     UnarySink,
+    ServerStreamingSink,
     RpcStatus,
     RpcStatusCode,
     EnvBuilder,
     ServerBuilder,
+    WriteFlags,
+    Error as GrpcError,
 };
 
 use protos::bank_account::{
@@ -27,46 +34,91 @@ use protos::bank_account::{
     WithdrawBankAccountResponse,
     CloseBankAccountRequest,
     CloseBankAccountResponse,
+    SubscribeAccountEventsRequest,
+    AccountEvent,
+    BankAccountOpened,
+    BankAccountUpdated,
+    BankAccountDeposited,
+    BankAccountWithdrawn,
+    BankAccountClosed,
 };
 
 use protos::bank_account_grpc::{BankAccountService, create_bank_account_service};
 
-use rust_cqrses_bankaccount::aggregate::{BankAccountId, BankAccountName};
-use rust_cqrses_bankaccount::usecase::command::BankAccountAggregateUseCase;
+use status::{invalid_argument_status, usecase_error_status};
+
+use rust_cqrses_bankaccount::aggregate::{
+    BankAccountAggregate,
+    BankAccountCommand,
+    BankAccountEvent,
+    BankAccountId,
+    BankAccountName,
+    Error as BankAccountError,
+};
+use rust_cqrses_bankaccount::usecase::command::{AsyncBankAccountAggregateUseCase, Error as UseCaseError};
+use rust_cqrses_bankaccount::blocking::{BlockingEventStore, BlockingIdempotencyStore};
+use rust_cqrses_bankaccount::publishing_eventstore::PublishingEventStore;
+use rust_cqrses_bankaccount::encrypting_eventstore::EncryptingEventStore;
+use rust_cqrses_bankaccount::instrumented_eventstore::InstrumentedEventStore;
+use rust_cqrses_bankaccount::crypto::PiiCipher;
+use rust_cqrses_bankaccount::subscription::{StreamNotifier, StreamWatcher};
 
 use rust_cqrses_bankaccount_mysql_example::Config;
 use rust_cqrses_bankaccount_mysql_example::constants;
 use rust_cqrses_bankaccount_mysql_example::db;
 use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
 use rust_cqrses_bankaccount_mysql_example::eventpublisher::KafkaBankAccountEventPublisher;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+use rust_cqrses_bankaccount_mysql_example::idempotency::MysqlIdempotencyStore;
+use rust_cqrses_bankaccount_mysql_example::monitoring::install_metrics_exporter;
+use rust_cqrses_bankaccount_mysql_example::telemetry::{TraceContext, continue_trace, init_telemetry};
 
 fn main() {
     dotenv::dotenv().ok();
 
-    env_logger::init();
-
     let config = envy::from_env::<Config>().unwrap();
 
+    let _telemetry = init_telemetry("grpc_server", config.trace_exporter);
+
     let args = Args::from_args();
 
+    install_metrics_exporter(&args.metrics_addr);
+
     let pool = db::init_database_pool(&config.database_url);
 
-    let eventpublisher = KafkaBankAccountEventPublisher::new(config.kafka_brokers.clone(), String::from(constants::TOPIC));
+    let eventpublisher = KafkaBankAccountEventPublisher::new(config.kafka_brokers.clone(), String::from(constants::TOPIC))
+        .expect("kafka producer build error occurred");
+
+    let cipher = PiiCipher::new(create_key_store(&config, pool.clone()));
 
-    let eventstore = Box::new(MysqlBankAccountEventStore::new(pool, eventpublisher));
+    let idempotency_store = Box::new(BlockingIdempotencyStore::new(MysqlIdempotencyStore::new(pool.clone())));
+    let idempotency_ttl = Duration::seconds(config.idempotency_key_ttl_secs);
 
-    let usecase = Arc::new(BankAccountAggregateUseCase::new(eventstore));
+    let notifier = Arc::new(StreamNotifier::new(1024));
+
+    let eventstore = Box::new(BlockingEventStore::new(EncryptingEventStore::new(
+            PublishingEventStore::new(
+                PublishingEventStore::new(
+                    InstrumentedEventStore::new(MysqlBankAccountEventStore::new(pool), "mysql"), notifier.clone()),
+                eventpublisher)
+            .with_policy(config.publish_failure_policy),
+            cipher)));
+
+    let usecase = Arc::new(AsyncBankAccountAggregateUseCase::new(eventstore)
+                           .with_idempotency_store(idempotency_store, idempotency_ttl));
+
+    let runtime = Runtime::new().expect("fail build runtime");
 
     let env = Arc::new(EnvBuilder::new().build());
 
     let mut sv = ServerBuilder::new(env)
-        .register_service(create_bank_account_service(Server::new(usecase)))
+        .register_service(create_bank_account_service(Server::new(usecase, notifier, runtime.handle().clone())))
         .bind(args.host, args.port)
         .build()
         .expect("fail build server");
     sv.start();
 
-    for &(ref host, port) in sv.bind_addrs() {
+    for (host, port) in sv.bind_addrs() {
         info!("listening on {}:{}", host, port);
     }
 
@@ -79,7 +131,7 @@ fn main() {
         }
     }
 
-    let _ = sv.shutdown().wait();
+    let _ = futures::executor::block_on(sv.shutdown());
 }
 
 #[derive(StructOpt, Debug)]
@@ -90,166 +142,233 @@ pub struct Args {
 
     #[structopt(long, default_value="8080")]
     pub port: u16,
+
+    #[structopt(long, default_value="127.0.0.1:9100")]
+    pub metrics_addr: String,
 }
 
 #[derive(Clone)]
 pub struct Server {
-    usecase: Arc<BankAccountAggregateUseCase>,
+    usecase: Arc<AsyncBankAccountAggregateUseCase>,
+    notifier: Arc<StreamNotifier<BankAccountEvent>>,
+    runtime: Handle,
 }
 
 impl Server {
-    pub fn new(usecase: Arc<BankAccountAggregateUseCase>) -> Self {
+    pub fn new(usecase: Arc<AsyncBankAccountAggregateUseCase>, notifier: Arc<StreamNotifier<BankAccountEvent>>,
+               runtime: Handle) -> Self {
         Self {
             usecase: usecase,
+            notifier: notifier,
+            runtime: runtime,
         }
     }
 }
 
-impl BankAccountService for Server {
-    fn open(&mut self, ctx: RpcContext, req: OpenBankAccountRequest, sink: UnarySink<OpenBankAccountResponse>) {
-        let bank_account_id = BankAccountId::new(uuid::Uuid::new_v4().to_hyphenated().to_string()).unwrap();
-        let name = match BankAccountName::new(String::from(req.get_name())) {
-            Ok(n) => n,
-            Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
-                return;
-            },
-        };
-
-        let usecase = self.usecase.clone();
-
-        let f = match usecase.open(bank_account_id.clone(), name) {
-            Ok(_) => {
-                let mut resp = OpenBankAccountResponse::new();
-                resp.set_bank_account_id(bank_account_id.value().to_string());
-                sink.success(resp)
-            },
-            Err(err) => {
-                error!("An error occurred when open bank account: {:?}", err);
-                sink.fail(RpcStatus::new(RpcStatusCode::Internal, None))
-            },
-        };
+fn idempotency_key(key: &str) -> Option<String> {
+    Some(String::from(key)).filter(|key| !key.is_empty())
+}
 
-        ctx.spawn(f.map_err(|err| error!("failed to fail response {:?}", err)));
+fn account_event(stream_version: u64, event: BankAccountEvent) -> AccountEvent {
+    let mut message = AccountEvent::new();
+    message.set_bank_account_id(event.bank_account_id().to_string());
+    message.set_stream_version(stream_version);
+    message.set_occurred_at(event.occurred_at().to_rfc3339());
+    match event {
+        BankAccountEvent::Opened{ name, .. } => {
+            let mut opened = BankAccountOpened::new();
+            opened.set_name(name.to_string());
+            message.set_opened(opened);
+        },
+        BankAccountEvent::Updated{ name, .. } => {
+            let mut updated = BankAccountUpdated::new();
+            updated.set_name(name.to_string());
+            message.set_updated(updated);
+        },
+        BankAccountEvent::Deposited{ deposit, .. } => {
+            let mut deposited = BankAccountDeposited::new();
+            deposited.set_deposit(deposit);
+            message.set_deposited(deposited);
+        },
+        BankAccountEvent::Withdrawn{ withdraw, .. } => {
+            let mut withdrawn = BankAccountWithdrawn::new();
+            withdrawn.set_withdraw(withdraw);
+            message.set_withdrawn(withdrawn);
+        },
+        BankAccountEvent::Closed{ .. } => message.set_closed(BankAccountClosed::new()),
     }
+    message
+}
 
-    fn update(&mut self, ctx: RpcContext, req: UpdateBankAccountRequest, sink: UnarySink<UpdateBankAccountResponse>) {
-        let bank_account_id = match BankAccountId::new(String::from(req.get_bank_account_id())) {
-            Ok(id) => id,
-            Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
-                return;
-            },
-        };
-
-        let name = match BankAccountName::new(String::from(req.get_name())) {
-            Ok(n) => n,
-            Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
-                return;
-            },
-        };
-
-        let usecase = self.usecase.clone();
+fn request_span(ctx: &RpcContext, span: Span) -> Span {
+    let carrier: TraceContext = ctx.request_headers().iter()
+        .filter_map(|(key, value)| {
+            std::str::from_utf8(value).ok().map(|value| (key.to_string(), value.to_string()))
+        })
+        .collect();
+    continue_trace(&span, &carrier);
+    span
+}
 
-        let f = match usecase.update(bank_account_id.clone(), name) {
-            Ok(_) => {
-                sink.success(UpdateBankAccountResponse::new())
-            },
-            Err(err) => {
-                error!("An error occurred when update bank account: {:?}", err);
-                sink.fail(RpcStatus::new(RpcStatusCode::Internal, None))
-            },
-        };
+fn spawn_response<F>(ctx: &RpcContext, f: F) where F: Future<Output = Result<(), GrpcError>> + Send + 'static {
+    ctx.spawn(f.unwrap_or_else(|err| error!("failed to send response {:?}", err)));
+}
 
-        ctx.spawn(f.map_err(|err| error!("failed to fail response {:?}", err)));
+fn logged_error_status(err: &UseCaseError, bank_account_id: &BankAccountId) -> RpcStatus {
+    let status = usecase_error_status(err, bank_account_id);
+    if status.code() == RpcStatusCode::INTERNAL || status.code() == RpcStatusCode::UNAVAILABLE {
+        error!("An error occurred when handling bank account request: {:?}", err);
     }
+    status
+}
 
-    fn deposit(&mut self, ctx: RpcContext, req: DepositBankAccountRequest, sink: UnarySink<DepositBankAccountResponse>) {
-        let bank_account_id = match BankAccountId::new(String::from(req.get_bank_account_id())) {
-            Ok(id) => id,
+impl Server {
+    fn handle<T, S, R>(&self, ctx: RpcContext, sink: UnarySink<T>, command: Result<BankAccountCommand, BankAccountError>,
+                       idempotency_key: Option<String>, span: S, response: R)
+        where T: Send + 'static,
+              S: FnOnce(&BankAccountId) -> Span,
+              R: FnOnce(&BankAccountId) -> T + Send + 'static {
+        let command = match command {
+            Ok(command) => command,
             Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
+                spawn_response(&ctx, sink.fail(invalid_argument_status(&err)));
                 return;
             },
         };
 
-        let deposit = req.get_deposit();
-
         let usecase = self.usecase.clone();
+        let bank_account_id = command.bank_account_id().clone();
+        let span = request_span(&ctx, span(&bank_account_id));
+
+        self.runtime.spawn(async move {
+            let result = match usecase.handle(command, idempotency_key.as_deref()).await {
+                Ok(_) => sink.success(response(&bank_account_id)).await,
+                Err(err) => sink.fail(logged_error_status(&err, &bank_account_id)).await,
+            };
+            if let Err(err) = result {
+                error!("failed to send response {:?}", err);
+            }
+        }.instrument(span));
+    }
+}
 
-        let f = match usecase.deposit(bank_account_id.clone(), deposit) {
-            Ok(_) => {
-                sink.success(DepositBankAccountResponse::new())
-            },
-            Err(err) => {
-                error!("An error occurred when update bank account: {:?}", err);
-                sink.fail(RpcStatus::new(RpcStatusCode::Internal, None))
-            },
+async fn stream_account_events(usecase: Arc<AsyncBankAccountAggregateUseCase>, mut watcher: StreamWatcher,
+                               bank_account_id: BankAccountId, mut next_version: u64,
+                               mut sink: ServerStreamingSink<AccountEvent>) -> Result<(), GrpcError> {
+    let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+    loop {
+        let events = match usecase.events_since(bank_account_id.clone(), next_version).await {
+            Ok(events) => events,
+            Err(err) => return sink.fail(logged_error_status(&err, &bank_account_id)).await,
         };
 
-        ctx.spawn(f.map_err(|err| error!("failed to fail response {:?}", err)));
+        for (stream_version, event) in events {
+            let closed = match event {
+                BankAccountEvent::Closed{ .. } => true,
+                _ => false,
+            };
+            sink.send((account_event(stream_version, event), WriteFlags::default())).await?;
+            next_version = stream_version + 1;
+            if closed {
+                return sink.close().await;
+            }
+        }
+
+        if !watcher.changed(&stream_id).await {
+            return sink.close().await;
+        }
     }
+}
 
-    fn withdraw(&mut self, ctx: RpcContext, req: WithdrawBankAccountRequest, sink: UnarySink<WithdrawBankAccountResponse>) {
-        let bank_account_id = match BankAccountId::new(String::from(req.get_bank_account_id())) {
-            Ok(id) => id,
-            Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
-                return;
-            },
+impl BankAccountService for Server {
+    fn open(&mut self, ctx: RpcContext, req: OpenBankAccountRequest, sink: UnarySink<OpenBankAccountResponse>) {
+        let idempotency_key = idempotency_key(req.get_idempotency_key());
+        let bank_account_id = match (req.get_bank_account_id(), &idempotency_key) {
+            ("", None) => Ok(BankAccountId::new(uuid::Uuid::new_v4().to_hyphenated().to_string()).unwrap()),
+            (value, _) => BankAccountId::new(String::from(value)),
         };
+        let command = bank_account_id.and_then(|bank_account_id| Ok(BankAccountCommand::Open {
+            bank_account_id: bank_account_id,
+            name: BankAccountName::new(String::from(req.get_name()))?,
+        }));
+
+        self.handle(ctx, sink, command, idempotency_key,
+                    |id| info_span!("Server::open", bank_account_id = %id),
+                    |id| {
+                        let mut resp = OpenBankAccountResponse::new();
+                        resp.set_bank_account_id(id.value().to_string());
+                        resp
+                    });
+    }
 
-        let withdraw = req.get_withdraw();
-
-        let usecase = self.usecase.clone();
+    fn update(&mut self, ctx: RpcContext, req: UpdateBankAccountRequest, sink: UnarySink<UpdateBankAccountResponse>) {
+        let command = BankAccountId::new(String::from(req.get_bank_account_id()))
+            .and_then(|bank_account_id| Ok(BankAccountCommand::Update {
+                bank_account_id: bank_account_id,
+                name: BankAccountName::new(String::from(req.get_name()))?,
+            }));
+
+        self.handle(ctx, sink, command, idempotency_key(req.get_idempotency_key()),
+                    |id| info_span!("Server::update", bank_account_id = %id),
+                    |_| UpdateBankAccountResponse::new());
+    }
 
-        let f = match usecase.withdraw(bank_account_id.clone(), withdraw) {
-            Ok(_) => {
-                sink.success(WithdrawBankAccountResponse::new())
-            },
-            Err(err) => {
-                error!("An error occurred when update bank account: {:?}", err);
-                sink.fail(RpcStatus::new(RpcStatusCode::Internal, None))
-            },
-        };
+    fn deposit(&mut self, ctx: RpcContext, req: DepositBankAccountRequest, sink: UnarySink<DepositBankAccountResponse>) {
+        let command = BankAccountId::new(String::from(req.get_bank_account_id()))
+            .map(|bank_account_id| BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id,
+                deposit: req.get_deposit(),
+            });
+
+        self.handle(ctx, sink, command, idempotency_key(req.get_idempotency_key()),
+                    |id| info_span!("Server::deposit", bank_account_id = %id),
+                    |_| DepositBankAccountResponse::new());
+    }
 
-        ctx.spawn(f.map_err(|err| error!("failed to fail response {:?}", err)));
+    fn withdraw(&mut self, ctx: RpcContext, req: WithdrawBankAccountRequest, sink: UnarySink<WithdrawBankAccountResponse>) {
+        let command = BankAccountId::new(String::from(req.get_bank_account_id()))
+            .map(|bank_account_id| BankAccountCommand::Withdraw {
+                bank_account_id: bank_account_id,
+                withdraw: req.get_withdraw(),
+            });
+
+        self.handle(ctx, sink, command, idempotency_key(req.get_idempotency_key()),
+                    |id| info_span!("Server::withdraw", bank_account_id = %id),
+                    |_| WithdrawBankAccountResponse::new());
     }
 
     fn close(&mut self, ctx: RpcContext, req: CloseBankAccountRequest, sink: UnarySink<CloseBankAccountResponse>) {
+        let command = BankAccountId::new(String::from(req.get_bank_account_id()))
+            .map(|bank_account_id| BankAccountCommand::Close {
+                bank_account_id: bank_account_id,
+            });
+
+        self.handle(ctx, sink, command, idempotency_key(req.get_idempotency_key()),
+                    |id| info_span!("Server::close", bank_account_id = %id),
+                    |_| CloseBankAccountResponse::new());
+    }
+
+    fn subscribe_account_events(&mut self, ctx: RpcContext, req: SubscribeAccountEventsRequest,
+                                sink: ServerStreamingSink<AccountEvent>) {
         let bank_account_id = match BankAccountId::new(String::from(req.get_bank_account_id())) {
             Ok(id) => id,
             Err(err) => {
-                let f = sink.fail(RpcStatus::new(RpcStatusCode::InvalidArgument, Some(err.to_string())))
-                    .map_err(|err| error!("failed to fail response {:?}", err));
-                ctx.spawn(f);
+                spawn_response(&ctx, sink.fail(invalid_argument_status(&err)));
                 return;
             },
         };
 
         let usecase = self.usecase.clone();
+        let watcher = self.notifier.watch();
+        let next_version = std::cmp::max(req.get_from_version(), 1);
 
-        let f = match usecase.close(bank_account_id.clone()) {
-            Ok(_) => {
-                sink.success(CloseBankAccountResponse::new())
-            },
-            Err(err) => {
-                error!("An error occurred when update bank account: {:?}", err);
-                sink.fail(RpcStatus::new(RpcStatusCode::Internal, None))
-            },
-        };
+        let span = request_span(&ctx, info_span!("Server::subscribe_account_events",
+                                                 bank_account_id = %bank_account_id));
 
-        ctx.spawn(f.map_err(|err| error!("failed to fail response {:?}", err)));
+        self.runtime.spawn(async move {
+            if let Err(err) = stream_account_events(usecase, watcher, bank_account_id, next_version, sink).await {
+                error!("failed to stream response {:?}", err);
+            }
+        }.instrument(span));
     }
 }
@@ -0,0 +1,126 @@
+use failure::Fail;
+use protobuf::Message;
+use protobuf::well_known_types::Any;
+use grpcio::{RpcStatus, RpcStatusCode};
+
+use rust_cqrses_bankaccount::aggregate::{
+    BankAccountId,
+    Error as BankAccountError,
+    ErrorKind as BankAccountErrorKind,
+};
+use rust_cqrses_bankaccount::eventsourcing::{EventStoreError, EventStoreErrorKind};
+use rust_cqrses_bankaccount::usecase::command::{Error as UseCaseError, ErrorKind as UseCaseErrorKind};
+
+use super::protos::bank_account::{ErrorDetail, ErrorReason, Status};
+
+const ERROR_DETAIL_TYPE_URL: &str = "type.googleapis.com/ErrorDetail";
+
+fn bank_account_reason(kind: &BankAccountErrorKind) -> (RpcStatusCode, ErrorReason, i32) {
+    match kind {
+        BankAccountErrorKind::InvalidBankAccountId(_) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::INVALID_BANK_ACCOUNT_ID, 0),
+        BankAccountErrorKind::InvalidBankAccountName(_) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::INVALID_BANK_ACCOUNT_NAME, 0),
+        BankAccountErrorKind::NotYetOpened =>
+            (RpcStatusCode::NOT_FOUND, ErrorReason::BANK_ACCOUNT_NOT_FOUND, 0),
+        BankAccountErrorKind::AlreadyOpened(_) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::ALREADY_OPENED, 0),
+        BankAccountErrorKind::AlreadyClosed(_) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::ALREADY_CLOSED, 0),
+        BankAccountErrorKind::DepositZero(_, amount) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::DEPOSIT_ZERO, *amount),
+        BankAccountErrorKind::NegativeDeposit(_, amount) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::NEGATIVE_DEPOSIT, *amount),
+        BankAccountErrorKind::NegativeWithdraw(_, amount) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::NEGATIVE_WITHDRAW, *amount),
+        BankAccountErrorKind::NegativeBalance(_, amount) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::INSUFFICIENT_FUNDS, *amount),
+        BankAccountErrorKind::BalanceOverflow(_, amount) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::BALANCE_OVERFLOW, *amount),
+        BankAccountErrorKind::InvalidState(_) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::INVALID_STATE, 0),
+    }
+}
+
+fn event_store_reason(kind: &EventStoreErrorKind) -> (RpcStatusCode, ErrorReason) {
+    match kind {
+        EventStoreErrorKind::DuplicateEntryError(_) =>
+            (RpcStatusCode::ABORTED, ErrorReason::CONCURRENT_MODIFICATION),
+        EventStoreErrorKind::StreamDeletedError(_) =>
+            (RpcStatusCode::NOT_FOUND, ErrorReason::BANK_ACCOUNT_DELETED),
+        EventStoreErrorKind::QueryError(_)
+            | EventStoreErrorKind::AppendEventStreamError(_)
+            | EventStoreErrorKind::BlockingTaskError(_) =>
+            (RpcStatusCode::UNAVAILABLE, ErrorReason::EVENT_STORE_UNAVAILABLE),
+        EventStoreErrorKind::PublishEventStreamError(_) =>
+            (RpcStatusCode::INTERNAL, ErrorReason::EVENTS_NOT_PUBLISHED),
+        EventStoreErrorKind::EncryptionError(_) =>
+            (RpcStatusCode::FAILED_PRECONDITION, ErrorReason::ENCRYPTION_FAILURE),
+        _ => (RpcStatusCode::INTERNAL, ErrorReason::EVENT_STORE_FAILURE),
+    }
+}
+
+fn rpc_status(code: RpcStatusCode, reason: ErrorReason, message: String, bank_account_id: String, amount: i32)
+    -> RpcStatus {
+    let mut detail = ErrorDetail::new();
+    detail.set_reason(reason);
+    detail.set_message(message.clone());
+    detail.set_bank_account_id(bank_account_id);
+    detail.set_amount(amount);
+
+    let details = detail.write_to_bytes().and_then(|bytes| {
+        let mut any = Any::new();
+        any.set_type_url(String::from(ERROR_DETAIL_TYPE_URL));
+        any.set_value(bytes);
+
+        let mut status = Status::new();
+        status.set_code(code.into());
+        status.set_message(message.clone());
+        status.mut_details().push(any);
+        status.write_to_bytes()
+    });
+    match details {
+        Ok(details) => RpcStatus::with_details(code, message, details),
+        Err(_) => RpcStatus::with_message(code, message),
+    }
+}
+
+pub fn invalid_argument_status(err: &BankAccountError) -> RpcStatus {
+    let (code, reason, amount) = bank_account_reason(err.kind());
+    let bank_account_id = match err.kind() {
+        BankAccountErrorKind::InvalidBankAccountId(value) => value.clone(),
+        _ => String::new(),
+    };
+    rpc_status(code, reason, err.to_string(), bank_account_id, amount)
+}
+
+pub fn usecase_error_status(err: &UseCaseError, bank_account_id: &BankAccountId) -> RpcStatus {
+    let cause = err.cause();
+    let (code, reason, amount, message) = match err.kind() {
+        UseCaseErrorKind::BankAccountNotFound(_) =>
+            (RpcStatusCode::NOT_FOUND, ErrorReason::BANK_ACCOUNT_NOT_FOUND, 0, err.to_string()),
+        UseCaseErrorKind::BankAccountDeleted(_) =>
+            (RpcStatusCode::NOT_FOUND, ErrorReason::BANK_ACCOUNT_DELETED, 0, err.to_string()),
+        UseCaseErrorKind::BankAccountError => match cause.and_then(|cause| cause.downcast_ref::<BankAccountError>()) {
+            Some(cause) => {
+                let (code, reason, amount) = bank_account_reason(cause.kind());
+                (code, reason, amount, cause.to_string())
+            },
+            None => (RpcStatusCode::INTERNAL, ErrorReason::UNKNOWN, 0, err.to_string()),
+        },
+        UseCaseErrorKind::EventStoreError => match cause.and_then(|cause| cause.downcast_ref::<EventStoreError>()) {
+            Some(cause) => {
+                let (code, reason) = event_store_reason(cause.kind());
+                (code, reason, 0, err.to_string())
+            },
+            None => (RpcStatusCode::INTERNAL, ErrorReason::EVENT_STORE_FAILURE, 0, err.to_string()),
+        },
+        UseCaseErrorKind::IdempotencyKeyReused(_) =>
+            (RpcStatusCode::INVALID_ARGUMENT, ErrorReason::IDEMPOTENCY_KEY_REUSED, 0, err.to_string()),
+        UseCaseErrorKind::CommandInProgress(_) =>
+            (RpcStatusCode::ABORTED, ErrorReason::COMMAND_IN_PROGRESS, 0, err.to_string()),
+        UseCaseErrorKind::IdempotencyStoreError =>
+            (RpcStatusCode::UNAVAILABLE, ErrorReason::IDEMPOTENCY_STORE_UNAVAILABLE, 0, err.to_string()),
+    };
+    rpc_status(code, reason, message, bank_account_id.to_string(), amount)
+}
@@ -1,79 +1,140 @@
+use std::sync::Arc;
 use log::{info, error};
-
-use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
-use elastic::prelude::*;
+use structopt::StructOpt;
 
 use rust_cqrses_bankaccount::aggregate::BankAccountEvent;
+use rust_cqrses_bankaccount::checkpoint::{
+    CheckpointStore,
+    CheckpointError,
+    CheckpointErrorKind,
+    handle_in_order,
+    next_version,
+};
+use rust_cqrses_bankaccount::crypto::PiiCipher;
+use rust_cqrses_bankaccount::encrypting_eventstore::EncryptingEventStore;
+use rust_cqrses_bankaccount::instrumented_eventstore::InstrumentedEventStore;
 use rust_cqrses_bankaccount::projector::BankAccountProjector;
+use rust_cqrses_bankaccount::usecase::command::BankAccountAggregateUseCase;
 use rust_cqrses_bankaccount_mysql_example::Config;
 use rust_cqrses_bankaccount_mysql_example::constants;
-use rust_cqrses_bankaccount_mysql_example::dao::ElasticBankAccountDao;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::checkpoint::create_checkpoint_store;
+use rust_cqrses_bankaccount_mysql_example::deadletter::create_dead_letter_sink;
+use rust_cqrses_bankaccount_mysql_example::consumer::{
+    ConsumerError,
+    ConsumerErrorKind,
+    ConsumerSettings,
+    EventConsumer,
+    EventHandler,
+    EventMessage,
+    shutdown_on_signal,
+};
+use rust_cqrses_bankaccount_mysql_example::dao::create_read_model_dao;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+use rust_cqrses_bankaccount_mysql_example::monitoring::install_metrics_exporter;
+use rust_cqrses_bankaccount_mysql_example::telemetry::init_telemetry;
 
 fn main() {
-    dotenv::dotenv().ok();
+    let shutdown = shutdown_on_signal();
 
-    env_logger::init();
+    dotenv::dotenv().ok();
 
     let config = envy::from_env::<Config>().unwrap();
 
-    project(config);
-}
+    let telemetry = init_telemetry("projector_runner", config.trace_exporter);
+
+    let args = Args::from_args();
 
-fn project(config: Config) {
-    let client = SyncClient::builder()
-        .static_node(config.elastic_search_endpoint)
-        .build()
-        .unwrap();
+    install_metrics_exporter(&args.metrics_addr);
 
-    let dao = Box::new(ElasticBankAccountDao::new(client));
+    let pool = db::init_database_pool(&config.database_url);
 
-    let projector = BankAccountProjector::new(dao);
+    let session = Arc::new(db::Session::new(pool.clone()));
 
-    let mut consumer = {
-        let cb = Consumer::from_hosts(config.kafka_brokers.clone())
-                .with_group(config.projector_kafka_consume_group.clone())
-                .with_topic(String::from(constants::TOPIC))
-                .with_fallback_offset(FetchOffset::Earliest)
-                .with_offset_storage(GroupOffsetStorage::Kafka);
-        cb.create().unwrap()
+    let cipher = PiiCipher::new(create_key_store(&config, pool.clone()));
+
+    let handler = ProjectorHandler {
+        projector: BankAccountProjector::new(create_read_model_dao(&config, session.clone())),
+        usecase: BankAccountAggregateUseCase::new(Box::new(EncryptingEventStore::new(
+                    InstrumentedEventStore::new(MysqlBankAccountEventStore::new(pool.clone()), "mysql"),
+                    cipher.clone()))),
+        cipher: cipher,
+        checkpoints: create_checkpoint_store(&config, session),
+        projection: config.projector_kafka_consume_group.clone(),
     };
 
-    loop {
-        let mss = match consumer.poll() {
-            Ok(mss) => mss,
-            Err(err) => {
-                error!("Error occrred: {:?}", err);
-                continue;
-            }
-        };
+    let dead_letters = create_dead_letter_sink(&config, pool);
 
-        if mss.is_empty() {
-            continue;
-        }
+    let settings = ConsumerSettings::new(
+        &config, config.projector_kafka_consume_group.clone(), String::from(constants::TOPIC));
+    let mut consumer = EventConsumer::new(settings).expect("kafka consumer build error occurred");
 
-        let mut error_occrred = false;
-
-        for ms in mss.iter() {
-            for m in ms.messages() {
-                let event: BankAccountEvent = match serde_json::from_slice(m.value) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        error!("Serialize event error: {:?}", err);
-                        error_occrred = true;
-                        break;
-                    }
-                };
-
-                projector.project(event.clone());
-
-                info!("{}:{}@{}: {:?}", ms.topic(), ms.partition(), m.offset, &event);
-            }
-            if error_occrred {
-                break;
-            }
-            let _ = consumer.consume_messageset(ms);
-        }
-        consumer.commit_consumed().unwrap();
+    if let Err(err) = consumer.run(&handler, &*dead_letters, &shutdown) {
+        error!("Projector stopped: {}", err);
+        drop(telemetry);
+        std::process::exit(1);
     }
+}
 
+#[derive(StructOpt, Debug)]
+#[structopt(name = "projector_runner")]
+struct Args {
+    #[structopt(long, default_value="127.0.0.1:9101")]
+    metrics_addr: String,
+}
+
+struct ProjectorHandler {
+    projector: BankAccountProjector,
+    usecase: BankAccountAggregateUseCase,
+    cipher: PiiCipher,
+    checkpoints: Box<dyn CheckpointStore>,
+    projection: String,
+}
+
+impl EventHandler for ProjectorHandler {
+    fn handle(&self, message: &EventMessage, stream_id: &str, stream_version: Option<u64>, event: BankAccountEvent)
+        -> Result<(), ConsumerError> {
+        let event = self.cipher.decrypt_event(event)
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::HandleError(err.to_string())))?;
+        let bank_account_id = event.bank_account_id().clone();
+
+        // Events published without a stream version catch up to the latest event of the stream instead.
+        let (stream_version, event) = match stream_version {
+            Some(stream_version) => (stream_version, event),
+            None => {
+                let next = next_version(&*self.checkpoints, &self.projection, stream_id)
+                    .map_err(|err| ConsumerError::from(ConsumerErrorKind::HandleError(err.to_string())))?;
+                let latest = self.usecase.events_since(bank_account_id.clone(), next)
+                    .map_err(|err| ConsumerError::from(ConsumerErrorKind::HandleError(err.to_string())))?
+                    .pop();
+                match latest {
+                    Some(latest) => latest,
+                    None => {
+                        info!("{}:{}@{}: {} already projected", message.topic, message.partition, message.offset, stream_id);
+                        return Ok(());
+                    },
+                }
+            },
+        };
+        let load_missed = |from_version: u64| {
+            info!("{}: catching up from {} to {}", stream_id, from_version, stream_version);
+            self.usecase.events_since(bank_account_id, from_version)
+                .map_err(|err| CheckpointError::from(CheckpointErrorKind::LoadError(err.to_string())))
+        };
+        let project = |version: u64, event: BankAccountEvent| {
+            self.projector.project(version, event)
+                .map_err(|err| CheckpointError::from(CheckpointErrorKind::HandleError(err.to_string())))
+        };
+        let projected = handle_in_order(
+            &*self.checkpoints, &self.projection, stream_id, stream_version, event.clone(), load_missed, project)
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::HandleError(err.to_string())))?;
+
+        if projected > 0 {
+            info!("{}:{}@{}: {}@{}: {:?}", message.topic, message.partition, message.offset, stream_id, stream_version, &event);
+        } else {
+            info!("{}:{}@{}: {}@{} already projected", message.topic, message.partition, message.offset, stream_id, stream_version);
+        }
+        Ok(())
+    }
 }
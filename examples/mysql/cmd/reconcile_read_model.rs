@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use structopt::StructOpt;
+
+use rust_cqrses_bankaccount::aggregate::{BankAccountId, BankAccountAggregate};
+use rust_cqrses_bankaccount::crypto::PiiCipher;
+use rust_cqrses_bankaccount::encrypting_eventstore::EncryptingEventStore;
+use rust_cqrses_bankaccount::hashchain::HashChainedEventStore;
+use rust_cqrses_bankaccount::reconciler::BankAccountReconciler;
+use rust_cqrses_bankaccount::usecase::command::BankAccountAggregateUseCase;
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::dao::create_read_model_dao;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    env_logger::init();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let args = Args::from_args();
+
+    let pool = db::init_database_pool(&config.database_url);
+
+    let store = MysqlBankAccountEventStore::new(pool.clone());
+    let bank_account_ids: Vec<BankAccountId> = if args.bank_account_ids.is_empty() {
+        store.stream_ids().expect("event store error occurred")
+            .iter()
+            .filter_map(|stream_id| BankAccountAggregate::bank_account_id_of(stream_id))
+            .collect()
+    } else {
+        args.bank_account_ids.into_iter()
+            .map(|value| BankAccountId::new(value).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }))
+            .collect()
+    };
+
+    let reconciler = BankAccountReconciler::new(
+        BankAccountAggregateUseCase::new(Box::new(EncryptingEventStore::new(
+                    store, PiiCipher::new(create_key_store(&config, pool.clone()))))),
+        create_read_model_dao(&config, Arc::new(db::Session::new(pool))));
+
+    let report = reconciler.reconcile(&bank_account_ids, args.repair);
+    for discrepancy in report.discrepancies.iter() {
+        println!("{}", serde_json::to_string(discrepancy).unwrap());
+    }
+    for (bank_account_id, reason) in report.errors.iter() {
+        eprintln!("{}: {}", bank_account_id, reason);
+    }
+    eprintln!("checked {} accounts: {} discrepancies, {} repaired, {} errors",
+              report.checked,
+              report.discrepancies.len(),
+              report.discrepancies.iter().filter(|discrepancy| discrepancy.repaired).count(),
+              report.errors.len());
+
+    if report.discrepancies.iter().any(|discrepancy| !discrepancy.repaired) || !report.errors.is_empty() {
+        std::process::exit(2);
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "reconcile_read_model")]
+pub struct Args {
+    #[structopt(long)]
+    repair: bool,
+    bank_account_ids: Vec<String>,
+}
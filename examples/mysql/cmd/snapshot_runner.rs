@@ -1,27 +1,70 @@
+use std::sync::Arc;
 use log::{info, error};
 use structopt::StructOpt;
 
-use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
-
 use rust_cqrses_bankaccount::snapshotter::BankAccountAggregateSnapshotter;
+use rust_cqrses_bankaccount::encrypting_eventstore::EncryptingEventStore;
+use rust_cqrses_bankaccount::instrumented_eventstore::InstrumentedEventStore;
+use rust_cqrses_bankaccount::crypto::PiiCipher;
 use rust_cqrses_bankaccount::aggregate::BankAccountEvent;
+use rust_cqrses_bankaccount::checkpoint::{CheckpointStore, CheckpointError, CheckpointErrorKind, handle_once, next_version};
 
 use rust_cqrses_bankaccount_mysql_example::Config;
 use rust_cqrses_bankaccount_mysql_example::constants;
 use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::checkpoint::create_checkpoint_store;
+use rust_cqrses_bankaccount_mysql_example::deadletter::create_dead_letter_sink;
+use rust_cqrses_bankaccount_mysql_example::consumer::{
+    ConsumerError,
+    ConsumerErrorKind,
+    ConsumerSettings,
+    EventConsumer,
+    EventHandler,
+    EventMessage,
+    shutdown_on_signal,
+};
 use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
-use rust_cqrses_bankaccount_mysql_example::eventpublisher::KafkaBankAccountEventPublisher;
+use rust_cqrses_bankaccount_mysql_example::keystore::create_key_store;
+use rust_cqrses_bankaccount_mysql_example::monitoring::install_metrics_exporter;
+use rust_cqrses_bankaccount_mysql_example::telemetry::init_telemetry;
 
 fn main() {
-    dotenv::dotenv().ok();
+    let shutdown = shutdown_on_signal();
 
-    env_logger::init();
+    dotenv::dotenv().ok();
 
     let config = envy::from_env::<Config>().unwrap();
 
+    let telemetry = init_telemetry("snapshot_runner", config.trace_exporter);
+
     let args = Args::from_args();
 
-    consume_messages(args, config);
+    install_metrics_exporter(&args.metrics_addr);
+
+    let pool = db::init_database_pool(&config.database_url);
+
+    let handler = SnapshotHandler {
+        snapshotter: BankAccountAggregateSnapshotter::new(
+            Box::new(EncryptingEventStore::new(
+                    InstrumentedEventStore::new(MysqlBankAccountEventStore::new(pool.clone()), "mysql"),
+                    PiiCipher::new(create_key_store(&config, pool.clone()))))),
+        checkpoints: create_checkpoint_store(&config, Arc::new(db::Session::new(pool.clone()))),
+        projection: config.snapshotter_kafka_consume_group.clone(),
+        dryrun: args.dryrun,
+    };
+
+    let dead_letters = create_dead_letter_sink(&config, pool);
+
+    let mut settings = ConsumerSettings::new(
+        &config, config.snapshotter_kafka_consume_group.clone(), String::from(constants::TOPIC));
+    settings.commit = !args.dryrun;
+    let mut consumer = EventConsumer::new(settings).expect("kafka consumer build error occurred");
+
+    if let Err(err) = consumer.run(&handler, &*dead_letters, &shutdown) {
+        error!("Snapshotter stopped: {}", err);
+        drop(telemetry);
+        std::process::exit(1);
+    }
 }
 
 #[derive(StructOpt, Debug)]
@@ -29,83 +72,46 @@ fn main() {
 struct Args {
     #[structopt(long)]
     dryrun: bool,
-}
-
-fn consume_messages(args: Args, config: Config) {
-    let pool = db::init_database_pool(&config.database_url);
 
-    let eventpublisher = KafkaBankAccountEventPublisher::new(config.kafka_brokers.clone(), String::from(constants::TOPIC));
-
-    let eventstore = Box::new(MysqlBankAccountEventStore::new(pool, eventpublisher));
+    #[structopt(long, default_value="127.0.0.1:9102")]
+    metrics_addr: String,
+}
 
-    let snapshotter = BankAccountAggregateSnapshotter::new(eventstore);
+struct SnapshotHandler {
+    snapshotter: BankAccountAggregateSnapshotter,
+    checkpoints: Box<dyn CheckpointStore>,
+    projection: String,
+    dryrun: bool,
+}
 
-    let mut consumer = {
-        let cb = Consumer::from_hosts(config.kafka_brokers.clone())
-                .with_group(config.snapshotter_kafka_consume_group.clone())
-                .with_topic(String::from(constants::TOPIC))
-                .with_fallback_offset(FetchOffset::Earliest)
-                .with_offset_storage(GroupOffsetStorage::Kafka);
-        cb.create().unwrap()
-    };
+impl EventHandler for SnapshotHandler {
+    fn handle(&self, message: &EventMessage, stream_id: &str, stream_version: Option<u64>, event: BankAccountEvent)
+        -> Result<(), ConsumerError> {
+        info!("{}:{}@{}: {:?}", message.topic, message.partition, message.offset, &event);
+        if self.dryrun {
+            return Ok(());
+        }
 
-    loop {
-        let mss = match consumer.poll() {
-            Ok(mss) => mss,
-            Err(err) => {
-                error!("Error occrred: {:?}", err);
-                continue;
-            }
+        // Events published without a stream version always take a snapshot.
+        let stream_version = match stream_version {
+            Some(stream_version) => stream_version,
+            None => next_version(&*self.checkpoints, &self.projection, stream_id)
+                .map_err(|err| ConsumerError::from(ConsumerErrorKind::HandleError(err.to_string())))?,
         };
 
-        if mss.is_empty() {
-            continue;
-        }
-
-        let mut error_occrred = false;
-
-        for ms in mss.iter() {
-            for m in ms.messages() {
-                let event: BankAccountEvent = match serde_json::from_slice(m.value) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        error!("Serialize event error: {:?}", err);
-                        error_occrred = true;
-                        break;
-                    }
-                };
-                if !args.dryrun {
-                    let result = match &event {
-                        BankAccountEvent::Opened{ bank_account_id, name: _, occurred_at: _ } => {
-                            snapshotter.take_snapshot(bank_account_id.clone())
-                        },
-                        BankAccountEvent::Updated{ bank_account_id, name: _, occurred_at: _ } => {
-                            snapshotter.take_snapshot(bank_account_id.clone())
-                        },
-                        BankAccountEvent::Deposited{ bank_account_id, deposit: _, occurred_at: _ } => {
-                            snapshotter.take_snapshot(bank_account_id.clone())
-                        },
-                        BankAccountEvent::Withdrawn{ bank_account_id, withdraw: _, occurred_at: _ } => {
-                            snapshotter.take_snapshot(bank_account_id.clone())
-                        },
-                        BankAccountEvent::Closed{ bank_account_id, occurred_at: _ } => {
-                            snapshotter.take_snapshot(bank_account_id.clone())
-                        },
-                    };
-                    match result {
-                        Ok(_) => {},
-                        Err(err) => println!("Snapshot error: {:?}", err.to_string()),
-                    }
-                }
-                info!("{}:{}@{}: {:?}", ms.topic(), ms.partition(), m.offset, &event);
-            }
-            if error_occrred {
-                break;
-            }
-            let _ = consumer.consume_messageset(ms);
-        }
-        if !args.dryrun {
-            consumer.commit_consumed().unwrap();
+        let result = handle_once(&*self.checkpoints, &self.projection, stream_id, stream_version, || {
+            self.snapshotter.take_snapshot(event.bank_account_id().clone())
+                .map_err(|err| CheckpointError::from(CheckpointErrorKind::HandleError(err.to_string())))
+        });
+
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                info!("{}:{}@{}: {}@{} already snapshotted",
+                      message.topic, message.partition, message.offset, stream_id, stream_version);
+                Ok(())
+            },
+            Err(err) => Err(ConsumerErrorKind::HandleError(err.to_string()))?,
         }
     }
 }
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use chrono::{Duration, Local};
+use structopt::StructOpt;
+
+use rust_cqrses_bankaccount::aggregate::{BankAccountId, BankAccountAggregate};
+use rust_cqrses_bankaccount::eventsourcing::{EventStore, StreamMetadata};
+use rust_cqrses_bankaccount::idempotency::IdempotencyStore;
+use rust_cqrses_bankaccount::snapshotter::BankAccountAggregateSnapshotter;
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+use rust_cqrses_bankaccount_mysql_example::idempotency::MysqlIdempotencyStore;
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    env_logger::init();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let args = Args::from_args();
+
+    let pool = db::init_database_pool(&config.database_url);
+
+    let store = Arc::new(MysqlBankAccountEventStore::new(pool.clone()));
+
+    match args.cmd {
+        Command::Show{ bank_account_id } => show(&store, bank_account_id),
+        Command::Delete{ bank_account_id } => delete(&store, bank_account_id),
+        Command::Truncate{ bank_account_id, before } => truncate(&store, bank_account_id, before),
+        Command::MaxAge{ bank_account_id, seconds } => max_age(&store, bank_account_id, seconds),
+        Command::Purge{ bank_account_id } => purge(&store, bank_account_id),
+        Command::PurgeIdempotencyKeys => purge_idempotency_keys(
+            &MysqlIdempotencyStore::new(pool), Duration::seconds(config.idempotency_key_ttl_secs)),
+    };
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "stream_maintenance")]
+pub struct Args {
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    Show {
+        bank_account_id: String,
+    },
+    Delete {
+        bank_account_id: String,
+    },
+    Truncate {
+        bank_account_id: String,
+        #[structopt(long = "before")]
+        before: Option<u64>,
+    },
+    MaxAge {
+        bank_account_id: String,
+        seconds: Option<u64>,
+    },
+    Purge {
+        bank_account_id: Option<String>,
+    },
+    PurgeIdempotencyKeys,
+}
+
+fn stream_id(bank_account_id: String) -> String {
+    match BankAccountId::new(bank_account_id) {
+        Ok(id) => BankAccountAggregate::stream_id(&id),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn update_metadata<F>(store: &MysqlBankAccountEventStore, stream_id: &str, update: F) -> StreamMetadata
+    where F: FnOnce(&mut StreamMetadata) {
+    let mut metadata = store.stream_metadata(stream_id.to_string()).expect("event store error occurred");
+    update(&mut metadata);
+    if let Err(err) = store.set_stream_metadata(stream_id.to_string(), metadata.clone()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    metadata
+}
+
+fn print_metadata(stream_id: &str, metadata: &StreamMetadata) {
+    println!("stream_id:       {}", stream_id);
+    println!("deleted:         {}", metadata.deleted);
+    println!("truncate_before: {}", metadata.truncate_before.map(|v| v.to_string()).unwrap_or_else(|| String::from("-")));
+    println!("max_age:         {}", metadata.max_age.map(|v| format!("{}s", v)).unwrap_or_else(|| String::from("-")));
+    println!("last_purged:     {}", metadata.last_purged.as_ref()
+             .map(|purged| format!("{} ({})", purged.stream_version, purged.event_hash))
+             .unwrap_or_else(|| String::from("-")));
+}
+
+fn show(store: &MysqlBankAccountEventStore, bank_account_id: String) {
+    let stream_id = stream_id(bank_account_id);
+    let metadata = store.stream_metadata(stream_id.clone()).expect("event store error occurred");
+    print_metadata(&stream_id, &metadata);
+}
+
+fn delete(store: &MysqlBankAccountEventStore, bank_account_id: String) {
+    let stream_id = stream_id(bank_account_id);
+    let metadata = update_metadata(store, &stream_id, |metadata| metadata.deleted = true);
+    print_metadata(&stream_id, &metadata);
+}
+
+fn truncate(store: &Arc<MysqlBankAccountEventStore>, bank_account_id: String, before: Option<u64>) {
+    let stream_id = stream_id(bank_account_id.clone());
+    if let Some(before) = before {
+        update_metadata(store, &stream_id, |metadata| metadata.truncate_before = Some(before));
+    } else {
+        let snapshotter = BankAccountAggregateSnapshotter::new(Box::new(store.clone()));
+        let id = BankAccountId::new(bank_account_id).unwrap();
+        if snapshotter.truncate_before_snapshot(id).expect("event store error occurred").is_none() {
+            eprintln!("{} has no snapshot to truncate to", stream_id);
+            std::process::exit(1);
+        }
+    }
+    let metadata = store.stream_metadata(stream_id.clone()).expect("event store error occurred");
+    print_metadata(&stream_id, &metadata);
+}
+
+fn max_age(store: &MysqlBankAccountEventStore, bank_account_id: String, seconds: Option<u64>) {
+    let stream_id = stream_id(bank_account_id);
+    let metadata = update_metadata(store, &stream_id, |metadata| metadata.max_age = seconds);
+    print_metadata(&stream_id, &metadata);
+}
+
+fn purge(store: &MysqlBankAccountEventStore, bank_account_id: Option<String>) {
+    let stream_ids = match bank_account_id {
+        Some(bank_account_id) => vec![stream_id(bank_account_id)],
+        None => store.stream_ids_with_metadata().expect("event store error occurred"),
+    };
+
+    for stream_id in stream_ids {
+        let purged = store.purge_stream(stream_id.clone()).expect("event store error occurred");
+        println!("{}: {} events purged", stream_id, purged);
+    }
+}
+
+fn purge_idempotency_keys(store: &MysqlIdempotencyStore, ttl: Duration) {
+    let purged = store.purge_expired(Local::now() - ttl).expect("idempotency store error occurred");
+    println!("{} idempotency keys purged", purged);
+}
@@ -0,0 +1,45 @@
+use structopt::StructOpt;
+
+use rust_cqrses_bankaccount::aggregate::{BankAccountId, BankAccountAggregate};
+use rust_cqrses_bankaccount::hashchain::{verify_store, verify_stream};
+use rust_cqrses_bankaccount_mysql_example::Config;
+use rust_cqrses_bankaccount_mysql_example::db;
+use rust_cqrses_bankaccount_mysql_example::eventstore::MysqlBankAccountEventStore;
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    env_logger::init();
+
+    let config = envy::from_env::<Config>().unwrap();
+
+    let args = Args::from_args();
+
+    let store = MysqlBankAccountEventStore::new(db::init_database_pool(&config.database_url));
+
+    let verification = match args.bank_account_id {
+        Some(bank_account_id) => match BankAccountId::new(bank_account_id) {
+            Ok(id) => verify_stream(&store, BankAccountAggregate::stream_id(&id)),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        None => verify_store(&store),
+    }.expect("event store error occurred");
+
+    match verification.broken_link {
+        Some(broken_link) => {
+            println!("verified {} events in {} streams", verification.events, verification.streams);
+            println!("broken link: {}", broken_link);
+            std::process::exit(2);
+        },
+        None => println!("verified {} events in {} streams: ok", verification.events, verification.streams),
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "verify_hash_chain")]
+pub struct Args {
+    bank_account_id: Option<String>,
+}
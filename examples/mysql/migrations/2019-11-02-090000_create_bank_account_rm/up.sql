@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_bank_account_rm (
+    `bank_account_id` varchar(250) NOT NULL,
+    `name` varchar(255) NOT NULL,
+    `is_closed` boolean NOT NULL,
+    `balance` int NOT NULL,
+    `created_at` datetime NOT NULL,
+    `updated_at` datetime NOT NULL,
+    `version` bigint(20) UNSIGNED NOT NULL,
+    PRIMARY KEY (`bank_account_id`)
+);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_projection_stream_version (
+    `projection` varchar(250) NOT NULL,
+    `stream_id` varchar(250) NOT NULL,
+    `stream_version` bigint(20) UNSIGNED NOT NULL,
+    `updated_at` datetime NOT NULL,
+    PRIMARY KEY (`projection`, `stream_id`)
+);
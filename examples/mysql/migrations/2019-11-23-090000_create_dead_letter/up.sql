@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_dead_letter (
+    `dead_letter_id` bigint(20) UNSIGNED NOT NULL auto_increment,
+    `consumer_group` varchar(250) NOT NULL,
+    `topic` varchar(250) NOT NULL,
+    `partition_id` int NOT NULL,
+    `message_offset` bigint(20) NOT NULL,
+    `message_key` BLOB NOT NULL,
+    `payload` MEDIUMBLOB NOT NULL,
+    `error` TEXT NOT NULL,
+    `retries` int NOT NULL,
+    `created_at` datetime NOT NULL,
+    `updated_at` datetime NOT NULL,
+    UNIQUE KEY (`consumer_group`, `topic`, `partition_id`, `message_offset`),
+    PRIMARY KEY (`dead_letter_id`)
+);
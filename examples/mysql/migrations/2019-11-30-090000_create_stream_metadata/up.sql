@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_stream_metadata (
+    `stream_id` varchar(250) NOT NULL,
+    `is_deleted` boolean NOT NULL DEFAULT FALSE,
+    `truncate_before` bigint(20) UNSIGNED NULL,
+    `max_age` bigint(20) UNSIGNED NULL,
+    `updated_at` datetime NOT NULL,
+    PRIMARY KEY (`stream_id`)
+);
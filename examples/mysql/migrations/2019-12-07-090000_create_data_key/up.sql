@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_data_key (
+    `subject` varchar(250) NOT NULL,
+    `data_key` varbinary(32) NULL,
+    `created_at` datetime NOT NULL,
+    `erased_at` datetime NULL,
+    PRIMARY KEY (`subject`)
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS tbl_stream_head;
+ALTER TABLE tbl_event_store DROP COLUMN `event_hash`;
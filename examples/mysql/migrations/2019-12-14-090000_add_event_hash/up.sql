@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE tbl_event_store ADD COLUMN `event_hash` char(64) NOT NULL DEFAULT '';
+
+CREATE TABLE IF NOT EXISTS tbl_stream_head (
+    `stream_id` varchar(250) NOT NULL,
+    `stream_version` bigint(20) UNSIGNED NOT NULL,
+    `event_hash` char(64) NOT NULL,
+    `updated_at` datetime NOT NULL,
+    PRIMARY KEY (`stream_id`)
+);
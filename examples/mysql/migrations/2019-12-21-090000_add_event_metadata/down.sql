@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tbl_event_store DROP COLUMN `event_metadata`;
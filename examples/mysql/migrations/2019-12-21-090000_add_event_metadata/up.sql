@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE tbl_event_store ADD COLUMN `event_metadata` text NULL;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_idempotency_key (
+    `bank_account_id` varchar(250) NOT NULL,
+    `idempotency_key` varchar(250) NOT NULL,
+    `fingerprint` char(64) NOT NULL,
+    `outcome` text NULL,
+    `created_at` datetime NOT NULL,
+    KEY (`created_at`),
+    PRIMARY KEY (`bank_account_id`, `idempotency_key`)
+);
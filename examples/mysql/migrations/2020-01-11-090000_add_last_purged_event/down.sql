@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tbl_stream_metadata DROP COLUMN `last_purged_hash`;
+ALTER TABLE tbl_stream_metadata DROP COLUMN `last_purged_version`;
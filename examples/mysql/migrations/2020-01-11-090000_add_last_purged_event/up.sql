@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE tbl_stream_metadata ADD COLUMN `last_purged_version` bigint(20) UNSIGNED NULL;
+ALTER TABLE tbl_stream_metadata ADD COLUMN `last_purged_hash` char(64) NULL;
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use chrono::{Local, NaiveDateTime};
+
+use diesel::prelude::*;
+
+use rust_cqrses_bankaccount::checkpoint::{
+    CheckpointStore,
+    CheckpointError,
+    CheckpointErrorKind,
+    InmemoryCheckpointStore,
+    FileCheckpointStore,
+};
+
+use super::schema::tbl_projection_stream_version;
+use super::db::{Pool, Session};
+use super::{Config, CheckpointStoreType};
+
+pub struct MysqlCheckpointStore {
+    session: Arc<Session>,
+}
+
+impl MysqlCheckpointStore {
+    pub fn new(pool: Pool) -> Self {
+        Self::with_session(Arc::new(Session::new(pool)))
+    }
+
+    pub fn with_session(session: Arc<Session>) -> Self {
+        Self {
+            session,
+        }
+    }
+}
+
+impl CheckpointStore for MysqlCheckpointStore {
+    fn stream_version(&self, projection: &str, stream_id: &str) -> Result<Option<u64>, CheckpointError> {
+        self.session.with_conn(|conn| {
+            tbl_projection_stream_version::table
+                .select(tbl_projection_stream_version::stream_version)
+                .filter(tbl_projection_stream_version::projection.eq(projection))
+                .filter(tbl_projection_stream_version::stream_id.eq(stream_id))
+                .first::<u64>(conn)
+                .optional()
+        }).map_err(|err| CheckpointError::from(CheckpointErrorKind::LoadError(err.to_string())))
+    }
+
+    fn save(&self, projection: &str, stream_id: &str, stream_version: u64) -> Result<(), CheckpointError> {
+        let new_stream_version = StreamVersionRecord {
+            projection: projection,
+            stream_id: stream_id,
+            stream_version: stream_version,
+            updated_at: Local::now().naive_local(),
+        };
+
+        self.session.with_conn(|conn| {
+            diesel::replace_into(tbl_projection_stream_version::table)
+                .values(&new_stream_version)
+                .execute(conn)
+        })
+            .map(|_| ())
+            .map_err(|err| CheckpointError::from(CheckpointErrorKind::SaveError(err.to_string())))
+    }
+
+    fn transaction(&self, work: &mut dyn FnMut() -> Result<(), CheckpointError>)
+        -> Result<(), CheckpointError> {
+        self.session.transaction(|| work())
+            .map_err(|err| CheckpointError::from(CheckpointErrorKind::SaveError(err.to_string())))
+            .and_then(|result| result)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_projection_stream_version"]
+struct StreamVersionRecord<'a> {
+    projection: &'a str,
+    stream_id: &'a str,
+    stream_version: u64,
+    updated_at: NaiveDateTime,
+}
+
+pub fn create_checkpoint_store(config: &Config, session: Arc<Session>) -> Box<dyn CheckpointStore> {
+    match config.checkpoint_store {
+        CheckpointStoreType::Memory => Box::new(InmemoryCheckpointStore::new()),
+        CheckpointStoreType::Mysql => Box::new(MysqlCheckpointStore::with_session(session)),
+        CheckpointStoreType::File => {
+            let directory = config.checkpoint_dir.clone()
+                .expect("CHECKPOINT_DIR is required for the file checkpoint store");
+            Box::new(FileCheckpointStore::open(directory).expect("checkpoint directory open error occurred"))
+        },
+    }
+}
@@ -1 +1,5 @@
 pub static TOPIC: &'static str = "bank_account";
+
+pub static DEAD_LETTER_TOPIC: &'static str = "bank_account_dead_letter";
+
+pub static READ_MODEL_INDEX: &'static str = "bank_account";
@@ -0,0 +1,410 @@
+use std::fmt;
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use log::{info, warn, error};
+use metrics::{counter, gauge};
+use tracing::info_span;
+use failure::{Fail, Context, Backtrace};
+use serde::{Serialize, Deserialize};
+use chan_signal::Signal;
+
+use kafka::client::KafkaClient;
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use kafka::producer::{Producer, Record, RequiredAcks};
+
+use rust_cqrses_bankaccount::aggregate::BankAccountEvent;
+
+use super::Config;
+use super::eventpublisher::decode_event;
+use super::telemetry::continue_trace;
+
+#[derive(Debug)]
+pub struct ConsumerError {
+    inner: Context<ConsumerErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum ConsumerErrorKind {
+    #[fail(display = "Kafka connection error: {}", _0)]
+    ConnectionError(String),
+
+    #[fail(display = "Handle event error: {}", _0)]
+    HandleError(String),
+
+    #[fail(display = "Commit offset error: {}", _0)]
+    CommitError(String),
+
+    #[fail(display = "Dead letter error: {}", _0)]
+    DeadLetterError(String),
+}
+
+impl Fail for ConsumerError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for ConsumerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl ConsumerError {
+    pub fn kind(&self) -> &ConsumerErrorKind {
+        &self.inner.get_context()
+    }
+}
+
+impl From<ConsumerErrorKind> for ConsumerError {
+    fn from(kind: ConsumerErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<ConsumerErrorKind>> for ConsumerError {
+    fn from(inner: Context<ConsumerErrorKind>) -> Self {
+        Self { inner: inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+pub trait EventHandler {
+    fn handle(&self, message: &EventMessage, stream_id: &str, stream_version: Option<u64>, event: BankAccountEvent)
+        -> Result<(), ConsumerError>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeadLetter {
+    pub group: String,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub error: String,
+    pub retries: u32,
+}
+
+pub trait DeadLetterSink {
+    fn send(&self, dead_letter: DeadLetter) -> Result<(), ConsumerError>;
+}
+
+pub struct KafkaDeadLetterSink {
+    producer: Mutex<Producer>,
+    topic: String,
+}
+
+impl KafkaDeadLetterSink {
+    pub fn new(hosts: Vec<String>, topic: String) -> Result<Self, ConsumerError> {
+        let producer = Producer::from_hosts(hosts)
+                 .with_ack_timeout(Duration::from_secs(1))
+                 .with_required_acks(RequiredAcks::One)
+                 .create()
+                 .map_err(|err| ConsumerError::from(ConsumerErrorKind::ConnectionError(err.to_string())))?;
+
+        Ok(Self {
+            producer: Mutex::new(producer),
+            topic: topic,
+        })
+    }
+}
+
+impl DeadLetterSink for KafkaDeadLetterSink {
+    fn send(&self, dead_letter: DeadLetter) -> Result<(), ConsumerError> {
+        let value = serde_json::to_vec(&dead_letter)
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::DeadLetterError(err.to_string())))?;
+
+        self.producer.lock().unwrap()
+            .send(&Record::from_key_value(&self.topic, dead_letter.key.as_slice(), value.as_slice()))
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::DeadLetterError(err.to_string())))
+    }
+}
+
+pub fn retry_topic(group: &str) -> String {
+    format!("{}_retry", group)
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsumerSettings {
+    pub brokers: Vec<String>,
+    pub group: String,
+    pub topic: String,
+    pub batch_size: usize,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_retries: u32,
+    pub commit: bool,
+}
+
+impl ConsumerSettings {
+    pub fn new(config: &Config, group: String, topic: String) -> Self {
+        Self {
+            brokers: config.kafka_brokers.clone(),
+            group: group,
+            topic: topic,
+            batch_size: config.consumer_batch_size,
+            min_backoff: Duration::from_millis(config.consumer_min_backoff_ms),
+            max_backoff: Duration::from_millis(config.consumer_max_backoff_ms),
+            max_retries: config.consumer_max_retries,
+            commit: true,
+        }
+    }
+}
+
+struct Backoff {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min: min,
+            max: max,
+            current: min,
+        }
+    }
+
+    fn wait(&mut self, shutdown: &AtomicBool) {
+        let mut waited = Duration::from_millis(0);
+        let step = Duration::from_millis(100);
+        while waited < self.current && !shutdown.load(Ordering::SeqCst) {
+            thread::sleep(step);
+            waited += step;
+        }
+        self.current = std::cmp::min(self.current * 2, self.max);
+    }
+
+    fn reset(&mut self) {
+        self.current = self.min;
+    }
+}
+
+pub fn shutdown_on_signal() -> Arc<AtomicBool> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let signal = chan_signal::notify(&[Signal::INT, Signal::TERM]);
+
+    let flag = shutdown.clone();
+    thread::spawn(move || {
+        if let Some(signal) = signal.recv() {
+            info!("Received {:?}, shutting down", signal);
+            flag.store(true, Ordering::SeqCst);
+        }
+    });
+
+    shutdown
+}
+
+struct LagMonitor {
+    client: KafkaClient,
+    group: String,
+    topic: String,
+    consumed: HashMap<i32, i64>,
+    reported_at: Option<Instant>,
+}
+
+impl LagMonitor {
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    fn new(settings: &ConsumerSettings) -> Self {
+        Self {
+            client: KafkaClient::new(settings.brokers.clone()),
+            group: settings.group.clone(),
+            topic: settings.topic.clone(),
+            consumed: HashMap::new(),
+            reported_at: None,
+        }
+    }
+
+    fn processed(&mut self, message: &EventMessage, outcome: &'static str) {
+        if message.topic == self.topic {
+            self.consumed.insert(message.partition, message.offset);
+        }
+        counter!("bankaccount_consumer_events_total", "group" => self.group.clone(), "outcome" => outcome)
+            .increment(1);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        gauge!("bankaccount_consumer_last_processed_timestamp_seconds", "group" => self.group.clone())
+            .set(now.as_secs_f64());
+    }
+
+    fn report(&mut self) {
+        if self.reported_at.is_some_and(|reported_at| reported_at.elapsed() < Self::INTERVAL) {
+            return;
+        }
+        self.reported_at = Some(Instant::now());
+
+        let offsets = self.client.load_metadata_all()
+            .and_then(|_| self.client.fetch_topic_offsets(self.topic.as_str(), FetchOffset::Latest));
+        match offsets {
+            Ok(offsets) => for offset in offsets {
+                let next = self.consumed.get(&offset.partition).map(|consumed| consumed + 1).unwrap_or(0);
+                gauge!("bankaccount_consumer_lag",
+                       "group" => self.group.clone(), "partition" => offset.partition.to_string())
+                    .set(std::cmp::max(offset.offset - next, 0) as f64);
+            },
+            Err(err) => warn!("Fetching latest offsets of {} failed: {}", self.topic, err),
+        }
+    }
+}
+
+pub struct EventConsumer {
+    consumer: Consumer,
+    settings: ConsumerSettings,
+    pending: usize,
+    lag: LagMonitor,
+}
+
+impl EventConsumer {
+    pub fn new(settings: ConsumerSettings) -> Result<Self, ConsumerError> {
+        let consumer = Consumer::from_hosts(settings.brokers.clone())
+                .with_group(settings.group.clone())
+                .with_topic(settings.topic.clone())
+                .with_topic(retry_topic(&settings.group))
+                .with_fallback_offset(FetchOffset::Earliest)
+                .with_offset_storage(GroupOffsetStorage::Kafka)
+                .create()
+                .map_err(|err| ConsumerError::from(ConsumerErrorKind::ConnectionError(err.to_string())))?;
+
+        Ok(Self {
+            consumer: consumer,
+            lag: LagMonitor::new(&settings),
+            settings: settings,
+            pending: 0,
+        })
+    }
+
+    pub fn run(&mut self, handler: &dyn EventHandler, dead_letters: &dyn DeadLetterSink, shutdown: &AtomicBool)
+        -> Result<(), ConsumerError> {
+        let mut backoff = Backoff::new(self.settings.min_backoff, self.settings.max_backoff);
+
+        while !shutdown.load(Ordering::SeqCst) {
+            self.lag.report();
+            let mss = match self.consumer.poll() {
+                Ok(mss) => {
+                    backoff.reset();
+                    mss
+                },
+                Err(err) => {
+                    error!("Poll error occurred: {:?}", err);
+                    backoff.wait(shutdown);
+                    continue;
+                }
+            };
+
+            'messagesets: for ms in mss.iter() {
+                for m in ms.messages() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break 'messagesets;
+                    }
+
+                    let message = EventMessage {
+                        topic: ms.topic().to_string(),
+                        partition: ms.partition(),
+                        offset: m.offset,
+                    };
+
+                    let failure = match decode_event(m.value) {
+                        Ok(envelope) => {
+                            let span = info_span!("consume_event",
+                                                  group = %self.settings.group,
+                                                  topic = %message.topic,
+                                                  partition = message.partition,
+                                                  offset = message.offset,
+                                                  stream_id = %envelope.stream_id,
+                                                  stream_version = ?envelope.stream_version,
+                                                  event_type = envelope.event.event_type());
+                            continue_trace(&span, &envelope.trace_context);
+                            let _entered = span.enter();
+
+                            let event = envelope.event;
+                            let mut retries = 0;
+                            loop {
+                                match handler.handle(&message, &envelope.stream_id, envelope.stream_version, event.clone()) {
+                                    Ok(_) => break None,
+                                    Err(err) => {
+                                        error!("{}:{}@{}: {}", message.topic, message.partition, message.offset, err);
+                                        if retries >= self.settings.max_retries {
+                                            break Some((err.to_string(), retries));
+                                        }
+                                        retries += 1;
+                                        backoff.wait(shutdown);
+                                        if shutdown.load(Ordering::SeqCst) {
+                                            break 'messagesets;
+                                        }
+                                    },
+                                }
+                            }
+                        },
+                        Err(err) => Some((err.to_string(), 0)),
+                    };
+                    backoff.reset();
+
+                    let outcome = if failure.is_some() { "dead_letter" } else { "ok" };
+                    if let Some((error, retries)) = failure {
+                        warn!("{}:{}@{}: message sent to dead letter: {}",
+                              message.topic, message.partition, message.offset, error);
+                        let dead_letter = DeadLetter {
+                            group: self.settings.group.clone(),
+                            topic: message.topic.clone(),
+                            partition: message.partition,
+                            offset: message.offset,
+                            key: m.key.to_vec(),
+                            payload: m.value.to_vec(),
+                            error: error,
+                            retries: retries,
+                        };
+                        while let Err(err) = dead_letters.send(dead_letter.clone()) {
+                            error!("{}:{}@{}: {}", message.topic, message.partition, message.offset, err);
+                            backoff.wait(shutdown);
+                            if shutdown.load(Ordering::SeqCst) {
+                                break 'messagesets;
+                            }
+                        }
+                        backoff.reset();
+                    }
+
+                    self.consume(&message)?;
+                    self.lag.processed(&message, outcome);
+                }
+            }
+            self.commit()?;
+        }
+
+        info!("Committing consumed offsets before shutdown");
+        self.commit()
+    }
+
+    fn consume(&mut self, message: &EventMessage) -> Result<(), ConsumerError> {
+        self.consumer.consume_message(&message.topic, message.partition, message.offset)
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::CommitError(err.to_string())))?;
+        self.pending += 1;
+        if self.pending >= self.settings.batch_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), ConsumerError> {
+        if self.settings.commit && self.pending > 0 {
+            self.consumer.commit_consumed()
+                .map_err(|err| ConsumerError::from(ConsumerErrorKind::CommitError(err.to_string())))?;
+        }
+        self.pending = 0;
+        Ok(())
+    }
+}
@@ -1,84 +1,552 @@
-use chrono::{DateTime, Utc, Local};
+use std::sync::Arc;
+use chrono::{DateTime, Utc, Local, NaiveDateTime, TimeZone};
 
-use elastic::client::SyncClient;
-use elastic::prelude::*;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
 
+use log::warn;
 use serde::{Serialize, Deserialize};
+use serde_json::json;
 
-use rust_cqrses_bankaccount::dao::{BankAccountRM, BankAccountRMDao};
+use rust_cqrses_bankaccount::dao::{BankAccountRM, BankAccountRMDao, DaoError, DaoErrorKind};
+
+use super::constants;
+use super::schema::tbl_bank_account_rm;
+use super::db::{Pool, Session};
+use super::{Config, ReadModelStoreType};
 
 pub struct ElasticBankAccountRMDao {
-    client: SyncClient,
+    agent: ureq::Agent,
+    endpoint: String,
+    index: String,
+    page_size: usize,
+}
+
+fn elastic_error(url: &str, err: impl std::fmt::Display) -> DaoError {
+    DaoError::from(DaoErrorKind::QueryError(format!("{}: {}", url, err)))
 }
 
 impl ElasticBankAccountRMDao {
-    pub fn new(client: SyncClient) -> Self {
+    const DEFAULT_PAGE_SIZE: usize = 1000;
+
+    pub fn new(endpoint: String) -> Self {
+        Self::with_index(endpoint, String::from(constants::READ_MODEL_INDEX))
+    }
+
+    pub fn with_index(endpoint: String, index: String) -> Self {
         Self {
-            client,
+            agent: ureq::Agent::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            index,
+            page_size: Self::DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn create_index(&self) -> Result<(), failure::Error> {
+        let mapping = json!({
+            "mappings": {
+                "properties": {
+                    "bank_account_id": { "type": "keyword" },
+                    "name": { "type": "text", "fields": { "keyword": { "type": "keyword" } } },
+                    "is_closed": { "type": "boolean" },
+                    "balance": { "type": "integer" },
+                    "created_at": { "type": "date" },
+                    "updated_at": { "type": "date" },
+                    "version": { "type": "long" }
+                }
+            }
+        });
+
+        match self.agent.put(&format!("{}/{}", self.endpoint, self.index)).send_json(mapping) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(400, response)) => {
+                let body: serde_json::Value = response.into_json()?;
+                if body["error"]["type"] == "resource_already_exists_exception" {
+                    Ok(())
+                } else {
+                    Err(failure::format_err!("create index error: {}", body))
+                }
+            },
+            Err(err) => Err(failure::format_err!("create index error: {}", err)),
+        }
+    }
+
+    fn document_url(&self, bank_account_id: &str) -> String {
+        format!("{}/{}/_doc/{}", self.endpoint, self.index, bank_account_id)
+    }
+
+    fn index_document(&self, model: BankAccountRM, version_type: &str) -> Result<(), DaoError> {
+        let url = self.document_url(&model.bank_account_id);
+        let version = model.version;
+        let doc = BankAccountDocument::from(model);
+
+        let response = self.agent.put(&url)
+            .query("version", &version.to_string())
+            .query("version_type", version_type)
+            .send_json(doc);
+
+        match response {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(409, _)) => {
+                warn!("Stale read model write ignored: {}@{}", url, version);
+                Ok(())
+            },
+            Err(err) => Err(elastic_error(&url, err)),
+        }
+    }
+
+    fn search_page(&self, url: &str, search_after: Option<&serde_json::Value>)
+        -> Result<Option<Vec<SearchHit>>, DaoError> {
+        let mut query = json!({
+            "size": self.page_size,
+            "query": { "match_all": {} },
+            "sort": [{ "bank_account_id": "asc" }]
+        });
+        if let Some(search_after) = search_after {
+            query["search_after"] = search_after.clone();
+        }
+
+        match self.agent.post(url).send_json(query) {
+            Ok(response) => {
+                let result: SearchResult = response.into_json().map_err(|err| elastic_error(url, err))?;
+                Ok(Some(result.hits.hits))
+            },
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(err) => Err(elastic_error(url, err)),
         }
     }
 }
 
 impl BankAccountRMDao for ElasticBankAccountRMDao {
-    fn find(&self, bank_account_id: String) -> Option<BankAccountRM> {
-        let response = self.client.document::<BankAccountRecord>().get(bank_account_id).send().unwrap();
-        match response.into_document() {
-            Some(doc) => Some(BankAccountRM {
-                bank_account_id: doc.bank_account_id.clone(),
-                name: doc.name.clone(),
-                is_closed: doc.is_closed,
-                balance: doc.balance,
-                created_at: DateTime::parse_from_rfc3339(&format!("{}", doc.created_at)).unwrap().with_timezone(&Local),
-                updated_at: DateTime::parse_from_rfc3339(&format!("{}", doc.updated_at)).unwrap().with_timezone(&Local),
-                version: doc.version.parse().unwrap(),
-            }),
-            None => None,
+    fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError> {
+        let url = self.document_url(&bank_account_id);
+        match self.agent.get(&url).call() {
+            Ok(response) => {
+                let result: GetResult = response.into_json().map_err(|err| elastic_error(&url, err))?;
+                Ok(result._source.map(BankAccountRM::from))
+            },
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(err) => Err(elastic_error(&url, err)),
         }
     }
 
-    fn insert(&self, model: BankAccountRM) {
-        let doc = BankAccountRecord {
-            bank_account_id: model.bank_account_id.clone(),
-            name: model.name.clone(),
+    fn insert(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        self.index_document(model, "external")
+    }
+
+    fn update(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        self.index_document(model, "external")
+    }
+
+    fn overwrite(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        self.index_document(model, "external_gte")
+    }
+
+    fn list(&self) -> Result<Vec<BankAccountRM>, DaoError> {
+        let url = format!("{}/{}/_search", self.endpoint, self.index);
+        let mut models = vec![];
+        let mut search_after = None;
+
+        while let Some(hits) = self.search_page(&url, search_after.as_ref())? {
+            let count = hits.len();
+            for hit in hits {
+                search_after = hit.sort;
+                models.extend(hit._source.map(BankAccountRM::from));
+            }
+            if count < self.page_size || search_after.is_none() {
+                break;
+            }
+        }
+        Ok(models)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BankAccountDocument {
+    bank_account_id: String,
+    name: String,
+    is_closed: bool,
+    balance: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    version: u64,
+}
+
+impl From<BankAccountRM> for BankAccountDocument {
+    fn from(model: BankAccountRM) -> Self {
+        Self {
+            bank_account_id: model.bank_account_id,
+            name: model.name,
             is_closed: model.is_closed,
             balance: model.balance,
-            created_at: Date::new(model.created_at.with_timezone(&Utc)),
-            updated_at: Date::new(model.updated_at.with_timezone(&Utc)),
-            version: model.version.to_string(),
-        };
+            created_at: model.created_at.with_timezone(&Utc),
+            updated_at: model.updated_at.with_timezone(&Utc),
+            version: model.version,
+        }
+    }
+}
+
+impl From<BankAccountDocument> for BankAccountRM {
+    fn from(doc: BankAccountDocument) -> Self {
+        Self {
+            bank_account_id: doc.bank_account_id,
+            name: doc.name,
+            is_closed: doc.is_closed,
+            balance: doc.balance,
+            created_at: doc.created_at.with_timezone(&Local),
+            updated_at: doc.updated_at.with_timezone(&Local),
+            version: doc.version,
+        }
+    }
+}
 
-        let response = self.client.document().index(doc).send();
-        response.unwrap();
+#[derive(Deserialize)]
+struct GetResult {
+    _source: Option<BankAccountDocument>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    hits: SearchHits,
+}
+
+#[derive(Deserialize)]
+struct SearchHits {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    _source: Option<BankAccountDocument>,
+    sort: Option<serde_json::Value>,
+}
+
+pub struct MysqlBankAccountRMDao {
+    session: Arc<Session>,
+}
+
+impl MysqlBankAccountRMDao {
+    pub fn new(pool: Pool) -> Self {
+        Self::with_session(Arc::new(Session::new(pool)))
     }
 
-    fn update(&self, model: BankAccountRM) {
-        let new_doc = BankAccountRecord {
-            bank_account_id: model.bank_account_id.clone(),
-            name: model.name.clone(),
+    pub fn with_session(session: Arc<Session>) -> Self {
+        Self {
+            session,
+        }
+    }
+}
+
+fn query_error(err: DieselError) -> DaoError {
+    DaoError::from(DaoErrorKind::QueryError(err.to_string()))
+}
+
+impl BankAccountRMDao for MysqlBankAccountRMDao {
+    fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError> {
+        self.session.with_conn(|conn| {
+            tbl_bank_account_rm::table
+                .filter(tbl_bank_account_rm::bank_account_id.eq(bank_account_id))
+                .first::<BankAccountRMRecord>(conn)
+                .optional()
+        }).map(|record| record.map(BankAccountRM::from)).map_err(query_error)
+    }
+
+    fn insert(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        let record = BankAccountRMRecord::from(model);
+        self.session.with_conn(|conn| {
+            diesel::insert_into(tbl_bank_account_rm::table)
+                .values(&record)
+                .execute(conn)
+        }).map(|_| ()).map_err(query_error)
+    }
+
+    fn update(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        let record = BankAccountRMRecord::from(model);
+        self.session.with_conn(|conn| {
+            diesel::update(tbl_bank_account_rm::table.find(record.bank_account_id.clone()))
+                .set(&record)
+                .execute(conn)
+        }).map(|_| ()).map_err(query_error)
+    }
+
+    fn list(&self) -> Result<Vec<BankAccountRM>, DaoError> {
+        self.session.with_conn(|conn| {
+            tbl_bank_account_rm::table
+                .order(tbl_bank_account_rm::bank_account_id.asc())
+                .load::<BankAccountRMRecord>(conn)
+        }).map(|records| records.into_iter().map(BankAccountRM::from).collect()).map_err(query_error)
+    }
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[table_name = "tbl_bank_account_rm"]
+#[primary_key(bank_account_id)]
+struct BankAccountRMRecord {
+    bank_account_id: String,
+    name: String,
+    is_closed: bool,
+    balance: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    version: u64,
+}
+
+impl From<BankAccountRM> for BankAccountRMRecord {
+    fn from(model: BankAccountRM) -> Self {
+        Self {
+            bank_account_id: model.bank_account_id,
+            name: model.name,
             is_closed: model.is_closed,
             balance: model.balance,
-            created_at: Date::new(model.created_at.with_timezone(&Utc)),
-            updated_at: Date::new(model.updated_at.with_timezone(&Utc)),
-            version: model.version.to_string(),
-        };
-
-        let response = self.client.document::<BankAccountRecord>()
-            .update(model.bank_account_id.clone())
-            .doc(new_doc)
-            .send();
-
-        response.unwrap();
-    }
-}
-
-#[derive(Serialize, Deserialize, ElasticType)]
-struct BankAccountRecord {
-    #[elastic(id)]
-    pub bank_account_id: String,
-    pub name: String,
-    pub is_closed: bool,
-    pub balance: i32,
-    pub created_at: Date<DefaultDateMapping<ChronoFormat>>,
-    pub updated_at: Date<DefaultDateMapping<ChronoFormat>>,
-    pub version: String,
+            created_at: model.created_at.naive_local(),
+            updated_at: model.updated_at.naive_local(),
+            version: model.version,
+        }
+    }
+}
+
+impl From<BankAccountRMRecord> for BankAccountRM {
+    fn from(record: BankAccountRMRecord) -> Self {
+        Self {
+            bank_account_id: record.bank_account_id,
+            name: record.name,
+            is_closed: record.is_closed,
+            balance: record.balance,
+            created_at: Local.from_local_datetime(&record.created_at).unwrap(),
+            updated_at: Local.from_local_datetime(&record.updated_at).unwrap(),
+            version: record.version,
+        }
+    }
+}
+
+pub fn create_read_model_dao(config: &Config, session: Arc<Session>) -> Box<dyn BankAccountRMDao> {
+    match config.read_model_store {
+        ReadModelStoreType::Elasticsearch => {
+            let endpoint = config.elastic_search_endpoint.clone()
+                .expect("ELASTIC_SEARCH_ENDPOINT is required for the elasticsearch read model store");
+            let dao = ElasticBankAccountRMDao::new(endpoint);
+            dao.create_index().expect("elasticsearch index create error occurred");
+            Box::new(dao)
+        },
+        ReadModelStoreType::Mysql => {
+            Box::new(MysqlBankAccountRMDao::with_session(session))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    use rust_cqrses_bankaccount::dao::{BankAccountRM, BankAccountRMDao};
+
+    use super::ElasticBankAccountRMDao;
+
+    fn create_dao(index: &str) -> ElasticBankAccountRMDao {
+        ElasticBankAccountRMDao::with_index(mockito::server_url(), String::from(index))
+    }
+
+    fn model(version: u64) -> BankAccountRM {
+        BankAccountRM {
+            bank_account_id: String::from("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            name: String::from("foo"),
+            is_closed: false,
+            balance: 700,
+            created_at: Local.ymd(2019, 11, 16).and_hms(10, 0, 0),
+            updated_at: Local.ymd(2019, 11, 16).and_hms(10, 30, 0),
+            version: version,
+        }
+    }
+
+    #[test]
+    fn test_elastic_dao_find_missing_document() {
+        let _m = mock("GET", "/missing/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .with_status(404)
+            .with_body(r#"{"_index":"missing","_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","found":false}"#)
+            .create();
+
+        assert_eq!(create_dao("missing").find(model(1).bank_account_id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_elastic_dao_find() {
+        let source = json!({
+            "bank_account_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "name": "foo",
+            "is_closed": false,
+            "balance": 700,
+            "created_at": model(3).created_at.to_rfc3339(),
+            "updated_at": model(3).updated_at.to_rfc3339(),
+            "version": 3
+        });
+        let _m = mock("GET", "/found/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .with_status(200)
+            .with_body(json!({ "found": true, "_version": 3, "_source": source }).to_string())
+            .create();
+
+        assert_eq!(create_dao("found").find(model(3).bank_account_id).unwrap(), Some(model(3)));
+    }
+
+    #[test]
+    fn test_elastic_dao_writes_with_external_version() {
+        let m = mock("PUT", "/written/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded(String::from("version"), String::from("2")),
+                Matcher::UrlEncoded(String::from("version_type"), String::from("external")),
+            ]))
+            .match_body(Matcher::PartialJson(json!({ "balance": 700, "version": 2 })))
+            .with_status(200)
+            .with_body(r#"{"result":"updated","_version":2}"#)
+            .create();
+
+        create_dao("written").update(model(2)).unwrap();
+        m.assert();
+    }
+
+    #[test]
+    fn test_elastic_dao_overwrites_same_version() {
+        let m = mock("PUT", "/overwritten/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded(String::from("version"), String::from("2")),
+                Matcher::UrlEncoded(String::from("version_type"), String::from("external_gte")),
+            ]))
+            .with_status(200)
+            .with_body(r#"{"result":"updated","_version":2}"#)
+            .create();
+
+        create_dao("overwritten").overwrite(model(2)).unwrap();
+        m.assert();
+    }
+
+    #[test]
+    fn test_elastic_dao_ignores_stale_write() {
+        let m = mock("PUT", "/stale/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .match_query(Matcher::Any)
+            .with_status(409)
+            .with_body(r#"{"error":{"type":"version_conflict_engine_exception"},"status":409}"#)
+            .create();
+
+        create_dao("stale").insert(model(1)).unwrap();
+        m.assert();
+    }
+
+    #[test]
+    fn test_elastic_dao_create_index() {
+        let created = mock("PUT", "/created")
+            .match_body(Matcher::PartialJson(json!({
+                "mappings": { "properties": { "version": { "type": "long" } } }
+            })))
+            .with_status(200)
+            .with_body(r#"{"acknowledged":true}"#)
+            .create();
+        let exists = mock("PUT", "/exists")
+            .with_status(400)
+            .with_body(r#"{"error":{"type":"resource_already_exists_exception"},"status":400}"#)
+            .create();
+        let broken = mock("PUT", "/broken")
+            .with_status(400)
+            .with_body(r#"{"error":{"type":"mapper_parsing_exception"},"status":400}"#)
+            .create();
+
+        assert!(create_dao("created").create_index().is_ok());
+        assert!(create_dao("exists").create_index().is_ok());
+        assert!(create_dao("broken").create_index().is_err());
+        created.assert();
+        exists.assert();
+        broken.assert();
+    }
+
+    #[test]
+    fn test_elastic_dao_list() {
+        let _m = mock("POST", "/listed/_search")
+            .with_status(200)
+            .with_body(json!({
+                "hits": {
+                    "hits": [
+                        { "_source": {
+                            "bank_account_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                            "name": "foo",
+                            "is_closed": false,
+                            "balance": 700,
+                            "created_at": model(3).created_at.to_rfc3339(),
+                            "updated_at": model(3).updated_at.to_rfc3339(),
+                            "version": 3
+                        } }
+                    ]
+                }
+            }).to_string())
+            .create();
+
+        assert_eq!(create_dao("listed").list().unwrap(), vec![model(3)]);
+    }
+
+    #[test]
+    fn test_elastic_dao_list_pages_with_search_after() {
+        let document = |bank_account_id: &str| json!({
+            "bank_account_id": bank_account_id,
+            "name": "foo",
+            "is_closed": false,
+            "balance": 700,
+            "created_at": model(3).created_at.to_rfc3339(),
+            "updated_at": model(3).updated_at.to_rfc3339(),
+            "version": 3
+        });
+        let first = mock("POST", "/paged/_search")
+            .match_body(Matcher::PartialJson(json!({ "size": 1 })))
+            .with_status(200)
+            .with_body(json!({
+                "hits": { "hits": [{ "_source": document("0c5f9e44-3ab1-4d2e-9d0d-8b2e1c7a3f10"),
+                                     "sort": ["0c5f9e44-3ab1-4d2e-9d0d-8b2e1c7a3f10"] }] }
+            }).to_string())
+            .expect(1)
+            .create();
+        let second = mock("POST", "/paged/_search")
+            .match_body(Matcher::PartialJson(json!({ "search_after": ["0c5f9e44-3ab1-4d2e-9d0d-8b2e1c7a3f10"] })))
+            .with_status(200)
+            .with_body(json!({
+                "hits": { "hits": [{ "_source": document("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+                                     "sort": ["67e55044-10b1-426f-9247-bb680e5fe0c8"] }] }
+            }).to_string())
+            .expect(1)
+            .create();
+        let last = mock("POST", "/paged/_search")
+            .match_body(Matcher::PartialJson(json!({ "search_after": ["67e55044-10b1-426f-9247-bb680e5fe0c8"] })))
+            .with_status(200)
+            .with_body(r#"{"hits":{"hits":[]}}"#)
+            .expect(1)
+            .create();
+
+        let models = create_dao("paged").with_page_size(1).list().unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[1], model(3));
+        first.assert();
+        second.assert();
+        last.assert();
+    }
+
+    #[test]
+    fn test_elastic_dao_returns_errors() {
+        let _get = mock("GET", "/failing/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .with_status(503)
+            .create();
+        let _put = mock("PUT", "/failing/_doc/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .create();
+        let _search = mock("POST", "/failing/_search")
+            .with_status(503)
+            .create();
+
+        let dao = create_dao("failing");
+        assert!(dao.find(model(1).bank_account_id).is_err());
+        assert!(dao.update(model(2)).is_err());
+        assert!(dao.list().is_err());
+    }
 }
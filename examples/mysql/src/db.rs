@@ -1,5 +1,8 @@
+use std::sync::Mutex;
 use diesel::r2d2::ConnectionManager;
 use diesel::mysql::MysqlConnection;
+use diesel::connection::{Connection, TransactionManager};
+use diesel::result::{Error as DieselError, DatabaseErrorKind, QueryResult};
 
 pub type Conn = r2d2::PooledConnection<ConnectionManager<MysqlConnection>>;
 pub type Pool = r2d2::Pool<ConnectionManager<MysqlConnection>>;
@@ -8,3 +11,62 @@ pub fn init_database_pool(database_url: &str) -> Pool {
     let manager = ConnectionManager::<MysqlConnection>::new(database_url);
     Pool::builder().build(manager).expect("db pool build error occurred")
 }
+
+pub struct Session {
+    pool: Pool,
+    current: Mutex<Option<Conn>>,
+}
+
+impl Session {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+            current: Mutex::new(None),
+        }
+    }
+
+    fn get_conn(&self) -> QueryResult<Conn> {
+        self.pool.get()
+            .map_err(|err| DieselError::DatabaseError(DatabaseErrorKind::UnableToSendCommand, Box::new(err.to_string())))
+    }
+
+    pub fn with_conn<T, F>(&self, f: F) -> QueryResult<T>
+        where F: FnOnce(&MysqlConnection) -> QueryResult<T> {
+        {
+            let current = self.current.lock().unwrap();
+            if let Some(ref conn) = *current {
+                return f(conn);
+            }
+        }
+        let conn = self.get_conn()?;
+        f(&conn)
+    }
+
+    pub fn transaction<T, E, F>(&self, f: F) -> QueryResult<Result<T, E>>
+        where F: FnOnce() -> Result<T, E> {
+        {
+            let mut current = self.current.lock().unwrap();
+            if current.is_some() {
+                drop(current);
+                return Ok(f());
+            }
+            let conn = self.get_conn()?;
+            conn.transaction_manager().begin_transaction(&*conn)?;
+            *current = Some(conn);
+        }
+
+        let result = f();
+
+        let conn = self.current.lock().unwrap().take().unwrap();
+        match result {
+            Ok(value) => {
+                conn.transaction_manager().commit_transaction(&*conn)?;
+                Ok(Ok(value))
+            },
+            Err(err) => {
+                conn.transaction_manager().rollback_transaction(&*conn)?;
+                Ok(Err(err))
+            },
+        }
+    }
+}
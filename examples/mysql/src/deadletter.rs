@@ -0,0 +1,194 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+use diesel::prelude::*;
+use diesel::result::{Error as DieselError, DatabaseErrorKind};
+
+use super::schema::tbl_dead_letter;
+use super::db::{Conn, Pool};
+use super::consumer::{DeadLetter, DeadLetterSink, KafkaDeadLetterSink, ConsumerError, ConsumerErrorKind};
+use super::{Config, DeadLetterStoreType};
+use super::constants;
+
+#[derive(Debug, Clone)]
+pub struct StoredDeadLetter {
+    pub id: u64,
+    pub dead_letter: DeadLetter,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+pub struct MysqlDeadLetterStore {
+    pool: Pool,
+}
+
+fn store_error(err: DieselError) -> ConsumerError {
+    ConsumerError::from(ConsumerErrorKind::DeadLetterError(err.to_string()))
+}
+
+impl MysqlDeadLetterStore {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+        }
+    }
+
+    fn get_conn(&self) -> Result<Conn, ConsumerError> {
+        self.pool.get()
+            .map_err(|err| ConsumerError::from(ConsumerErrorKind::DeadLetterError(err.to_string())))
+    }
+
+    pub fn list(&self) -> Result<Vec<StoredDeadLetter>, ConsumerError> {
+        let conn = self.get_conn()?;
+
+        tbl_dead_letter::table
+            .order(tbl_dead_letter::dead_letter_id.asc())
+            .load::<DeadLetterRecord>(&conn)
+            .map(|records| records.into_iter().map(StoredDeadLetter::from).collect())
+            .map_err(store_error)
+    }
+
+    pub fn find(&self, id: u64) -> Result<Option<StoredDeadLetter>, ConsumerError> {
+        let conn = self.get_conn()?;
+
+        tbl_dead_letter::table
+            .find(id)
+            .first::<DeadLetterRecord>(&conn)
+            .optional()
+            .map(|record| record.map(StoredDeadLetter::from))
+            .map_err(store_error)
+    }
+
+    pub fn discard(&self, id: u64) -> Result<bool, ConsumerError> {
+        let conn = self.get_conn()?;
+
+        diesel::delete(tbl_dead_letter::table.find(id))
+            .execute(&conn)
+            .map(|count| count > 0)
+            .map_err(store_error)
+    }
+
+    pub fn discard_message(&self, dead_letter: &DeadLetter) -> Result<usize, ConsumerError> {
+        let conn = self.get_conn()?;
+
+        diesel::delete(tbl_dead_letter::table
+                       .filter(tbl_dead_letter::topic.eq(&dead_letter.topic))
+                       .filter(tbl_dead_letter::partition_id.eq(dead_letter.partition))
+                       .filter(tbl_dead_letter::message_offset.eq(dead_letter.offset)))
+            .execute(&conn)
+            .map_err(store_error)
+    }
+
+    pub fn record_retry(&self, id: u64, error: String) -> Result<(), ConsumerError> {
+        let conn = self.get_conn()?;
+
+        diesel::update(tbl_dead_letter::table.find(id))
+            .set((
+                tbl_dead_letter::error.eq(error),
+                tbl_dead_letter::retries.eq(tbl_dead_letter::retries + 1),
+                tbl_dead_letter::updated_at.eq(Local::now().naive_local()),
+            ))
+            .execute(&conn)
+            .map(|_| ())
+            .map_err(store_error)
+    }
+}
+
+impl DeadLetterSink for MysqlDeadLetterStore {
+    fn send(&self, dead_letter: DeadLetter) -> Result<(), ConsumerError> {
+        let conn = self.get_conn()?;
+        let now = Local::now().naive_local();
+
+        let new_record = NewDeadLetterRecord {
+            consumer_group: &dead_letter.group,
+            topic: &dead_letter.topic,
+            partition_id: dead_letter.partition,
+            message_offset: dead_letter.offset,
+            message_key: &dead_letter.key,
+            payload: &dead_letter.payload,
+            error: &dead_letter.error,
+            retries: dead_letter.retries as i32,
+            created_at: now,
+            updated_at: now,
+        };
+
+        match diesel::insert_into(tbl_dead_letter::table).values(&new_record).execute(&conn) {
+            Ok(_) => Ok(()),
+            Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                diesel::update(tbl_dead_letter::table
+                               .filter(tbl_dead_letter::consumer_group.eq(&dead_letter.group))
+                               .filter(tbl_dead_letter::topic.eq(&dead_letter.topic))
+                               .filter(tbl_dead_letter::partition_id.eq(dead_letter.partition))
+                               .filter(tbl_dead_letter::message_offset.eq(dead_letter.offset)))
+                    .set((
+                        tbl_dead_letter::error.eq(&dead_letter.error),
+                        tbl_dead_letter::retries.eq(tbl_dead_letter::retries + 1),
+                        tbl_dead_letter::updated_at.eq(now),
+                    ))
+                    .execute(&conn)
+                    .map(|_| ())
+                    .map_err(store_error)
+            },
+            Err(err) => Err(store_error(err)),
+        }
+    }
+}
+
+pub fn create_dead_letter_sink(config: &Config, pool: Pool) -> Box<dyn DeadLetterSink> {
+    match config.dead_letter_store {
+        DeadLetterStoreType::Kafka => {
+            Box::new(KafkaDeadLetterSink::new(config.kafka_brokers.clone(), String::from(constants::DEAD_LETTER_TOPIC))
+                     .expect("kafka producer build error occurred"))
+        },
+        DeadLetterStoreType::Mysql => Box::new(MysqlDeadLetterStore::new(pool)),
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_dead_letter"]
+struct NewDeadLetterRecord<'a> {
+    consumer_group: &'a str,
+    topic: &'a str,
+    partition_id: i32,
+    message_offset: i64,
+    message_key: &'a [u8],
+    payload: &'a [u8],
+    error: &'a str,
+    retries: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Queryable)]
+struct DeadLetterRecord {
+    dead_letter_id: u64,
+    consumer_group: String,
+    topic: String,
+    partition_id: i32,
+    message_offset: i64,
+    message_key: Vec<u8>,
+    payload: Vec<u8>,
+    error: String,
+    retries: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl From<DeadLetterRecord> for StoredDeadLetter {
+    fn from(record: DeadLetterRecord) -> Self {
+        Self {
+            id: record.dead_letter_id,
+            dead_letter: DeadLetter {
+                group: record.consumer_group,
+                topic: record.topic,
+                partition: record.partition_id,
+                offset: record.message_offset,
+                key: record.message_key,
+                payload: record.payload,
+                error: record.error,
+                retries: record.retries as u32,
+            },
+            created_at: Local.from_local_datetime(&record.created_at).unwrap(),
+            updated_at: Local.from_local_datetime(&record.updated_at).unwrap(),
+        }
+    }
+}
@@ -1,38 +1,161 @@
+use std::sync::Mutex;
 use std::time::Duration;
-use rust_cqrses_bankaccount::eventsourcing::EventPublisher;
-use rust_cqrses_bankaccount::aggregate::BankAccountEvent;
+use serde::{Serialize, Deserialize};
+use tracing::info_span;
+use rust_cqrses_bankaccount::eventsourcing::{EventPublisher, EventPublisherError, EventPublisherErrorKind};
+use rust_cqrses_bankaccount::aggregate::{BankAccountAggregate, BankAccountEvent};
 
 use kafka::producer::{Producer, Record, RequiredAcks};
 
+use super::telemetry::{TraceContext, current_trace_context};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventEnvelope {
+    #[serde(default)]
+    pub trace_context: TraceContext,
+    pub stream_id: String,
+    pub stream_version: Option<u64>,
+    pub event: BankAccountEvent,
+}
+
+#[derive(Deserialize)]
+struct LegacyEventEnvelope {
+    #[serde(default)]
+    trace_context: TraceContext,
+    event: BankAccountEvent,
+}
+
+pub fn decode_event(payload: &[u8]) -> Result<EventEnvelope, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_slice(payload)?;
+    if value.get("stream_id").is_some() {
+        return serde_json::from_value(value);
+    }
+
+    let legacy = if value.get("event").is_some() {
+        serde_json::from_value(value)?
+    } else {
+        LegacyEventEnvelope {
+            trace_context: TraceContext::new(),
+            event: serde_json::from_value(value)?,
+        }
+    };
+    Ok(EventEnvelope {
+        trace_context: legacy.trace_context,
+        stream_id: BankAccountAggregate::stream_id(legacy.event.bank_account_id()),
+        stream_version: None,
+        event: legacy.event,
+    })
+}
+
 pub struct KafkaBankAccountEventPublisher {
-    hosts: Vec<String>,
+    producer: Mutex<Producer>,
     topic: String,
 }
 
 impl KafkaBankAccountEventPublisher {
-    pub fn new(hosts: Vec<String>, topic: String) -> Self{
-        Self {
-            hosts: hosts,
+    pub fn new(hosts: Vec<String>, topic: String) -> Result<Self, EventPublisherError> {
+        let producer = Producer::from_hosts(hosts)
+                 .with_ack_timeout(Duration::from_secs(1))
+                 .with_required_acks(RequiredAcks::One)
+                 .create()
+                 .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::ConnectionError(err.to_string())))?;
+
+        Ok(Self {
+            producer: Mutex::new(producer),
             topic: topic,
-        }
+        })
     }
 }
 
 impl EventPublisher for KafkaBankAccountEventPublisher {
     type Event = BankAccountEvent;
 
-    fn publish(&self, event: Self::Event) {
-        let mut producer = Producer::from_hosts(self.hosts.clone())
-                 .with_ack_timeout(Duration::from_secs(1))
-                 .with_required_acks(RequiredAcks::One)
-                 .create()
-                 .unwrap();
+    fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError> {
+        let _span = info_span!("publish_events", stream_id = %stream_id, count = events.len()).entered();
+        let trace_context = current_trace_context();
+
+        let values = events.into_iter()
+            .enumerate()
+            .map(|(i, event)| serde_json::to_string(&EventEnvelope {
+                trace_context: trace_context.clone(),
+                stream_id: stream_id.clone(),
+                stream_version: Some(stream_version + i as u64),
+                event: event,
+            }))
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::PublishError(err.to_string())))?;
+
+        let records: Vec<Record<&str, &str>> = values.iter()
+            .map(|value| Record {
+                topic: &self.topic,
+                partition: -1,
+                key: stream_id.as_str(),
+                value: value.as_str(),
+            })
+            .collect();
 
-        producer.send(&Record {
-            topic: &self.topic,
-            partition: -1,
-            key: (),
-            value: serde_json::to_string(&event).unwrap(),
+        let mut producer = self.producer.lock().unwrap();
+        let confirms = producer.send_all(&records)
+            .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::PublishError(err.to_string())))?;
+
+        for confirm in confirms {
+            for partition_confirm in confirm.partition_confirms {
+                if let Err(code) = partition_confirm.offset {
+                    return Err(EventPublisherErrorKind::PublishError(
+                            format!("{}:{}: {:?}", confirm.topic, partition_confirm.partition, code)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use rust_cqrses_bankaccount::aggregate::{BankAccountEvent, BankAccountId};
+
+    use super::super::telemetry::TraceContext;
+    use super::{EventEnvelope, decode_event};
+
+    #[test]
+    fn test_decode_event() {
+        let event = BankAccountEvent::Deposited {
+            bank_account_id: BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+            deposit: 100,
+            occurred_at: Local::now(),
+        };
+        let mut trace_context = TraceContext::new();
+        trace_context.insert(
+            String::from("traceparent"),
+            String::from("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"));
+
+        let envelope = serde_json::to_vec(&EventEnvelope {
+            trace_context: trace_context.clone(),
+            stream_id: String::from("bank_account:67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            stream_version: Some(2),
+            event: event.clone(),
         }).unwrap();
+        let decoded = decode_event(&envelope).unwrap();
+        assert_eq!(decoded.event, event);
+        assert_eq!(decoded.trace_context, trace_context);
+        assert_eq!(decoded.stream_id, "bank_account:67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(decoded.stream_version, Some(2));
+
+        let legacy = serde_json::json!({ "trace_context": trace_context, "event": event });
+        let decoded = decode_event(&serde_json::to_vec(&legacy).unwrap()).unwrap();
+        assert_eq!(decoded.event, event);
+        assert_eq!(decoded.trace_context, trace_context);
+        assert_eq!(decoded.stream_id, "bank_account:67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(decoded.stream_version, None);
+
+        let decoded = decode_event(&serde_json::to_vec(&event).unwrap()).unwrap();
+        assert_eq!(decoded.event, event);
+        assert!(decoded.trace_context.is_empty());
+        assert_eq!(decoded.stream_id, "bank_account:67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(decoded.stream_version, None);
+
+        assert!(decode_event(b"{\"Deposited\": {}}").is_err());
     }
 }
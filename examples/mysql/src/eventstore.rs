@@ -3,87 +3,241 @@ use chrono::{Local, NaiveDateTime, TimeZone};
 use rust_cqrses_bankaccount::eventsourcing::{
     EventStream,
     Snapshot,
+    StreamMetadata,
+    EventMetadata,
+    PurgedEvent,
     EventStoreError,
     EventStoreErrorKind,
     EventStore,
-    EventPublisher,
 };
 use rust_cqrses_bankaccount::aggregate::{BankAccountEvent, BankAccount};
+use rust_cqrses_bankaccount::crypto::{redact_plaintext_event, redact_plaintext_snapshot};
+use rust_cqrses_bankaccount::hashchain::{GENESIS_HASH, ChainHead, HashChainedEventStore, event_hash};
+use rust_cqrses_bankaccount::inmemory_eventstore::StoredEvent;
 
 use diesel::prelude::*;
-use super::schema::{tbl_event_store, tbl_snapshot};
+use diesel::result::{Error as DieselError, DatabaseErrorKind};
+use super::schema::{tbl_event_store, tbl_snapshot, tbl_stream_head, tbl_stream_metadata};
 use super::db::{Conn, Pool};
-use super::eventpublisher::KafkaBankAccountEventPublisher;
+use super::telemetry::current_trace_context;
 
 pub struct MysqlBankAccountEventStore {
     pool: Pool,
-    publisher: KafkaBankAccountEventPublisher,
 }
 
 impl MysqlBankAccountEventStore {
-    pub fn new(pool: Pool, publisher: KafkaBankAccountEventPublisher) -> Self {
+    pub fn new(pool: Pool) -> Self {
         Self {
             pool,
-            publisher,
         }
     }
 
     pub fn get_conn(&self) -> Result<Conn, r2d2::Error> {
         self.pool.get()
     }
-}
 
-impl EventStore for MysqlBankAccountEventStore {
-    type Event = BankAccountEvent;
-    type EventStream = EventStream<Self::Event>;
-    type SnapshotData = BankAccount;
+    pub fn stream_ids_with_metadata(&self) -> Result<Vec<String>, EventStoreError> {
+        let conn = self.get_conn()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
 
-    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
-        -> Result<(), EventStoreError> {
+        tbl_stream_metadata::table
+            .select(tbl_stream_metadata::stream_id)
+            .order(tbl_stream_metadata::stream_id.asc())
+            .load::<String>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+    }
+
+    pub fn redact_plaintext_names(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        let conn = self.get_conn()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let metadata = Self::load_stream_metadata(&conn, &stream_id)?;
+            let records = tbl_event_store::table
+                .filter(tbl_event_store::stream_id.eq(&stream_id))
+                .order(tbl_event_store::stream_version.asc())
+                .for_update()
+                .load::<EventRecord>(&conn)?;
 
+            let mut previous_hash = metadata.last_purged
+                .map(|purged| purged.event_hash)
+                .unwrap_or_else(|| GENESIS_HASH.to_string());
+            let mut redacted = 0;
+            for record in records {
+                let event: BankAccountEvent = serde_json::from_str(&record.event_body).unwrap();
+                let redacted_event = redact_plaintext_event(event.clone());
+                let event_body = if redacted_event != event {
+                    redacted += 1;
+                    serde_json::to_string(&redacted_event).unwrap()
+                } else {
+                    record.event_body.clone()
+                };
+                let hash = event_hash(&previous_hash, &stream_id, record.stream_version, &record.event_type, &event_body,
+                                      record.event_metadata.as_deref());
+
+                if event_body != record.event_body || hash != record.event_hash {
+                    diesel::update(tbl_event_store::table.find(record.event_id))
+                        .set((tbl_event_store::event_body.eq(&event_body),
+                              tbl_event_store::event_hash.eq(&hash)))
+                        .execute(&conn)?;
+                    diesel::update(tbl_stream_head::table
+                                   .find(&stream_id)
+                                   .filter(tbl_stream_head::stream_version.eq(record.stream_version)))
+                        .set(tbl_stream_head::event_hash.eq(&hash))
+                        .execute(&conn)?;
+                }
+                previous_hash = hash;
+            }
+
+            let snapshot = tbl_snapshot::table
+                .find(&stream_id)
+                .first::<SnapshotRecord>(&conn)
+                .optional()?;
+            if let Some(snapshot) = snapshot {
+                let data: BankAccount = serde_json::from_str(&snapshot.data).unwrap();
+                let redacted_data = redact_plaintext_snapshot(data.clone());
+                if redacted_data != data {
+                    redacted += 1;
+                    diesel::update(tbl_snapshot::table.find(&stream_id))
+                        .set(tbl_snapshot::data.eq(serde_json::to_string(&redacted_data).unwrap()))
+                        .execute(&conn)?;
+                }
+            }
+            Ok(redacted)
+        }).map_err(|err| EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())))
+    }
+
+    fn load_stream_metadata(conn: &MysqlConnection, stream_id: &str) -> QueryResult<StreamMetadata> {
+        tbl_stream_metadata::table
+            .find(stream_id)
+            .first::<StreamMetadataRecord>(conn)
+            .optional()
+            .map(|record| record.map(StreamMetadata::from).unwrap_or_default())
+    }
+
+    fn append(&self, stream_id: String, stream_version: u64, events: Vec<BankAccountEvent>,
+              idempotency_key: Option<String>) -> Result<(), EventStoreError> {
         let conn = self.get_conn().unwrap();
 
+        let metadata = Self::load_stream_metadata(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        if metadata.deleted {
+            return Err(EventStoreErrorKind::StreamDeletedError(stream_id))?;
+        }
+        let snapshot_version = Self::load_snapshot_version(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        if stream_version <= snapshot_version {
+            return Err(EventStoreErrorKind::DuplicateEntryError(format!("{}:{}", stream_id, stream_version)))?;
+        }
+
+        let mut event_metadata = current_trace_context();
+        if let Some(idempotency_key) = idempotency_key {
+            event_metadata.insert(String::from("idempotency_key"), idempotency_key);
+        }
+        let event_metadata = if event_metadata.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&event_metadata).unwrap())
+        };
+
         conn.transaction::<_, diesel::result::Error, _>(|| {
-            let mut i: u64 = 0;
-            let mut stream_version = stream_version;
+            let mut previous_hash = tbl_event_store::table
+                .filter(tbl_event_store::stream_id.eq(&stream_id))
+                .order(tbl_event_store::stream_version.desc())
+                .select(tbl_event_store::event_hash)
+                .for_update()
+                .first::<String>(&conn)
+                .optional()?
+                .or_else(|| metadata.last_purged.as_ref().map(|purged| purged.event_hash.clone()))
+                .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+            for (i, event) in events.iter().enumerate() {
+                let stream_version = stream_version + i as u64;
 
-            for event in &events {
-                stream_version = stream_version + i;
+                let event_body = serde_json::to_string(&event).unwrap();
+                let hash = event_hash(&previous_hash, &stream_id, stream_version, event.event_type(), &event_body,
+                                      event_metadata.as_deref());
 
                 let new_event = NewEventRecord {
                     event_type: event.event_type(),
-                    event_body: &serde_json::to_string(&event).unwrap(),
+                    event_body: &event_body,
                     stream_id: &stream_id,
                     stream_version: stream_version,
                     event_occurred_at: event.occurred_at().naive_local(),
+                    event_hash: &hash,
+                    event_metadata: event_metadata.as_deref(),
                 };
 
                 diesel::insert_into(tbl_event_store::table)
                     .values(&new_event)
                     .execute(&conn)?;
 
-                i = i + 1;
+                previous_hash = hash;
             }
 
-            Ok(events)
-        }).map_err(|err| {
-            EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string()))
-        })
-        .and_then(|events| {
-            for event in events {
-                self.publisher.publish(event.clone());
+            if !events.is_empty() {
+                diesel::replace_into(tbl_stream_head::table)
+                    .values((
+                        tbl_stream_head::stream_id.eq(&stream_id),
+                        tbl_stream_head::stream_version.eq(stream_version + events.len() as u64 - 1),
+                        tbl_stream_head::event_hash.eq(&previous_hash),
+                        tbl_stream_head::updated_at.eq(Local::now().naive_local()),
+                    ))
+                    .execute(&conn)?;
             }
+
             Ok(())
+        }).map_err(|err| match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                EventStoreError::from(EventStoreErrorKind::DuplicateEntryError(
+                        format!("{}:{}", stream_id, stream_version)))
+            },
+            _ => EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())),
         })
     }
 
+    fn load_snapshot_version(conn: &MysqlConnection, stream_id: &str) -> QueryResult<u64> {
+        tbl_snapshot::table
+            .filter(tbl_snapshot::stream_id.eq(stream_id))
+            .select(tbl_snapshot::stream_version)
+            .first::<u64>(conn)
+            .optional()
+            .map(|stream_version| stream_version.unwrap_or(0))
+    }
+}
+
+impl EventStore for MysqlBankAccountEventStore {
+    type Event = BankAccountEvent;
+    type EventStream = EventStream<Self::Event>;
+    type SnapshotData = BankAccount;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        self.append(stream_id, stream_version, events, None)
+    }
+
     fn event_stream_since(&self, stream_id: String, stream_version: u64)
         -> Result<Self::EventStream, EventStoreError> {
         let conn = self.get_conn().unwrap();
 
-        tbl_event_store::table
+        let metadata = Self::load_stream_metadata(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        if metadata.deleted {
+            return Err(EventStoreErrorKind::StreamDeletedError(stream_id))?;
+        }
+        let snapshot_version = Self::load_snapshot_version(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+
+        let mut query = tbl_event_store::table
             .filter(tbl_event_store::stream_id.eq(stream_id.clone()))
-            .filter(tbl_event_store::stream_version.ge(stream_version))
+            .filter(tbl_event_store::stream_version.ge(metadata.first_readable_version(stream_version, snapshot_version)))
+            .into_boxed();
+        if let Some(expired_before) = metadata.expired_before(Local::now()) {
+            query = query.filter(tbl_event_store::event_occurred_at.ge(expired_before.naive_local())
+                                 .or(tbl_event_store::stream_version.gt(snapshot_version)));
+        }
+
+        query
             .order(tbl_event_store::stream_version.asc())
             .load::<EventRecord>(&conn)
             .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
@@ -145,6 +299,171 @@ impl EventStore for MysqlBankAccountEventStore {
                 }
             })
     }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        self.append(stream_id, stream_version, events, Some(idempotency_key))
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        let conn = self.get_conn().unwrap();
+
+        tbl_event_store::table
+            .filter(tbl_event_store::stream_id.eq(&stream_id))
+            .filter(tbl_event_store::event_metadata.is_not_null())
+            .order(tbl_event_store::stream_version.desc())
+            .select((tbl_event_store::stream_version, tbl_event_store::event_metadata))
+            .load::<(u64, Option<String>)>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+            .map(|records| {
+                records.into_iter()
+                    .find(|(_, event_metadata)| {
+                        event_metadata.as_deref()
+                            .and_then(|event_metadata| serde_json::from_str::<EventMetadata>(event_metadata).ok())
+                            .and_then(|event_metadata| event_metadata.idempotency_key)
+                            .is_some_and(|key| key == idempotency_key)
+                    })
+                    .map(|(stream_version, _)| stream_version)
+            })
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        let conn = self.get_conn().unwrap();
+
+        Self::load_stream_metadata(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        let conn = self.get_conn().unwrap();
+
+        let snapshot_version = Self::load_snapshot_version(&conn, &stream_id)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        metadata.check_truncation(snapshot_version)?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let last_purged = Self::load_stream_metadata(&conn, &stream_id)?.last_purged;
+
+            let new_metadata = StreamMetadataRecord {
+                stream_id: stream_id.clone(),
+                is_deleted: metadata.deleted,
+                truncate_before: metadata.truncate_before,
+                max_age: metadata.max_age,
+                updated_at: Local::now().naive_local(),
+                last_purged_version: last_purged.as_ref().map(|purged| purged.stream_version),
+                last_purged_hash: last_purged.map(|purged| purged.event_hash),
+            };
+
+            diesel::replace_into(tbl_stream_metadata::table)
+                .values(&new_metadata)
+                .execute(&conn)
+                .map(|_| ())
+        }).map_err(|err| EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())))
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        let conn = self.get_conn().unwrap();
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let metadata = Self::load_stream_metadata(&conn, &stream_id)?;
+
+            if metadata.deleted {
+                diesel::delete(tbl_snapshot::table.filter(tbl_snapshot::stream_id.eq(&stream_id)))
+                    .execute(&conn)?;
+                diesel::delete(tbl_stream_head::table.find(&stream_id))
+                    .execute(&conn)?;
+                return diesel::delete(tbl_event_store::table.filter(tbl_event_store::stream_id.eq(&stream_id)))
+                    .execute(&conn)
+                    .map(|count| count as u64);
+            }
+
+            let snapshot_version = Self::load_snapshot_version(&conn, &stream_id)?;
+            let mut purged = 0;
+            let mut last_purged = None;
+            if metadata.truncate_before.is_some() {
+                let truncated = tbl_event_store::table
+                    .filter(tbl_event_store::stream_id.eq(&stream_id))
+                    .filter(tbl_event_store::stream_version.lt(metadata.truncated_before(snapshot_version)));
+                let last_truncated = truncated.clone()
+                    .order(tbl_event_store::stream_version.desc())
+                    .select((tbl_event_store::stream_version, tbl_event_store::event_hash))
+                    .first::<(u64, String)>(&conn)
+                    .optional()?;
+                last_purged = std::cmp::max(last_purged, last_truncated);
+                purged += diesel::delete(truncated).execute(&conn)?;
+            }
+            if let Some(expired_before) = metadata.expired_before(Local::now()) {
+                let expired = tbl_event_store::table
+                    .filter(tbl_event_store::stream_id.eq(&stream_id))
+                    .filter(tbl_event_store::stream_version.le(snapshot_version))
+                    .filter(tbl_event_store::event_occurred_at.lt(expired_before.naive_local()));
+                let last_expired = expired.clone()
+                    .order(tbl_event_store::stream_version.desc())
+                    .select((tbl_event_store::stream_version, tbl_event_store::event_hash))
+                    .first::<(u64, String)>(&conn)
+                    .optional()?;
+                last_purged = std::cmp::max(last_purged, last_expired);
+                purged += diesel::delete(expired).execute(&conn)?;
+            }
+
+            if let Some((stream_version, event_hash)) = last_purged {
+                diesel::update(tbl_stream_metadata::table.find(&stream_id))
+                    .set((tbl_stream_metadata::last_purged_version.eq(Some(stream_version)),
+                          tbl_stream_metadata::last_purged_hash.eq(Some(event_hash))))
+                    .execute(&conn)?;
+            }
+            Ok(purged as u64)
+        }).map_err(|err| {
+            EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string()))
+        })
+    }
+}
+
+impl HashChainedEventStore for MysqlBankAccountEventStore {
+    fn stream_ids(&self) -> Result<Vec<String>, EventStoreError> {
+        let conn = self.get_conn()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+
+        let mut stream_ids = tbl_event_store::table
+            .select(tbl_event_store::stream_id)
+            .distinct()
+            .load::<String>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        stream_ids.extend(tbl_stream_head::table
+            .select(tbl_stream_head::stream_id)
+            .load::<String>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?);
+        stream_ids.sort();
+        stream_ids.dedup();
+        Ok(stream_ids)
+    }
+
+    fn stored_events(&self, stream_id: String) -> Result<Vec<StoredEvent>, EventStoreError> {
+        let conn = self.get_conn()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+
+        tbl_event_store::table
+            .filter(tbl_event_store::stream_id.eq(stream_id))
+            .order(tbl_event_store::stream_version.asc())
+            .load::<EventRecord>(&conn)
+            .map(|records| records.into_iter().map(StoredEvent::from).collect())
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+    }
+
+    fn chain_head(&self, stream_id: String) -> Result<Option<ChainHead>, EventStoreError> {
+        let conn = self.get_conn()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+
+        tbl_stream_head::table
+            .find(stream_id)
+            .select((tbl_stream_head::stream_version, tbl_stream_head::event_hash))
+            .first::<(u64, String)>(&conn)
+            .optional()
+            .map(|head| head.map(|(stream_version, event_hash)| ChainHead { stream_version, event_hash }))
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+    }
 }
 
 #[derive(Insertable)]
@@ -155,6 +474,8 @@ struct NewEventRecord<'a> {
     stream_id: &'a str,
     stream_version: u64,
     event_occurred_at: NaiveDateTime,
+    event_hash: &'a str,
+    event_metadata: Option<&'a str>,
 }
 
 #[derive(Debug, Queryable)]
@@ -165,6 +486,21 @@ struct EventRecord {
     stream_id: String,
     stream_version: u64,
     event_occurred_at: NaiveDateTime,
+    event_hash: String,
+    event_metadata: Option<String>,
+}
+
+impl From<EventRecord> for StoredEvent {
+    fn from(record: EventRecord) -> Self {
+        Self::new(
+            record.event_type,
+            record.event_body,
+            Local.from_local_datetime(&record.event_occurred_at).unwrap(),
+            record.stream_id,
+            record.stream_version,
+            record.event_hash,
+            ).with_event_metadata(record.event_metadata)
+    }
 }
 
 #[derive(Insertable)]
@@ -183,3 +519,32 @@ struct SnapshotRecord {
     data: String,
     created_at: NaiveDateTime,
 }
+
+#[derive(Insertable, Queryable)]
+#[table_name = "tbl_stream_metadata"]
+struct StreamMetadataRecord {
+    stream_id: String,
+    is_deleted: bool,
+    truncate_before: Option<u64>,
+    max_age: Option<u64>,
+    updated_at: NaiveDateTime,
+    last_purged_version: Option<u64>,
+    last_purged_hash: Option<String>,
+}
+
+impl From<StreamMetadataRecord> for StreamMetadata {
+    fn from(record: StreamMetadataRecord) -> Self {
+        Self {
+            deleted: record.is_deleted,
+            truncate_before: record.truncate_before,
+            max_age: record.max_age,
+            last_purged: match (record.last_purged_version, record.last_purged_hash) {
+                (Some(stream_version), Some(event_hash)) => Some(PurgedEvent {
+                    stream_version: stream_version,
+                    event_hash: event_hash,
+                }),
+                _ => None,
+            },
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::convert::TryFrom;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+use diesel::prelude::*;
+use diesel::result::{Error as DieselError, DatabaseErrorKind};
+
+use rust_cqrses_bankaccount::idempotency::{
+    CommandOutcome,
+    IdempotencyStore,
+    IdempotencyRecord,
+    IdempotencyError,
+    IdempotencyErrorKind,
+    Reservation,
+};
+
+use super::schema::tbl_idempotency_key;
+use super::db::{Conn, Pool};
+
+pub struct MysqlIdempotencyStore {
+    pool: Pool,
+}
+
+fn load_error(err: DieselError) -> IdempotencyError {
+    IdempotencyError::from(IdempotencyErrorKind::LoadError(err.to_string()))
+}
+
+fn save_error(err: DieselError) -> IdempotencyError {
+    IdempotencyError::from(IdempotencyErrorKind::SaveError(err.to_string()))
+}
+
+impl MysqlIdempotencyStore {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+        }
+    }
+
+    fn get_conn(&self) -> Result<Conn, IdempotencyError> {
+        self.pool.get()
+            .map_err(|err| IdempotencyError::from(IdempotencyErrorKind::LoadError(err.to_string())))
+    }
+}
+
+impl IdempotencyStore for MysqlIdempotencyStore {
+    fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+               expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError> {
+        let conn = self.get_conn()?;
+
+        let new_record = NewIdempotencyKeyRecord {
+            bank_account_id: bank_account_id,
+            idempotency_key: idempotency_key,
+            fingerprint: fingerprint,
+            outcome: None,
+            created_at: Local::now().naive_local(),
+        };
+
+        conn.transaction::<_, DieselError, _>(|| {
+            let record = tbl_idempotency_key::table
+                .find((bank_account_id, idempotency_key))
+                .select((tbl_idempotency_key::fingerprint, tbl_idempotency_key::outcome,
+                         tbl_idempotency_key::created_at))
+                .for_update()
+                .first::<IdempotencyKeyRecord>(&conn)
+                .optional()?
+                .map(IdempotencyRecord::try_from)
+                .transpose()
+                .map_err(|err| DieselError::DeserializationError(Box::new(err)))?;
+
+            match record {
+                Some(ref record) if record.created_at >= expired_before => Ok(record.reservation(fingerprint)),
+                Some(_) => {
+                    diesel::replace_into(tbl_idempotency_key::table)
+                        .values(&new_record)
+                        .execute(&conn)?;
+                    Ok(Reservation::Reserved)
+                },
+                None => {
+                    diesel::insert_into(tbl_idempotency_key::table)
+                        .values(&new_record)
+                        .execute(&conn)?;
+                    Ok(Reservation::Reserved)
+                },
+            }
+        }).or_else(|err| match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => Ok(Reservation::InProgress),
+            _ => Err(load_error(err)),
+        })
+    }
+
+    fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError> {
+        let conn = self.get_conn()?;
+
+        let outcome = serde_json::to_string(&outcome)
+            .map_err(|err| IdempotencyError::from(IdempotencyErrorKind::SaveError(err.to_string())))?;
+        let count = diesel::update(tbl_idempotency_key::table.find((bank_account_id, idempotency_key)))
+            .set(tbl_idempotency_key::outcome.eq(Some(outcome)))
+            .execute(&conn)
+            .map_err(save_error)?;
+
+        match count {
+            0 => Err(IdempotencyErrorKind::SaveError(
+                    format!("{}:{} is not reserved", bank_account_id, idempotency_key)))?,
+            _ => Ok(()),
+        }
+    }
+
+    fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError> {
+        let conn = self.get_conn()?;
+
+        diesel::delete(tbl_idempotency_key::table.find((bank_account_id, idempotency_key)))
+            .execute(&conn)
+            .map(|_| ())
+            .map_err(save_error)
+    }
+
+    fn purge_expired(&self, expired_before: DateTime<Local>) -> Result<u64, IdempotencyError> {
+        let conn = self.get_conn()?;
+
+        diesel::delete(tbl_idempotency_key::table
+                       .filter(tbl_idempotency_key::created_at.lt(expired_before.naive_local())))
+            .execute(&conn)
+            .map(|count| count as u64)
+            .map_err(save_error)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_idempotency_key"]
+struct NewIdempotencyKeyRecord<'a> {
+    bank_account_id: &'a str,
+    idempotency_key: &'a str,
+    fingerprint: &'a str,
+    outcome: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Queryable)]
+struct IdempotencyKeyRecord {
+    fingerprint: String,
+    outcome: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<IdempotencyKeyRecord> for IdempotencyRecord {
+    type Error = serde_json::Error;
+
+    fn try_from(record: IdempotencyKeyRecord) -> Result<Self, Self::Error> {
+        let outcome = match record.outcome {
+            Some(outcome) => Some(serde_json::from_str(&outcome)?),
+            None => None,
+        };
+        Ok(Self {
+            fingerprint: record.fingerprint,
+            outcome: outcome,
+            created_at: Local.from_local_datetime(&record.created_at).unwrap(),
+        })
+    }
+}
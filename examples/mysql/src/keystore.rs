@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use chrono::{Local, NaiveDateTime};
+
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use rust_cqrses_bankaccount::crypto::{
+    KeyStore,
+    FileKeyStore,
+    CryptoError,
+    CryptoErrorKind,
+    generate_key,
+};
+
+use super::schema::tbl_data_key;
+use super::db::{Conn, Pool};
+use super::{Config, KeyStoreType};
+
+pub struct MysqlKeyStore {
+    pool: Pool,
+}
+
+fn store_error(err: DieselError) -> CryptoError {
+    CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string()))
+}
+
+impl MysqlKeyStore {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+        }
+    }
+
+    fn get_conn(&self) -> Result<Conn, CryptoError> {
+        self.pool.get()
+            .map_err(|err| CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string())))
+    }
+
+    fn load(conn: &MysqlConnection, subject: &str) -> QueryResult<Option<Option<Vec<u8>>>> {
+        tbl_data_key::table
+            .find(subject)
+            .select(tbl_data_key::data_key)
+            .first::<Option<Vec<u8>>>(conn)
+            .optional()
+    }
+}
+
+impl KeyStore for MysqlKeyStore {
+    fn find_key(&self, subject: &str) -> Result<Option<Vec<u8>>, CryptoError> {
+        let conn = self.get_conn()?;
+
+        Self::load(&conn, subject)
+            .map(|key| key.unwrap_or(None))
+            .map_err(store_error)
+    }
+
+    fn create_key(&self, subject: &str) -> Result<Vec<u8>, CryptoError> {
+        let conn = self.get_conn()?;
+
+        let key = generate_key();
+        let new_key = NewDataKeyRecord {
+            subject: subject,
+            data_key: Some(&key),
+            created_at: Local::now().naive_local(),
+        };
+        diesel::insert_or_ignore_into(tbl_data_key::table)
+            .values(&new_key)
+            .execute(&conn)
+            .map_err(store_error)?;
+
+        match Self::load(&conn, subject).map_err(store_error)? {
+            Some(Some(key)) => Ok(key),
+            _ => Err(CryptoErrorKind::SubjectErasedError(subject.to_string()))?,
+        }
+    }
+
+    fn erase_key(&self, subject: &str) -> Result<(), CryptoError> {
+        let conn = self.get_conn()?;
+        let now = Local::now().naive_local();
+
+        diesel::replace_into(tbl_data_key::table)
+            .values((
+                tbl_data_key::subject.eq(subject),
+                tbl_data_key::data_key.eq(None::<Vec<u8>>),
+                tbl_data_key::created_at.eq(now),
+                tbl_data_key::erased_at.eq(Some(now)),
+            ))
+            .execute(&conn)
+            .map(|_| ())
+            .map_err(store_error)
+    }
+
+    fn is_erased(&self, subject: &str) -> Result<bool, CryptoError> {
+        let conn = self.get_conn()?;
+
+        Self::load(&conn, subject)
+            .map(|key| matches!(key, Some(None)))
+            .map_err(store_error)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_data_key"]
+struct NewDataKeyRecord<'a> {
+    subject: &'a str,
+    data_key: Option<&'a [u8]>,
+    created_at: NaiveDateTime,
+}
+
+pub fn create_key_store(config: &Config, pool: Pool) -> Arc<dyn KeyStore> {
+    match config.key_store {
+        KeyStoreType::Mysql => Arc::new(MysqlKeyStore::new(pool)),
+        KeyStoreType::File => {
+            let directory = config.key_store_dir.clone()
+                .expect("KEY_STORE_DIR is required for the file key store");
+            Arc::new(FileKeyStore::open(directory).expect("key store directory open error occurred"))
+        },
+    }
+}
@@ -1,18 +1,90 @@
 #[macro_use]
 extern crate diesel;
 
-extern crate elastic;
-#[macro_use]
-extern crate elastic_derive;
-
 use serde::Deserialize;
 
+use rust_cqrses_bankaccount::publishing_eventstore::PublishFailurePolicy;
+
 pub mod constants;
 pub mod schema;
 pub mod db;
 pub mod eventstore;
 pub mod eventpublisher;
 pub mod dao;
+pub mod checkpoint;
+pub mod consumer;
+pub mod deadletter;
+pub mod keystore;
+pub mod idempotency;
+pub mod monitoring;
+pub mod telemetry;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadModelStoreType {
+    Elasticsearch,
+    Mysql,
+}
+
+impl Default for ReadModelStoreType {
+    fn default() -> Self {
+        ReadModelStoreType::Elasticsearch
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointStoreType {
+    Memory,
+    Mysql,
+    File,
+}
+
+impl Default for CheckpointStoreType {
+    fn default() -> Self {
+        CheckpointStoreType::Mysql
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadLetterStoreType {
+    Kafka,
+    Mysql,
+}
+
+impl Default for DeadLetterStoreType {
+    fn default() -> Self {
+        DeadLetterStoreType::Mysql
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStoreType {
+    Mysql,
+    File,
+}
+
+impl Default for KeyStoreType {
+    fn default() -> Self {
+        KeyStoreType::Mysql
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceExporterType {
+    None,
+    Stdout,
+    Otlp,
+}
+
+impl Default for TraceExporterType {
+    fn default() -> Self {
+        TraceExporterType::None
+    }
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -24,5 +96,62 @@ pub struct Config {
 
     pub projector_kafka_consume_group: String,
 
-    pub elastic_search_endpoint: String,
+    #[serde(default)]
+    pub publish_failure_policy: PublishFailurePolicy,
+
+    #[serde(default)]
+    pub read_model_store: ReadModelStoreType,
+
+    pub elastic_search_endpoint: Option<String>,
+
+    #[serde(default)]
+    pub checkpoint_store: CheckpointStoreType,
+
+    pub checkpoint_dir: Option<String>,
+
+    #[serde(default = "default_consumer_batch_size")]
+    pub consumer_batch_size: usize,
+
+    #[serde(default = "default_consumer_min_backoff_ms")]
+    pub consumer_min_backoff_ms: u64,
+
+    #[serde(default = "default_consumer_max_backoff_ms")]
+    pub consumer_max_backoff_ms: u64,
+
+    #[serde(default = "default_consumer_max_retries")]
+    pub consumer_max_retries: u32,
+
+    #[serde(default)]
+    pub dead_letter_store: DeadLetterStoreType,
+
+    #[serde(default)]
+    pub key_store: KeyStoreType,
+
+    pub key_store_dir: Option<String>,
+
+    #[serde(default)]
+    pub trace_exporter: TraceExporterType,
+
+    #[serde(default = "default_idempotency_key_ttl_secs")]
+    pub idempotency_key_ttl_secs: i64,
+}
+
+fn default_consumer_batch_size() -> usize {
+    100
+}
+
+fn default_consumer_min_backoff_ms() -> u64 {
+    100
+}
+
+fn default_consumer_max_backoff_ms() -> u64 {
+    30000
+}
+
+fn default_consumer_max_retries() -> u32 {
+    10
+}
+
+fn default_idempotency_key_ttl_secs() -> i64 {
+    86400
 }
@@ -0,0 +1,10 @@
+use std::net::SocketAddr;
+use metrics_exporter_prometheus::PrometheusBuilder;
+
+pub fn install_metrics_exporter(addr: &str) {
+    let addr: SocketAddr = addr.parse().expect("invalid metrics address");
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .install()
+        .expect("metrics exporter install error occurred");
+}
@@ -1,3 +1,28 @@
+table! {
+    tbl_data_key (subject) {
+        subject -> Varchar,
+        data_key -> Nullable<Varbinary>,
+        created_at -> Datetime,
+        erased_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    tbl_dead_letter (dead_letter_id) {
+        dead_letter_id -> Unsigned<Bigint>,
+        consumer_group -> Varchar,
+        topic -> Varchar,
+        partition_id -> Integer,
+        message_offset -> Bigint,
+        message_key -> Blob,
+        payload -> Blob,
+        error -> Text,
+        retries -> Integer,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+    }
+}
+
 table! {
     tbl_event_store (event_id) {
         event_id -> Unsigned<Bigint>,
@@ -6,6 +31,39 @@ table! {
         stream_id -> Varchar,
         stream_version -> Unsigned<Bigint>,
         event_occurred_at -> Datetime,
+        event_hash -> Char,
+        event_metadata -> Nullable<Text>,
+    }
+}
+
+table! {
+    tbl_idempotency_key (bank_account_id, idempotency_key) {
+        bank_account_id -> Varchar,
+        idempotency_key -> Varchar,
+        fingerprint -> Char,
+        outcome -> Nullable<Text>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    tbl_bank_account_rm (bank_account_id) {
+        bank_account_id -> Varchar,
+        name -> Varchar,
+        is_closed -> Bool,
+        balance -> Integer,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+        version -> Unsigned<Bigint>,
+    }
+}
+
+table! {
+    tbl_projection_stream_version (projection, stream_id) {
+        projection -> Varchar,
+        stream_id -> Varchar,
+        stream_version -> Unsigned<Bigint>,
+        updated_at -> Datetime,
     }
 }
 
@@ -18,7 +76,35 @@ table! {
     }
 }
 
+table! {
+    tbl_stream_head (stream_id) {
+        stream_id -> Varchar,
+        stream_version -> Unsigned<Bigint>,
+        event_hash -> Char,
+        updated_at -> Datetime,
+    }
+}
+
+table! {
+    tbl_stream_metadata (stream_id) {
+        stream_id -> Varchar,
+        is_deleted -> Bool,
+        truncate_before -> Nullable<Unsigned<Bigint>>,
+        max_age -> Nullable<Unsigned<Bigint>>,
+        updated_at -> Datetime,
+        last_purged_version -> Nullable<Unsigned<Bigint>>,
+        last_purged_hash -> Nullable<Char>,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
+    tbl_data_key,
+    tbl_dead_letter,
+    tbl_bank_account_rm,
+    tbl_projection_stream_version,
     tbl_event_store,
+    tbl_idempotency_key,
     tbl_snapshot,
+    tbl_stream_head,
+    tbl_stream_metadata,
 );
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use opentelemetry::global;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use super::TraceExporterType;
+
+pub type TraceContext = HashMap<String, String>;
+
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("tracer provider shutdown error occurred: {}", err);
+            }
+        }
+    }
+}
+
+pub fn init_telemetry(service_name: &'static str, exporter: TraceExporterType) -> TelemetryGuard {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let resource = Resource::builder().with_service_name(service_name).build();
+    let provider = match exporter {
+        TraceExporterType::None => None,
+        TraceExporterType::Stdout => Some(SdkTracerProvider::builder()
+            .with_batch_exporter(opentelemetry_stdout::SpanExporter::default())
+            .with_resource(resource)
+            .build()),
+        TraceExporterType::Otlp => Some(SdkTracerProvider::builder()
+            .with_batch_exporter(opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .build()
+                .expect("otlp exporter build error occurred"))
+            .with_resource(resource)
+            .build()),
+    };
+
+    let otel = provider.as_ref()
+        .map(|provider| tracing_opentelemetry::layer()
+             .with_tracer(provider.tracer(service_name))
+             .with_filter(LevelFilter::INFO));
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env()))
+        .with(otel)
+        .init();
+
+    TelemetryGuard {
+        provider: provider,
+    }
+}
+
+pub fn current_trace_context() -> TraceContext {
+    let context = Span::current().context();
+    let mut carrier = TraceContext::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut carrier));
+    carrier
+}
+
+pub fn continue_trace(span: &Span, carrier: &TraceContext) {
+    if carrier.is_empty() {
+        return;
+    }
+    let context = global::get_text_map_propagator(|propagator| propagator.extract(carrier));
+    span.set_parent(context);
+}
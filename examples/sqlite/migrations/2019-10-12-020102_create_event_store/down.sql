@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS tbl_event_store;
+DROP TABLE IF EXISTS tbl_snapshot;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS tbl_event_store (
+    `event_id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    `event_body` TEXT NOT NULL,
+    `event_type` VARCHAR(250) NOT NULL,
+    `stream_id` VARCHAR(250) NOT NULL,
+    `stream_version` BIGINT NOT NULL,
+    `event_occurred_at` DATETIME NOT NULL,
+    UNIQUE (`stream_id`, `stream_version`)
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_store_stream_id ON tbl_event_store (`stream_id`);
+
+CREATE TABLE IF NOT EXISTS tbl_snapshot (
+    `stream_id` VARCHAR(250) NOT NULL PRIMARY KEY,
+    `stream_version` BIGINT NOT NULL,
+    `data` TEXT NOT NULL,
+    `created_at` DATETIME NOT NULL
+);
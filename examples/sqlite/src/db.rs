@@ -0,0 +1,29 @@
+use diesel::r2d2::{ConnectionManager, CustomizeConnection};
+use diesel::sqlite::SqliteConnection;
+use diesel::connection::SimpleConnection;
+
+pub type Conn = r2d2::PooledConnection<ConnectionManager<SqliteConnection>>;
+pub type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+
+embed_migrations!("migrations");
+
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute("PRAGMA busy_timeout = 5000; PRAGMA journal_mode = WAL;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+pub fn init_database_pool(database_url: &str) -> Pool {
+    let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+    let pool = Pool::builder()
+        .connection_customizer(Box::new(ConnectionOptions))
+        .build(manager)
+        .expect("db pool build error occurred");
+    let conn = pool.get().expect("db connection error occurred");
+    embedded_migrations::run(&conn).expect("db migration error occurred");
+    pool
+}
@@ -0,0 +1,333 @@
+use std::convert::TryFrom;
+use chrono::{Local, NaiveDateTime, TimeZone};
+
+use rust_cqrses_bankaccount::eventsourcing::{
+    EventStream,
+    Snapshot,
+    EventStoreError,
+    EventStoreErrorKind,
+    EventStore,
+};
+use rust_cqrses_bankaccount::aggregate::{BankAccountEvent, BankAccount};
+
+use diesel::prelude::*;
+use diesel::result::{Error as DieselError, DatabaseErrorKind};
+use super::schema::{tbl_event_store, tbl_snapshot};
+use super::db::{Conn, Pool};
+
+pub struct SqliteBankAccountEventStore {
+    pool: Pool,
+}
+
+impl SqliteBankAccountEventStore {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+        }
+    }
+
+    pub fn get_conn(&self) -> Result<Conn, EventStoreError> {
+        self.pool.get()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+    }
+
+    pub fn events_since_position(&self, position: u64, limit: i64)
+        -> Result<Vec<(u64, BankAccountEvent)>, EventStoreError> {
+        let position = match i64::try_from(position) {
+            Ok(position) => position,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let conn = self.get_conn()?;
+
+        tbl_event_store::table
+            .filter(tbl_event_store::event_id.gt(position))
+            .order(tbl_event_store::event_id.asc())
+            .limit(limit)
+            .load::<EventRecord>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?
+            .iter()
+            .map(|record| {
+                serde_json::from_str(&record.event_body)
+                    .map(|event| (record.event_id as u64, event))
+                    .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+            })
+            .collect()
+    }
+}
+
+impl EventStore for SqliteBankAccountEventStore {
+    type Event = BankAccountEvent;
+    type EventStream = EventStream<Self::Event>;
+    type SnapshotData = BankAccount;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        if events.is_empty() {
+            return Err(EventStoreErrorKind::NoEventsError)?;
+        }
+
+        let conn = self.get_conn()?;
+
+        conn.transaction::<_, DieselError, _>(|| {
+            for (i, event) in events.iter().enumerate() {
+                let new_event = NewEventRecord {
+                    event_type: event.event_type(),
+                    event_body: &serde_json::to_string(&event).unwrap(),
+                    stream_id: &stream_id,
+                    stream_version: (stream_version + i as u64) as i64,
+                    event_occurred_at: event.occurred_at().naive_local(),
+                };
+
+                diesel::insert_into(tbl_event_store::table)
+                    .values(&new_event)
+                    .execute(&conn)?;
+            }
+
+            Ok(())
+        }).map_err(|err| match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                EventStoreError::from(EventStoreErrorKind::DuplicateEntryError(
+                        format!("{}:{}", stream_id, stream_version)))
+            },
+            _ => EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())),
+        })
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        let conn = self.get_conn()?;
+
+        tbl_event_store::table
+            .filter(tbl_event_store::stream_id.eq(stream_id.clone()))
+            .filter(tbl_event_store::stream_version.ge(stream_version as i64))
+            .order(tbl_event_store::stream_version.asc())
+            .load::<EventRecord>(&conn)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+            .and_then(|event_records| {
+                if event_records.is_empty() {
+                    return Err(EventStoreErrorKind::NoEventStreamError(stream_id.clone(), stream_version))?;
+                }
+
+                let events = event_records.iter()
+                    .map(|event_record| serde_json::from_str(&event_record.event_body))
+                    .collect::<Result<Vec<BankAccountEvent>, _>>()
+                    .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+                let version = event_records.last().unwrap().stream_version as u64;
+                Ok(EventStream::new(events, version))
+            })
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        let conn = self.get_conn()?;
+
+        let new_snapshot = NewSnapshotRecord {
+            stream_id: snapshot.stream_id(),
+            stream_version: snapshot.stream_version() as i64,
+            data: &serde_json::to_string(snapshot.snapshot()).unwrap(),
+            created_at: snapshot.created_at().naive_local(),
+        };
+
+        diesel::replace_into(tbl_snapshot::table)
+            .values(&new_snapshot)
+            .execute(&conn)
+            .map(|_| ())
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())))
+    }
+
+    fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        let conn = self.get_conn()?;
+
+        tbl_snapshot::table
+            .filter(tbl_snapshot::stream_id.eq(stream_id.clone()))
+            .first::<SnapshotRecord>(&conn)
+            .optional()
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))
+            .and_then(|result| {
+                match result {
+                    Some(record) => {
+                        let data = serde_json::from_str(&record.data)
+                            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+                        Ok(Some(Snapshot::new(
+                            record.stream_id,
+                            record.stream_version as u64,
+                            data,
+                            Local.from_local_datetime(&record.created_at).unwrap(),
+                            )))
+                    },
+                    None => Ok(None),
+                }
+            })
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_event_store"]
+struct NewEventRecord<'a> {
+    event_type: &'a str,
+    event_body: &'a str,
+    stream_id: &'a str,
+    stream_version: i64,
+    event_occurred_at: NaiveDateTime,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Queryable)]
+struct EventRecord {
+    event_id: i64,
+    event_body: String,
+    event_type: String,
+    stream_id: String,
+    stream_version: i64,
+    event_occurred_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "tbl_snapshot"]
+struct NewSnapshotRecord<'a> {
+    stream_id: &'a str,
+    stream_version: i64,
+    data: &'a str,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Queryable)]
+struct SnapshotRecord {
+    stream_id: String,
+    stream_version: i64,
+    data: String,
+    created_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use tempfile::TempDir;
+
+    use rust_cqrses_bankaccount::eventsourcing::{EventStore, EventStoreErrorKind, Snapshot};
+    use rust_cqrses_bankaccount::aggregate::{BankAccountEvent, BankAccountId, BankAccountName, BankAccountAggregate};
+    use rust_cqrses_bankaccount::usecase::command::BankAccountAggregateUseCase;
+    use rust_cqrses_bankaccount::testing::verify_event_store;
+
+    use super::SqliteBankAccountEventStore;
+    use super::super::db;
+
+    fn create_store(dir: &TempDir) -> SqliteBankAccountEventStore {
+        let database_url = dir.path().join("event_store.db");
+        SqliteBankAccountEventStore::new(db::init_database_pool(database_url.to_str().unwrap()))
+    }
+
+    fn bank_account_id() -> BankAccountId {
+        BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap()
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        let dir = TempDir::new().unwrap();
+        let store = create_store(&dir);
+
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id());
+
+        let events = vec![
+            BankAccountEvent::Opened {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("foo")).unwrap(),
+                occurred_at: Local::now(),
+            },
+            BankAccountEvent::Deposited {
+                bank_account_id: bank_account_id(),
+                deposit: 500,
+                occurred_at: Local::now(),
+            },
+        ];
+        assert!(store.append_event_stream(stream_id.clone(), 1, events.clone()).is_ok());
+
+        let stream = store.event_stream_since(stream_id.clone(), 1).unwrap();
+        assert_eq!(stream.events(), &events);
+        assert_eq!(stream.version(), 2);
+
+        let stream = store.event_stream_since(stream_id.clone(), 2).unwrap();
+        assert_eq!(stream.events().len(), 1);
+
+        match store.event_stream_since(stream_id.clone(), 3) {
+            Err(err) => assert!(matches!(err.kind(), EventStoreErrorKind::NoEventStreamError(_, _))),
+            Ok(_) => panic!("Read past the end of the stream"),
+        };
+
+        let positions: Vec<u64> = store.events_since_position(0, 10).unwrap()
+            .iter()
+            .map(|(position, _)| *position)
+            .collect();
+        assert_eq!(positions, vec![1, 2]);
+        assert_eq!(store.events_since_position(1, 10).unwrap().len(), 1);
+        assert!(store.events_since_position(u64::MAX, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_store_behaviour() {
+        let dir = TempDir::new().unwrap();
+        verify_event_store(&create_store(&dir));
+    }
+
+    #[test]
+    fn test_sqlite_store_rejects_concurrent_append() {
+        let dir = TempDir::new().unwrap();
+        let store = create_store(&dir);
+
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id());
+        let deposited = BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id(),
+            deposit: 500,
+            occurred_at: Local::now(),
+        };
+
+        assert!(store.append_event_stream(stream_id.clone(), 1, vec![deposited.clone()]).is_ok());
+        match store.append_event_stream(stream_id.clone(), 1, vec![deposited.clone()]) {
+            Err(err) => assert!(matches!(err.kind(), EventStoreErrorKind::DuplicateEntryError(_))),
+            Ok(_) => panic!("Concurrent append was accepted"),
+        };
+        assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().events().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_store_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let store = create_store(&dir);
+
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id());
+        assert!(store.read_snapshot(stream_id.clone()).unwrap().is_none());
+
+        let events = vec![
+            BankAccountEvent::Opened {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("foo")).unwrap(),
+                occurred_at: Local::now(),
+            },
+        ];
+        let aggregate = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), events, 1).unwrap();
+        let snapshot = Snapshot::new(stream_id.clone(), 1, aggregate.state().clone().unwrap(), Local::now());
+        assert!(store.record_snapshot(snapshot.clone()).is_ok());
+        assert!(store.record_snapshot(snapshot).is_ok());
+
+        let snapshot = store.read_snapshot(stream_id.clone()).unwrap().unwrap();
+        assert_eq!(snapshot.stream_version(), 1);
+        assert_eq!(Some(snapshot.snapshot()), aggregate.state().as_ref());
+    }
+
+    #[test]
+    fn test_sqlite_store_with_usecase() {
+        let dir = TempDir::new().unwrap();
+        let usecase = BankAccountAggregateUseCase::new(Box::new(create_store(&dir)));
+
+        assert!(usecase.open(bank_account_id(), BankAccountName::new(String::from("foo")).unwrap()).is_ok());
+        assert!(usecase.deposit(bank_account_id(), 1000).is_ok());
+        assert!(usecase.withdraw(bank_account_id(), 300).is_ok());
+        assert!(usecase.withdraw(bank_account_id(), 800).is_err());
+
+        let usecase = BankAccountAggregateUseCase::new(Box::new(create_store(&dir)));
+        let aggregate = usecase.get(bank_account_id()).unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().balance(), 700);
+        assert_eq!(aggregate.version(), 3);
+    }
+}
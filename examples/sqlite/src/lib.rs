@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
+pub mod schema;
+pub mod db;
+pub mod eventstore;
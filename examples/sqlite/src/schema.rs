@@ -0,0 +1,24 @@
+table! {
+    tbl_event_store (event_id) {
+        event_id -> BigInt,
+        event_body -> Text,
+        event_type -> Text,
+        stream_id -> Text,
+        stream_version -> BigInt,
+        event_occurred_at -> Timestamp,
+    }
+}
+
+table! {
+    tbl_snapshot (stream_id) {
+        stream_id -> Text,
+        stream_version -> BigInt,
+        data -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    tbl_event_store,
+    tbl_snapshot,
+);
@@ -3,14 +3,14 @@ use chrono::{Local, DateTime};
 use uuid::Uuid;
 use failure::{Fail, Context, Backtrace};
 use serde::{Serialize, Deserialize};
-use super::eventsourcing::Snapshot;
+use super::eventsourcing::{Snapshot, Aggregate};
 
 #[derive(Debug)]
 pub struct Error {
     inner: Context<ErrorKind>,
 }
 
-#[derive(Clone, Debug, Fail)]
+#[derive(Clone, Debug, PartialEq, Fail, Serialize, Deserialize)]
 pub enum ErrorKind {
     #[fail(display = "Invalid bank account id: {:?}", _0)]
     InvalidBankAccountId(String),
@@ -33,6 +33,15 @@ pub enum ErrorKind {
     #[fail(display = "Forbidden that deposit amount to negative: id = {:?}, money = {:?}", _0, _1)]
     NegativeBalance(BankAccountId, i32),
 
+    #[fail(display = "A negative deposit amount is illegal: id = {:?}, money = {:?}", _0, _1)]
+    NegativeDeposit(BankAccountId, i32),
+
+    #[fail(display = "A negative withdraw amount is illegal: id = {:?}, money = {:?}", _0, _1)]
+    NegativeWithdraw(BankAccountId, i32),
+
+    #[fail(display = "Balance would overflow: id = {:?}, money = {:?}", _0, _1)]
+    BalanceOverflow(BankAccountId, i32),
+
     #[fail(display = "Invalid state: {:?}", _0)]
     InvalidState(BankAccountId),
 }
@@ -71,7 +80,7 @@ impl From<Context<ErrorKind>> for Error {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BankAccountEvent {
     Opened {
         bank_account_id: BankAccountId,
@@ -110,6 +119,16 @@ impl BankAccountEvent {
         }
     }
 
+    pub fn bank_account_id(&self) -> &BankAccountId {
+        match self {
+            Self::Opened {bank_account_id, name: _, occurred_at: _} => bank_account_id,
+            Self::Updated {bank_account_id, name: _, occurred_at: _} => bank_account_id,
+            Self::Deposited {bank_account_id, deposit: _, occurred_at: _} => bank_account_id,
+            Self::Withdrawn {bank_account_id, withdraw: _, occurred_at: _} => bank_account_id,
+            Self::Closed {bank_account_id, occurred_at: _} => bank_account_id,
+        }
+    }
+
     pub fn occurred_at(&self) -> DateTime<Local> {
         match self {
             Self::Opened {bank_account_id: _, name: _, occurred_at} => occurred_at.clone(),
@@ -144,6 +163,28 @@ pub enum BankAccountCommand {
     },
 }
 
+impl BankAccountCommand {
+    pub fn command_type(&self) -> &'static str {
+        match self {
+            Self::Open {bank_account_id: _, name: _} => "OpenBankAccount",
+            Self::Update {bank_account_id: _, name: _} => "UpdateBankAccount",
+            Self::Deposit {bank_account_id: _, deposit: _} => "DepositBankAccount",
+            Self::Withdraw {bank_account_id: _, withdraw: _} => "WithdrawBankAccount",
+            Self::Close {bank_account_id: _} => "CloseBankAccount",
+        }
+    }
+
+    pub fn bank_account_id(&self) -> &BankAccountId {
+        match self {
+            Self::Open {bank_account_id, name: _} => bank_account_id,
+            Self::Update {bank_account_id, name: _} => bank_account_id,
+            Self::Deposit {bank_account_id, deposit: _} => bank_account_id,
+            Self::Withdraw {bank_account_id, withdraw: _} => bank_account_id,
+            Self::Close {bank_account_id} => bank_account_id,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BankAccountId {
     value: Uuid,
@@ -168,6 +209,9 @@ impl fmt::Display for BankAccountId {
     }
 }
 
+pub const REDACTED_NAME: &str = "[erased]";
+pub const ENCRYPTED_NAME_PREFIX: &str = "enc:v1:";
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BankAccountName {
     value: String,
@@ -175,16 +219,33 @@ pub struct BankAccountName {
 
 impl BankAccountName {
     pub fn new(value: String) -> Result<Self, Error> {
-        if !value.is_empty() && value.len() < 255 {
+        let reserved = value == REDACTED_NAME || value.starts_with(ENCRYPTED_NAME_PREFIX);
+        if !value.is_empty() && value.len() < 255 && !reserved {
             Ok(Self { value: value })
         } else {
             Err(ErrorKind::InvalidBankAccountName(value))?
         }
     }
 
+    pub fn redacted() -> Self {
+        Self { value: String::from(REDACTED_NAME) }
+    }
+
+    pub(crate) fn unchecked(value: String) -> Self {
+        Self { value }
+    }
+
     pub fn value(&self) -> &str {
         &self.value
     }
+
+    pub fn is_redacted(&self) -> bool {
+        self.value == REDACTED_NAME
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.value.starts_with(ENCRYPTED_NAME_PREFIX)
+    }
 }
 
 impl fmt::Display for BankAccountName {
@@ -193,7 +254,7 @@ impl fmt::Display for BankAccountName {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BankAccount {
     id: BankAccountId,
     name: BankAccountName,
@@ -246,61 +307,79 @@ impl BankAccount {
         &self.updated_at
     }
 
-    pub fn with_name(&self, name: BankAccountName, occurred_at: DateTime<Local>)
-        -> Result<Self, Error> {
+    pub fn validate_update(&self) -> Result<(), Error> {
         if self.is_closed {
             Err(ErrorKind::AlreadyClosed(self.id.clone()))?
         } else {
-            Ok(Self {
-                name: name,
-                updated_at: occurred_at,
-                .. self.clone()
-            })
+            Ok(())
         }
     }
 
-    pub fn deposit(&self, deposit: i32, occurred_at: DateTime<Local>)
-        -> Result<Self, Error> {
+    pub fn validate_deposit(&self, deposit: i32) -> Result<(), Error> {
         if self.is_closed {
             Err(ErrorKind::AlreadyClosed(self.id.clone()))?
         } else if deposit == 0 {
             Err(ErrorKind::DepositZero(self.id.clone(), deposit))?
-        } else if (self.balance + deposit) < 0 {
-            Err(ErrorKind::NegativeBalance(self.id.clone(), deposit))?
+        } else if deposit < 0 {
+            Err(ErrorKind::NegativeDeposit(self.id.clone(), deposit))?
+        } else if self.balance.checked_add(deposit).is_none() {
+            Err(ErrorKind::BalanceOverflow(self.id.clone(), deposit))?
         } else {
-            Ok(Self {
-                balance: self.balance + deposit,
-                updated_at: occurred_at,
-                .. self.clone()
-            })
+            Ok(())
         }
     }
 
-    pub fn withdraw(&self, withdraw: i32, occurred_at: DateTime<Local>) -> Result<Self, Error> {
+    pub fn validate_withdraw(&self, withdraw: i32) -> Result<(), Error> {
         if self.is_closed {
             Err(ErrorKind::AlreadyClosed(self.id.clone()))?
         } else if withdraw == 0 {
             Err(ErrorKind::DepositZero(self.id.clone(), withdraw))?
+        } else if withdraw < 0 {
+            Err(ErrorKind::NegativeWithdraw(self.id.clone(), withdraw))?
         } else if (self.balance - withdraw) < 0 {
             Err(ErrorKind::NegativeBalance(self.id.clone(), withdraw))?
         } else {
-            Ok(Self {
-                balance: self.balance - withdraw,
-                updated_at: occurred_at,
-                .. self.clone()
-            })
+            Ok(())
         }
     }
 
-    pub fn close(&self, occurred_at: DateTime<Local>) -> Result<Self, Error> {
+    pub fn validate_close(&self) -> Result<(), Error> {
         if self.is_closed {
             Err(ErrorKind::AlreadyClosed(self.id.clone()))?
         } else {
-            Ok(Self {
-                is_closed: true,
-                updated_at: occurred_at,
-                .. self.clone()
-            })
+            Ok(())
+        }
+    }
+
+    pub fn with_name(&self, name: BankAccountName, occurred_at: DateTime<Local>) -> Self {
+        Self {
+            name,
+            updated_at: occurred_at,
+            .. self.clone()
+        }
+    }
+
+    pub fn deposit(&self, deposit: i32, occurred_at: DateTime<Local>) -> Self {
+        Self {
+            balance: self.balance.wrapping_add(deposit),
+            updated_at: occurred_at,
+            .. self.clone()
+        }
+    }
+
+    pub fn withdraw(&self, withdraw: i32, occurred_at: DateTime<Local>) -> Self {
+        Self {
+            balance: self.balance.wrapping_sub(withdraw),
+            updated_at: occurred_at,
+            .. self.clone()
+        }
+    }
+
+    pub fn close(&self, occurred_at: DateTime<Local>) -> Self {
+        Self {
+            is_closed: true,
+            updated_at: occurred_at,
+            .. self.clone()
         }
     }
 }
@@ -323,6 +402,11 @@ impl BankAccountAggregate {
         format!("bank_account:{}", bank_account_id)
     }
 
+    pub fn bank_account_id_of(stream_id: &str) -> Option<BankAccountId> {
+        stream_id.strip_prefix("bank_account:")
+            .and_then(|id| BankAccountId::new(id.to_string()).ok())
+    }
+
     pub fn load(bank_account: BankAccount, version: u64) -> Self {
         Self {
             state: Some(bank_account),
@@ -365,116 +449,86 @@ impl BankAccountAggregate {
                 }
             }
             BankAccountCommand::Update{ bank_account_id, name } => {
-                if aggregate.equals_id(&bank_account_id) {
-                    Ok(vec![
-                        BankAccountEvent::Updated {
-                            bank_account_id: bank_account_id,
-                            name: name,
-                            occurred_at: Local::now(),
-                        }
-                    ])
-                } else {
-                    Err(ErrorKind::InvalidState(bank_account_id))?
-                }
+                aggregate.opened_state(&bank_account_id)?.validate_update()?;
+                Ok(vec![
+                    BankAccountEvent::Updated {
+                        bank_account_id,
+                        name,
+                        occurred_at: Local::now(),
+                    }
+                ])
             },
             BankAccountCommand::Deposit{ bank_account_id, deposit } => {
-                if aggregate.equals_id(&bank_account_id) {
-                    Ok(vec![
-                        BankAccountEvent::Deposited {
-                            bank_account_id: bank_account_id,
-                            deposit: deposit,
-                            occurred_at: Local::now(),
-                        }
-                    ])
-                } else {
-                    Err(ErrorKind::InvalidState(bank_account_id))?
-                }
+                aggregate.opened_state(&bank_account_id)?.validate_deposit(deposit)?;
+                Ok(vec![
+                    BankAccountEvent::Deposited {
+                        bank_account_id,
+                        deposit,
+                        occurred_at: Local::now(),
+                    }
+                ])
             },
             BankAccountCommand::Withdraw{ bank_account_id, withdraw } => {
-                if aggregate.equals_id(&bank_account_id) {
-                    Ok(vec![
-                        BankAccountEvent::Withdrawn {
-                            bank_account_id: bank_account_id,
-                            withdraw: withdraw,
-                            occurred_at: Local::now(),
-                        }
-                    ])
-                } else {
-                    Err(ErrorKind::InvalidState(bank_account_id))?
-                }
+                aggregate.opened_state(&bank_account_id)?.validate_withdraw(withdraw)?;
+                Ok(vec![
+                    BankAccountEvent::Withdrawn {
+                        bank_account_id,
+                        withdraw,
+                        occurred_at: Local::now(),
+                    }
+                ])
             },
             BankAccountCommand::Close{ bank_account_id } => {
-                if aggregate.equals_id(&bank_account_id) {
-                    Ok(vec![
-                        BankAccountEvent::Closed {
-                            bank_account_id: bank_account_id,
-                            occurred_at: Local::now(),
-                        }
-                    ])
-                } else {
-                    Err(ErrorKind::InvalidState(bank_account_id))?
-                }
+                aggregate.opened_state(&bank_account_id)?.validate_close()?;
+                Ok(vec![
+                    BankAccountEvent::Closed {
+                        bank_account_id,
+                        occurred_at: Local::now(),
+                    }
+                ])
             },
         }
     }
 
     pub fn apply_event(aggregate: &Self, event: BankAccountEvent)
         -> Result<Self, Error> {
-        match event {
+        let state = match event {
             BankAccountEvent::Opened{ bank_account_id, name, occurred_at } => {
                 match aggregate.state() {
                     Some(_) => Err(ErrorKind::AlreadyOpened(bank_account_id))?,
-                    None => {
-                        Ok(Self {
-                            state: Some(BankAccount::new(
-                                bank_account_id.clone(),
-                                name.clone(),
-                                false,
-                                0,
-                                occurred_at.clone(),
-                                occurred_at.clone(),
-                                )),
-                            version: 0,
-                        })
-                    },
+                    None => BankAccount::new(bank_account_id, name, false, 0, occurred_at, occurred_at),
                 }
             },
             BankAccountEvent::Updated{ bank_account_id: _, name, occurred_at } => {
-                aggregate.state().as_ref().unwrap().with_name(name.clone(), occurred_at.clone())
-                    .and_then(|new_state| {
-                        Ok(Self {
-                            state: Some(new_state),
-                            version: aggregate.version(),
-                        })
-                    })
+                aggregate.current_state()?.with_name(name, occurred_at)
             },
             BankAccountEvent::Deposited{ bank_account_id: _, deposit, occurred_at } => {
-                aggregate.state().as_ref().unwrap().deposit(deposit, occurred_at.clone())
-                    .and_then(|new_state| {
-                        Ok(Self {
-                            state: Some(new_state),
-                            version: aggregate.version(),
-                        })
-                    })
+                aggregate.current_state()?.deposit(deposit, occurred_at)
             },
             BankAccountEvent::Withdrawn{ bank_account_id: _, withdraw, occurred_at } => {
-                aggregate.state().as_ref().unwrap().withdraw(withdraw, occurred_at.clone())
-                    .and_then(|new_state| {
-                        Ok(Self {
-                            state: Some(new_state),
-                            version: aggregate.version(),
-                        })
-                    })
+                aggregate.current_state()?.withdraw(withdraw, occurred_at)
             },
             BankAccountEvent::Closed{ bank_account_id: _, occurred_at } => {
-                aggregate.state().as_ref().unwrap().close(occurred_at.clone())
-                    .and_then(|new_state| {
-                        Ok(Self {
-                            state: Some(new_state),
-                            version: aggregate.version(),
-                        })
-                    })
+                aggregate.current_state()?.close(occurred_at)
             },
+        };
+        Ok(Self {
+            state: Some(state),
+            version: aggregate.version(),
+        })
+    }
+
+    fn opened_state(&self, bank_account_id: &BankAccountId) -> Result<&BankAccount, Error> {
+        match &self.state {
+            Some(ba) if ba.id() == bank_account_id => Ok(ba),
+            _ => Err(ErrorKind::InvalidState(bank_account_id.clone()))?,
+        }
+    }
+
+    fn current_state(&self) -> Result<&BankAccount, Error> {
+        match &self.state {
+            Some(ba) => Ok(ba),
+            None => Err(ErrorKind::NotYetOpened)?,
         }
     }
 
@@ -506,6 +560,33 @@ impl BankAccountAggregate {
     }
 }
 
+impl Default for BankAccountAggregate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Aggregate for BankAccountAggregate {
+    type Command = BankAccountCommand;
+    type Event = BankAccountEvent;
+    type Error = Error;
+
+    fn handle_command(aggregate: &Self, command: Self::Command)
+        -> Result<Vec<Self::Event>, Self::Error> {
+        BankAccountAggregate::handle_command(aggregate, command)
+    }
+
+    fn apply_event(aggregate: &Self, event: Self::Event)
+        -> Result<Self, Self::Error> {
+        BankAccountAggregate::apply_event(aggregate, event)
+    }
+
+    fn load_from_history(aggregate: &Self, history: Vec<Self::Event>, version: u64)
+        -> Result<Self, Self::Error> {
+        BankAccountAggregate::load_from_history(aggregate, history, version)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::{Rng, thread_rng};
@@ -513,11 +594,14 @@ mod tests {
     use chrono::Local;
     use uuid::Uuid;
 
+    use super::super::testing::given;
     use super::ErrorKind;
+    use super::BankAccountEvent;
     use super::BankAccountCommand;
     use super::BankAccountId;
     use super::BankAccountName;
     use super::BankAccount;
+    use super::REDACTED_NAME;
     use super::BankAccountAggregate;
 
     fn create_bank_account(is_closed: bool, balance: i32) -> BankAccount {
@@ -535,7 +619,11 @@ mod tests {
     fn test_new_bank_account_id() {
         let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8"));
         assert!(bank_account_id.is_ok());
-        assert_eq!(bank_account_id.unwrap().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let bank_account_id = bank_account_id.unwrap();
+        assert_eq!(bank_account_id.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(BankAccountAggregate::bank_account_id_of(&BankAccountAggregate::stream_id(&bank_account_id)),
+                   Some(bank_account_id));
+        assert!(BankAccountAggregate::bank_account_id_of("67e55044-10b1-426f-9247-bb680e5fe0c8").is_none());
 
         match BankAccountId::new(String::from("!")) {
             Err(err) => match err.kind() {
@@ -561,18 +649,24 @@ mod tests {
             },
             _ => assert!(false),
         };
+
+        for reserved in [REDACTED_NAME, "enc:v1:Zm9v"] {
+            match BankAccountName::new(String::from(reserved)) {
+                Err(err) => assert!(matches!(err.kind(), ErrorKind::InvalidBankAccountName(_))),
+                Ok(_) => panic!("Reserved name was accepted: {}", reserved),
+            };
+        }
     }
 
     #[test]
     fn test_bank_account_with_name() {
         let bank_account = create_bank_account(false, 0);
-        match bank_account.with_name(BankAccountName::new(String::from("bar")).unwrap(), Local::now()) {
-            Ok(ba) => assert_eq!(ba.name().value(), "bar"),
-            _ => assert!(false),
-        };
+        assert!(bank_account.validate_update().is_ok());
+        let ba = bank_account.with_name(BankAccountName::new(String::from("bar")).unwrap(), Local::now());
+        assert_eq!(ba.name().value(), "bar");
 
         let bank_account = create_bank_account(true, 0);
-        match bank_account.with_name(BankAccountName::new(String::from("bar")).unwrap(), Local::now()) {
+        match bank_account.validate_update() {
             Err(err) => match err.kind() {
                 ErrorKind::AlreadyClosed(_) => assert!(true),
                 _ => assert!(false),
@@ -584,13 +678,11 @@ mod tests {
     #[test]
     fn test_bank_account_deposit() {
         let bank_account = create_bank_account(false, 0);
-        match bank_account.deposit(500, Local::now()) {
-            Ok(ba) => assert_eq!(ba.balance(), 500),
-            _ => assert!(false),
-        };
+        assert!(bank_account.validate_deposit(500).is_ok());
+        assert_eq!(bank_account.deposit(500, Local::now()).balance(), 500);
 
         let bank_account = create_bank_account(true, 0);
-        match bank_account.deposit(500, Local::now()) {
+        match bank_account.validate_deposit(500) {
             Err(err) => match err.kind() {
                 ErrorKind::AlreadyClosed(_) => assert!(true),
                 _ => assert!(false),
@@ -599,7 +691,7 @@ mod tests {
         };
 
         let bank_account = create_bank_account(false, 0);
-        match bank_account.deposit(0, Local::now()) {
+        match bank_account.validate_deposit(0) {
             Err(err) => match err.kind() {
                 ErrorKind::DepositZero(_, _) => assert!(true),
                 _ => assert!(false),
@@ -607,26 +699,27 @@ mod tests {
             _ => assert!(false),
         };
 
-        let bank_account = create_bank_account(false, 0);
-        match bank_account.deposit(-500, Local::now()) {
-            Err(err) => match err.kind() {
-                ErrorKind::NegativeBalance(_, _) => assert!(true),
-                _ => assert!(false),
-            },
-            _ => assert!(false),
+        let bank_account = create_bank_account(false, 1000);
+        match bank_account.validate_deposit(-500) {
+            Err(err) => assert!(matches!(err.kind(), ErrorKind::NegativeDeposit(_, _))),
+            Ok(_) => panic!("Negative deposit was accepted"),
+        };
+
+        let bank_account = create_bank_account(false, i32::MAX);
+        match bank_account.validate_deposit(1) {
+            Err(err) => assert!(matches!(err.kind(), ErrorKind::BalanceOverflow(_, _))),
+            Ok(_) => panic!("Overflowing deposit was accepted"),
         };
     }
 
     #[test]
     fn test_bank_account_withdraw() {
         let bank_account = create_bank_account(false, 1000);
-        match bank_account.withdraw(500, Local::now()) {
-            Ok(ba) => assert_eq!(ba.balance(), 500),
-            _ => assert!(false),
-        };
+        assert!(bank_account.validate_withdraw(500).is_ok());
+        assert_eq!(bank_account.withdraw(500, Local::now()).balance(), 500);
 
         let bank_account = create_bank_account(true, 1000);
-        match bank_account.withdraw(500, Local::now()) {
+        match bank_account.validate_withdraw(500) {
             Err(err) => match err.kind() {
                 ErrorKind::AlreadyClosed(_) => assert!(true),
                 _ => assert!(false),
@@ -635,7 +728,7 @@ mod tests {
         };
 
         let bank_account = create_bank_account(false, 1000);
-        match bank_account.withdraw(0, Local::now()) {
+        match bank_account.validate_withdraw(0) {
             Err(err) => match err.kind() {
                 ErrorKind::DepositZero(_, _) => assert!(true),
                 _ => assert!(false),
@@ -644,7 +737,13 @@ mod tests {
         };
 
         let bank_account = create_bank_account(false, 1000);
-        match bank_account.withdraw(1100, Local::now()) {
+        match bank_account.validate_withdraw(-500) {
+            Err(err) => assert!(matches!(err.kind(), ErrorKind::NegativeWithdraw(_, _))),
+            Ok(_) => panic!("Negative withdraw was accepted"),
+        };
+
+        let bank_account = create_bank_account(false, 1000);
+        match bank_account.validate_withdraw(1100) {
             Err(err) => match err.kind() {
                 ErrorKind::NegativeBalance(_, _) => assert!(true),
                 _ => assert!(false),
@@ -656,13 +755,11 @@ mod tests {
     #[test]
     fn test_bank_account_close() {
         let bank_account = create_bank_account(false, 0);
-        match bank_account.close(Local::now()) {
-            Ok(ba) => assert!(ba.is_closed()),
-            _ => assert!(false),
-        };
+        assert!(bank_account.validate_close().is_ok());
+        assert!(bank_account.close(Local::now()).is_closed());
 
         let bank_account = create_bank_account(true, 0);
-        match bank_account.close(Local::now()) {
+        match bank_account.validate_close() {
             Err(err) => match err.kind() {
                 ErrorKind::AlreadyClosed(_) => assert!(true),
                 _ => assert!(false),
@@ -671,158 +768,218 @@ mod tests {
         };
     }
 
-    #[test]
-    fn test_aggregate_handle_open_bank_account_command() {
-        let aggregate = BankAccountAggregate::new();
+    fn bank_account_id() -> BankAccountId {
+        BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap()
+    }
 
-        let result = BankAccountAggregate::handle_command(&aggregate, BankAccountCommand::Open {
-            bank_account_id: BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+    fn opened() -> BankAccountEvent {
+        BankAccountEvent::Opened {
+            bank_account_id: bank_account_id(),
             name: BankAccountName::new(String::from("foo")).unwrap(),
-        });
-        assert!(result.is_ok());
+            occurred_at: Local::now(),
+        }
+    }
 
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
+    fn deposited(deposit: i32) -> BankAccountEvent {
+        BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id(),
+            deposit,
+            occurred_at: Local::now(),
+        }
+    }
 
-        let result = BankAccountAggregate::apply_event(&aggregate, events[0].clone());
-        let aggregate = result.unwrap();
-        assert!(aggregate.state.is_some());
+    fn closed() -> BankAccountEvent {
+        BankAccountEvent::Closed {
+            bank_account_id: bank_account_id(),
+            occurred_at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_apply_event_does_not_validate_history() {
+        let aggregate = BankAccountAggregate::load_from_history(
+            &BankAccountAggregate::new(), vec![opened(), closed(), deposited(500)], 3).unwrap();
+        let ba = aggregate.state().as_ref().unwrap();
+        assert!(ba.is_closed());
+        assert_eq!(ba.balance(), 500);
+
+        match BankAccountAggregate::apply_event(&BankAccountAggregate::new(), deposited(500)) {
+            Err(err) => assert_eq!(err.kind(), &ErrorKind::NotYetOpened),
+            Ok(_) => panic!("Event was applied before the account was opened"),
+        };
+    }
 
-        let ba = aggregate.state.unwrap();
+    #[test]
+    fn test_aggregate_handle_open_bank_account_command() {
+        let aggregate = given::<BankAccountAggregate>(vec![])
+            .when(BankAccountCommand::Open {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("foo")).unwrap(),
+            })
+            .then_expect_events(vec![opened()]);
+
+        let ba = aggregate.state().as_ref().unwrap();
         assert_eq!(ba.id().value().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
         assert_eq!(ba.name().value(), "foo");
-        assert_eq!(ba.is_closed(), false);
+        assert!(!ba.is_closed());
         assert_eq!(ba.balance(), 0);
+
+        given::<BankAccountAggregate>(vec![opened()])
+            .when(BankAccountCommand::Open {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("foo")).unwrap(),
+            })
+            .then_expect_error(ErrorKind::AlreadyOpened(bank_account_id()));
     }
 
     #[test]
     fn test_aggregate_handle_update_bank_account_command() {
-        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
-
-        let aggregate = BankAccountAggregate::load(BankAccount::new(
-                bank_account_id.clone(),
-                BankAccountName::new(String::from("foo")).unwrap(),
-                false,
-                0,
-                Local::now(),
-                Local::now(),
-                ), 1);
-
-        let result = BankAccountAggregate::handle_command(&aggregate, BankAccountCommand::Update {
-            bank_account_id: bank_account_id.clone(),
-            name: BankAccountName::new(String::from("bar")).unwrap(),
-        });
-        assert!(result.is_ok());
-
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
-
-        let result = BankAccountAggregate::apply_event(&aggregate, events[0].clone());
-        let aggregate = result.unwrap();
-        assert!(aggregate.state.is_some());
-
-        let ba = aggregate.state.unwrap();
-        assert_eq!(ba.id().value().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let aggregate = given::<BankAccountAggregate>(vec![opened()])
+            .when(BankAccountCommand::Update {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("bar")).unwrap(),
+            })
+            .then_expect_events(vec![
+                BankAccountEvent::Updated {
+                    bank_account_id: bank_account_id(),
+                    name: BankAccountName::new(String::from("bar")).unwrap(),
+                    occurred_at: Local::now(),
+                },
+            ]);
+
+        let ba = aggregate.state().as_ref().unwrap();
         assert_eq!(ba.name().value(), "bar");
-        assert_eq!(ba.is_closed(), false);
+        assert!(!ba.is_closed());
         assert_eq!(ba.balance(), 0);
+
+        given::<BankAccountAggregate>(vec![])
+            .when(BankAccountCommand::Update {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("bar")).unwrap(),
+            })
+            .then_expect_error(ErrorKind::InvalidState(bank_account_id()));
+
+        given::<BankAccountAggregate>(vec![opened(), closed()])
+            .when(BankAccountCommand::Update {
+                bank_account_id: bank_account_id(),
+                name: BankAccountName::new(String::from("bar")).unwrap(),
+            })
+            .then_expect_error(ErrorKind::AlreadyClosed(bank_account_id()));
     }
 
     #[test]
     fn test_aggregate_handle_deposit_bank_account_command() {
-        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
-
-        let aggregate = BankAccountAggregate::load(BankAccount::new(
-                bank_account_id.clone(),
-                BankAccountName::new(String::from("foo")).unwrap(),
-                false,
-                0,
-                Local::now(),
-                Local::now(),
-                ), 1);
-
-        let result = BankAccountAggregate::handle_command(&aggregate, BankAccountCommand::Deposit {
-            bank_account_id: bank_account_id.clone(),
-            deposit: 500,
-        });
-        assert!(result.is_ok());
-
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
-
-        let result = BankAccountAggregate::apply_event(&aggregate, events[0].clone());
-        let aggregate = result.unwrap();
-        assert!(aggregate.state.is_some());
-
-        let ba = aggregate.state.unwrap();
-        assert_eq!(ba.id().value().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let aggregate = given::<BankAccountAggregate>(vec![opened()])
+            .when(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id(),
+                deposit: 500,
+            })
+            .then_expect_events(vec![deposited(500)]);
+
+        let ba = aggregate.state().as_ref().unwrap();
         assert_eq!(ba.name().value(), "foo");
-        assert_eq!(ba.is_closed(), false);
+        assert!(!ba.is_closed());
         assert_eq!(ba.balance(), 500);
+
+        given::<BankAccountAggregate>(vec![])
+            .when(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id(),
+                deposit: 500,
+            })
+            .then_expect_error(ErrorKind::InvalidState(bank_account_id()));
+
+        given::<BankAccountAggregate>(vec![opened(), closed()])
+            .when(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id(),
+                deposit: 500,
+            })
+            .then_expect_error(ErrorKind::AlreadyClosed(bank_account_id()));
+
+        given::<BankAccountAggregate>(vec![opened()])
+            .when(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id(),
+                deposit: 0,
+            })
+            .then_expect_error(ErrorKind::DepositZero(bank_account_id(), 0));
+
+        given::<BankAccountAggregate>(vec![opened(), deposited(1000)])
+            .when(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id(),
+                deposit: -500,
+            })
+            .then_expect_error(ErrorKind::NegativeDeposit(bank_account_id(), -500));
     }
 
     #[test]
     fn test_aggregate_handle_withdraw_bank_account_command() {
-        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
-
-        let aggregate = BankAccountAggregate::load(BankAccount::new(
-                bank_account_id.clone(),
-                BankAccountName::new(String::from("foo")).unwrap(),
-                false,
-                500,
-                Local::now(),
-                Local::now(),
-                ), 1);
-
-        let result = BankAccountAggregate::handle_command(&aggregate, BankAccountCommand::Withdraw {
-            bank_account_id: bank_account_id.clone(),
-            withdraw: 300,
-        });
-        assert!(result.is_ok());
-
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
-
-        let result = BankAccountAggregate::apply_event(&aggregate, events[0].clone());
-        let aggregate = result.unwrap();
-        assert!(aggregate.state.is_some());
-
-        let ba = aggregate.state.unwrap();
-        assert_eq!(ba.id().value().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let aggregate = given::<BankAccountAggregate>(vec![opened(), deposited(500)])
+            .when(BankAccountCommand::Withdraw {
+                bank_account_id: bank_account_id(),
+                withdraw: 300,
+            })
+            .then_expect_events(vec![
+                BankAccountEvent::Withdrawn {
+                    bank_account_id: bank_account_id(),
+                    withdraw: 300,
+                    occurred_at: Local::now(),
+                },
+            ]);
+
+        let ba = aggregate.state().as_ref().unwrap();
         assert_eq!(ba.name().value(), "foo");
-        assert_eq!(ba.is_closed(), false);
+        assert!(!ba.is_closed());
         assert_eq!(ba.balance(), 200);
+
+        given::<BankAccountAggregate>(vec![])
+            .when(BankAccountCommand::Withdraw {
+                bank_account_id: bank_account_id(),
+                withdraw: 300,
+            })
+            .then_expect_error(ErrorKind::InvalidState(bank_account_id()));
+
+        given::<BankAccountAggregate>(vec![opened(), deposited(500)])
+            .when(BankAccountCommand::Withdraw {
+                bank_account_id: bank_account_id(),
+                withdraw: 600,
+            })
+            .then_expect_error(ErrorKind::NegativeBalance(bank_account_id(), 600));
+
+        given::<BankAccountAggregate>(vec![opened(), deposited(500)])
+            .when(BankAccountCommand::Withdraw {
+                bank_account_id: bank_account_id(),
+                withdraw: -300,
+            })
+            .then_expect_error(ErrorKind::NegativeWithdraw(bank_account_id(), -300));
     }
 
     #[test]
     fn test_aggregate_handle_close_bank_account_command() {
-        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
-
-        let aggregate = BankAccountAggregate::load(BankAccount::new(
-                bank_account_id.clone(),
-                BankAccountName::new(String::from("foo")).unwrap(),
-                false,
-                0,
-                Local::now(),
-                Local::now(),
-                ), 1);
-
-        let result = BankAccountAggregate::handle_command(&aggregate, BankAccountCommand::Close {
-            bank_account_id: bank_account_id.clone(),
-        });
-        assert!(result.is_ok());
-
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
-
-        let result = BankAccountAggregate::apply_event(&aggregate, events[0].clone());
-        let aggregate = result.unwrap();
-        assert!(aggregate.state.is_some());
-
-        let ba = aggregate.state.unwrap();
-        assert_eq!(ba.id().value().to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let aggregate = given::<BankAccountAggregate>(vec![opened()])
+            .when(BankAccountCommand::Close {
+                bank_account_id: bank_account_id(),
+            })
+            .then_expect_events(vec![
+                BankAccountEvent::Closed {
+                    bank_account_id: bank_account_id(),
+                    occurred_at: Local::now(),
+                },
+            ]);
+
+        let ba = aggregate.state().as_ref().unwrap();
         assert_eq!(ba.name().value(), "foo");
-        assert_eq!(ba.is_closed(), true);
+        assert!(ba.is_closed());
         assert_eq!(ba.balance(), 0);
+
+        given::<BankAccountAggregate>(vec![])
+            .when(BankAccountCommand::Close {
+                bank_account_id: bank_account_id(),
+            })
+            .then_expect_error(ErrorKind::InvalidState(bank_account_id()));
+
+        given::<BankAccountAggregate>(vec![opened(), closed()])
+            .when(BankAccountCommand::Close {
+                bank_account_id: bank_account_id(),
+            })
+            .then_expect_error(ErrorKind::AlreadyClosed(bank_account_id()));
     }
 }
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use tokio::task;
+use tracing::Span;
+
+use super::eventsourcing::{
+    Snapshot,
+    StreamMetadata,
+    EventStoreError,
+    EventStoreErrorKind,
+    EventStore,
+    EventPublisher,
+    EventPublisherError,
+    EventPublisherErrorKind,
+    AsyncEventStore,
+    AsyncEventPublisher,
+};
+use super::dao::{BankAccountRM, BankAccountRMDao, AsyncBankAccountRMDao, DaoError, DaoErrorKind};
+use super::idempotency::{
+    Reservation,
+    CommandOutcome,
+    IdempotencyError,
+    IdempotencyErrorKind,
+    IdempotencyStore,
+    AsyncIdempotencyStore,
+};
+
+pub struct BlockingEventStore<S> {
+    inner: Arc<S>,
+}
+
+impl<S> BlockingEventStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+fn spawn_blocking<F, T>(f: F) -> task::JoinHandle<T>
+    where F: FnOnce() -> T + Send + 'static,
+          T: Send + 'static {
+    let span = Span::current();
+    task::spawn_blocking(move || span.in_scope(f))
+}
+
+fn join_error(err: task::JoinError) -> EventStoreError {
+    EventStoreError::from(EventStoreErrorKind::BlockingTaskError(err.to_string()))
+}
+
+#[async_trait]
+impl<S> AsyncEventStore for BlockingEventStore<S>
+    where S: EventStore + 'static,
+          S::Event: Send + 'static,
+          S::EventStream: Send + 'static,
+          S::SnapshotData: Send + 'static {
+    type Event = S::Event;
+    type EventStream = S::EventStream;
+    type SnapshotData = S::SnapshotData;
+
+    async fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.append_event_stream(stream_id, stream_version, events))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.event_stream_since(stream_id, stream_version))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.record_snapshot(snapshot))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.read_snapshot(stream_id))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                            idempotency_key: String) -> Result<(), EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.append_idempotent_event_stream(stream_id, stream_version, events, idempotency_key))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.idempotency_key_version(stream_id, idempotency_key))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.stream_metadata(stream_id))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.set_stream_metadata(stream_id, metadata))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.purge_stream(stream_id))
+            .await
+            .map_err(join_error)?
+    }
+}
+
+pub struct BlockingEventPublisher<P> {
+    inner: Arc<P>,
+}
+
+impl<P> BlockingEventPublisher<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+#[async_trait]
+impl<P> AsyncEventPublisher for BlockingEventPublisher<P>
+    where P: EventPublisher + Send + Sync + 'static,
+          P::Event: Send + 'static {
+    type Event = P::Event;
+
+    async fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.publish(stream_id, stream_version, events))
+            .await
+            .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::PublishError(err.to_string())))?
+    }
+}
+
+pub struct BlockingBankAccountRMDao<D> {
+    inner: Arc<D>,
+}
+
+impl<D> BlockingBankAccountRMDao<D> {
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+fn dao_join_error(err: task::JoinError) -> DaoError {
+    DaoError::from(DaoErrorKind::BlockingTaskError(err.to_string()))
+}
+
+#[async_trait]
+impl<D> AsyncBankAccountRMDao for BlockingBankAccountRMDao<D>
+    where D: BankAccountRMDao + 'static {
+    async fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.find(bank_account_id)).await.map_err(dao_join_error)?
+    }
+
+    async fn insert(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.insert(model)).await.map_err(dao_join_error)?
+    }
+
+    async fn update(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.update(model)).await.map_err(dao_join_error)?
+    }
+
+    async fn list(&self) -> Result<Vec<BankAccountRM>, DaoError> {
+        let inner = self.inner.clone();
+        spawn_blocking(move || inner.list()).await.map_err(dao_join_error)?
+    }
+}
+
+pub struct BlockingIdempotencyStore<S> {
+    inner: Arc<S>,
+}
+
+impl<S> BlockingIdempotencyStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+fn idempotency_join_error(err: task::JoinError) -> IdempotencyError {
+    IdempotencyError::from(IdempotencyErrorKind::SaveError(err.to_string()))
+}
+
+#[async_trait]
+impl<S> AsyncIdempotencyStore for BlockingIdempotencyStore<S>
+    where S: IdempotencyStore + 'static {
+    async fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+                     expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError> {
+        let inner = self.inner.clone();
+        let (bank_account_id, idempotency_key, fingerprint) =
+            (bank_account_id.to_string(), idempotency_key.to_string(), fingerprint.to_string());
+        spawn_blocking(move || inner.reserve(&bank_account_id, &idempotency_key, &fingerprint, expired_before))
+            .await
+            .map_err(idempotency_join_error)?
+    }
+
+    async fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError> {
+        let inner = self.inner.clone();
+        let (bank_account_id, idempotency_key) = (bank_account_id.to_string(), idempotency_key.to_string());
+        spawn_blocking(move || inner.complete(&bank_account_id, &idempotency_key, outcome))
+            .await
+            .map_err(idempotency_join_error)?
+    }
+
+    async fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError> {
+        let inner = self.inner.clone();
+        let (bank_account_id, idempotency_key) = (bank_account_id.to_string(), idempotency_key.to_string());
+        spawn_blocking(move || inner.release(&bank_account_id, &idempotency_key))
+            .await
+            .map_err(idempotency_join_error)?
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use failure::{Fail, Context, Backtrace};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug)]
+pub struct CheckpointError {
+    inner: Context<CheckpointErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum CheckpointErrorKind {
+    #[fail(display = "Load checkpoint error: {}", _0)]
+    LoadError(String),
+
+    #[fail(display = "Save checkpoint error: {}", _0)]
+    SaveError(String),
+
+    #[fail(display = "Handle event error: {}", _0)]
+    HandleError(String),
+}
+
+impl Fail for CheckpointError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl CheckpointError {
+    pub fn kind(&self) -> &CheckpointErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<CheckpointErrorKind> for CheckpointError {
+    fn from(kind: CheckpointErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<CheckpointErrorKind>> for CheckpointError {
+    fn from(inner: Context<CheckpointErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+pub trait CheckpointStore: Send + Sync {
+    fn stream_version(&self, projection: &str, stream_id: &str) -> Result<Option<u64>, CheckpointError>;
+
+    fn save(&self, projection: &str, stream_id: &str, stream_version: u64) -> Result<(), CheckpointError>;
+
+    fn transaction(&self, work: &mut dyn FnMut() -> Result<(), CheckpointError>)
+        -> Result<(), CheckpointError> {
+        work()
+    }
+}
+
+pub fn next_version(store: &dyn CheckpointStore, projection: &str, stream_id: &str)
+    -> Result<u64, CheckpointError> {
+    Ok(store.stream_version(projection, stream_id)?.map_or(1, |stream_version| stream_version + 1))
+}
+
+pub fn handle_once<F>(store: &dyn CheckpointStore, projection: &str, stream_id: &str, stream_version: u64, mut handle: F)
+    -> Result<bool, CheckpointError>
+    where F: FnMut() -> Result<u64, CheckpointError> {
+    if stream_version < next_version(store, projection, stream_id)? {
+        return Ok(false);
+    }
+
+    store.transaction(&mut || {
+        let handled_version = handle()?;
+        store.save(projection, stream_id, handled_version)
+    })?;
+    Ok(true)
+}
+
+pub fn handle_in_order<E, L, F>(store: &dyn CheckpointStore, projection: &str, stream_id: &str,
+                                stream_version: u64, event: E, load_missed: L, mut handle: F)
+    -> Result<u64, CheckpointError>
+    where E: Clone,
+          L: FnOnce(u64) -> Result<Vec<(u64, E)>, CheckpointError>,
+          F: FnMut(u64, E) -> Result<u64, CheckpointError> {
+    let next = next_version(store, projection, stream_id)?;
+    if stream_version < next {
+        return Ok(0);
+    }
+
+    let mut events = vec![];
+    if stream_version > next {
+        events.extend(load_missed(next)?.into_iter().filter(|(version, _)| *version < stream_version));
+    }
+    events.push((stream_version, event));
+
+    let mut handled = 0;
+    for (version, event) in events {
+        if handle_once(store, projection, stream_id, version, || handle(version, event.clone()))? {
+            handled += 1;
+        }
+    }
+    Ok(handled)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Checkpoint {
+    stream_versions: BTreeMap<String, u64>,
+}
+
+pub struct InmemoryCheckpointStore {
+    checkpoints: Mutex<HashMap<String, Checkpoint>>,
+}
+
+impl InmemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self {
+            checkpoints: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InmemoryCheckpointStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CheckpointStore for InmemoryCheckpointStore {
+    fn stream_version(&self, projection: &str, stream_id: &str) -> Result<Option<u64>, CheckpointError> {
+        Ok(self.checkpoints.lock().unwrap()
+           .get(projection)
+           .and_then(|checkpoint| checkpoint.stream_versions.get(stream_id).cloned()))
+    }
+
+    fn save(&self, projection: &str, stream_id: &str, stream_version: u64) -> Result<(), CheckpointError> {
+        self.checkpoints.lock().unwrap()
+            .entry(projection.to_string())
+            .or_default()
+            .stream_versions.insert(stream_id.to_string(), stream_version);
+        Ok(())
+    }
+}
+
+pub struct FileCheckpointStore {
+    directory: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileCheckpointStore {
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, CheckpointError> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)
+            .map_err(|err| CheckpointError::from(CheckpointErrorKind::LoadError(err.to_string())))?;
+
+        Ok(Self {
+            directory,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn checkpoint_path(&self, projection: &str) -> PathBuf {
+        let name: String = projection.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.directory.join(format!("{}.json", name))
+    }
+
+    fn read(&self, projection: &str) -> Result<Option<Checkpoint>, CheckpointError> {
+        let data = match fs::read(self.checkpoint_path(projection)) {
+            Ok(data) => data,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(CheckpointErrorKind::LoadError(err.to_string()))?,
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|err| CheckpointError::from(CheckpointErrorKind::LoadError(err.to_string())))
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn stream_version(&self, projection: &str, stream_id: &str) -> Result<Option<u64>, CheckpointError> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self.read(projection)?.and_then(|checkpoint| checkpoint.stream_versions.get(stream_id).cloned()))
+    }
+
+    fn save(&self, projection: &str, stream_id: &str, stream_version: u64) -> Result<(), CheckpointError> {
+        let _lock = self.lock.lock().unwrap();
+        let mut checkpoint = self.read(projection)?.unwrap_or_default();
+        checkpoint.stream_versions.insert(stream_id.to_string(), stream_version);
+
+        let path = self.checkpoint_path(projection);
+        let tmp = path.with_extension("json.tmp");
+        let write = |tmp: &Path| -> std::io::Result<()> {
+            let mut file = File::create(tmp)?;
+            file.write_all(&serde_json::to_vec(&checkpoint).unwrap())?;
+            file.sync_all()?;
+            fs::rename(tmp, &path)
+        };
+        write(&tmp).map_err(|err| CheckpointError::from(CheckpointErrorKind::SaveError(err.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use tempfile::TempDir;
+
+    use super::{
+        CheckpointStore,
+        InmemoryCheckpointStore,
+        FileCheckpointStore,
+        CheckpointErrorKind,
+        handle_once,
+        handle_in_order,
+    };
+
+    fn verify_checkpoint_store(store: &dyn CheckpointStore) {
+        assert_eq!(store.stream_version("projector", "stream-1").unwrap(), None);
+
+        let handled = Cell::new(0);
+        let handle = |stream_version: u64| {
+            handled.set(handled.get() + 1);
+            Ok(stream_version)
+        };
+        assert!(handle_once(store, "projector", "stream-1", 1, || handle(1)).unwrap());
+        assert!(handle_once(store, "projector", "stream-1", 2, || handle(2)).unwrap());
+        assert!(!handle_once(store, "projector", "stream-1", 2, || handle(2)).unwrap());
+        assert!(!handle_once(store, "projector", "stream-1", 1, || handle(1)).unwrap());
+        assert!(handle_once(store, "projector", "stream-2", 1, || handle(1)).unwrap());
+        assert!(handle_once(store, "projector", "stream-2", 2, || handle(5)).unwrap());
+        assert!(!handle_once(store, "projector", "stream-2", 4, || handle(4)).unwrap());
+        assert_eq!(handled.get(), 4);
+
+        assert_eq!(store.stream_version("projector", "stream-1").unwrap(), Some(2));
+        assert_eq!(store.stream_version("projector", "stream-2").unwrap(), Some(5));
+        assert_eq!(store.stream_version("snapshotter", "stream-1").unwrap(), None);
+
+        let result = handle_once(store, "projector", "stream-1", 3, || {
+            Err(CheckpointErrorKind::HandleError(String::from("boom")))?
+        });
+        assert!(result.is_err());
+        assert_eq!(store.stream_version("projector", "stream-1").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_inmemory_checkpoint_store() {
+        verify_checkpoint_store(&InmemoryCheckpointStore::new());
+    }
+
+    #[test]
+    fn test_file_checkpoint_store() {
+        let dir = TempDir::new().unwrap();
+        verify_checkpoint_store(&FileCheckpointStore::open(dir.path()).unwrap());
+
+        let store = FileCheckpointStore::open(dir.path()).unwrap();
+        assert_eq!(store.stream_version("projector", "stream-1").unwrap(), Some(2));
+        assert_eq!(store.stream_version("projector", "stream-2").unwrap(), Some(5));
+    }
+
+    #[test]
+    fn test_handle_in_order_catches_up_missed_events() {
+        let store = InmemoryCheckpointStore::new();
+        let handled = RefCell::new(vec![]);
+        let handle = |stream_version: u64, event: &'static str| {
+            handled.borrow_mut().push((stream_version, event));
+            Ok(stream_version)
+        };
+        let history = vec![(1, "opened"), (2, "deposited"), (3, "withdrawn"), (4, "closed")];
+        let load_missed = |from_version: u64| {
+            Ok(history.iter().cloned().filter(|(version, _)| *version >= from_version).collect())
+        };
+
+        assert_eq!(handle_in_order(&store, "projector", "stream-1", 1, "opened", load_missed, handle).unwrap(), 1);
+        assert_eq!(handle_in_order(&store, "projector", "stream-1", 3, "withdrawn", load_missed, handle).unwrap(), 2);
+        assert_eq!(handle_in_order(&store, "projector", "stream-1", 2, "deposited", load_missed, handle).unwrap(), 0);
+        assert_eq!(handle_in_order(&store, "projector", "stream-1", 4, "closed", |_| {
+            Err(CheckpointErrorKind::HandleError(String::from("no gap to load")))?
+        }, handle).unwrap(), 1);
+
+        assert_eq!(*handled.borrow(), history);
+        assert_eq!(store.stream_version("projector", "stream-1").unwrap(), Some(4));
+    }
+}
@@ -0,0 +1,477 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use failure::{Fail, Context, Backtrace};
+use serde::{Serialize, Deserialize};
+use rand::RngCore;
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+use super::aggregate::{BankAccount, BankAccountEvent, BankAccountId, BankAccountName, ENCRYPTED_NAME_PREFIX};
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+#[derive(Debug)]
+pub struct CryptoError {
+    inner: Context<CryptoErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum CryptoErrorKind {
+    #[fail(display = "Key store error: {}", _0)]
+    KeyStoreError(String),
+
+    #[fail(display = "Subject is erased: {}", _0)]
+    SubjectErasedError(String),
+
+    #[fail(display = "Cipher error: {}", _0)]
+    CipherError(String),
+}
+
+impl Fail for CryptoError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl CryptoError {
+    pub fn kind(&self) -> &CryptoErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<CryptoErrorKind> for CryptoError {
+    fn from(kind: CryptoErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<CryptoErrorKind>> for CryptoError {
+    fn from(inner: Context<CryptoErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+pub fn generate_key() -> Vec<u8> {
+    let mut key = vec![0u8; KEY_SIZE];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+}
+
+pub trait KeyStore: Send + Sync {
+    fn find_key(&self, subject: &str) -> Result<Option<Vec<u8>>, CryptoError>;
+
+    fn create_key(&self, subject: &str) -> Result<Vec<u8>, CryptoError>;
+
+    fn erase_key(&self, subject: &str) -> Result<(), CryptoError>;
+
+    fn is_erased(&self, subject: &str) -> Result<bool, CryptoError>;
+}
+
+impl<K: KeyStore + ?Sized> KeyStore for Arc<K> {
+    fn find_key(&self, subject: &str) -> Result<Option<Vec<u8>>, CryptoError> {
+        (**self).find_key(subject)
+    }
+
+    fn create_key(&self, subject: &str) -> Result<Vec<u8>, CryptoError> {
+        (**self).create_key(subject)
+    }
+
+    fn erase_key(&self, subject: &str) -> Result<(), CryptoError> {
+        (**self).erase_key(subject)
+    }
+
+    fn is_erased(&self, subject: &str) -> Result<bool, CryptoError> {
+        (**self).is_erased(subject)
+    }
+}
+
+pub struct InmemoryKeyStore {
+    keys: Mutex<HashMap<String, Option<Vec<u8>>>>,
+}
+
+impl InmemoryKeyStore {
+    pub fn new() -> Self {
+        Self {
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InmemoryKeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyStore for InmemoryKeyStore {
+    fn find_key(&self, subject: &str) -> Result<Option<Vec<u8>>, CryptoError> {
+        Ok(self.keys.lock().unwrap().get(subject).cloned().unwrap_or(None))
+    }
+
+    fn create_key(&self, subject: &str) -> Result<Vec<u8>, CryptoError> {
+        let mut keys = self.keys.lock().unwrap();
+        match keys.entry(subject.to_string()).or_insert_with(|| Some(generate_key())) {
+            Some(key) => Ok(key.clone()),
+            None => Err(CryptoErrorKind::SubjectErasedError(subject.to_string()))?,
+        }
+    }
+
+    fn erase_key(&self, subject: &str) -> Result<(), CryptoError> {
+        self.keys.lock().unwrap().insert(subject.to_string(), None);
+        Ok(())
+    }
+
+    fn is_erased(&self, subject: &str) -> Result<bool, CryptoError> {
+        Ok(matches!(self.keys.lock().unwrap().get(subject), Some(None)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StoredKey {
+    key: Option<String>,
+}
+
+pub struct FileKeyStore {
+    directory: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileKeyStore {
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, CryptoError> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)
+            .map_err(|err| CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string())))?;
+
+        Ok(Self {
+            directory,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn key_path(&self, subject: &str) -> PathBuf {
+        let name: String = subject.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.directory.join(format!("{}.key", name))
+    }
+
+    fn read(&self, subject: &str) -> Result<Option<StoredKey>, CryptoError> {
+        let data = match fs::read(self.key_path(subject)) {
+            Ok(data) => data,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(CryptoErrorKind::KeyStoreError(err.to_string()))?,
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|err| CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string())))
+    }
+
+    fn write(&self, subject: &str, stored: &StoredKey) -> Result<(), CryptoError> {
+        let path = self.key_path(subject);
+        let tmp = path.with_extension("key.tmp");
+        let write = |tmp: &Path| -> std::io::Result<()> {
+            let mut file = File::create(tmp)?;
+            file.write_all(&serde_json::to_vec(stored).unwrap())?;
+            file.sync_all()?;
+            fs::rename(tmp, &path)
+        };
+        write(&tmp).map_err(|err| CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string())))
+    }
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    base64::decode(encoded)
+        .map_err(|err| CryptoError::from(CryptoErrorKind::KeyStoreError(err.to_string())))
+}
+
+impl KeyStore for FileKeyStore {
+    fn find_key(&self, subject: &str) -> Result<Option<Vec<u8>>, CryptoError> {
+        let _lock = self.lock.lock().unwrap();
+        match self.read(subject)?.and_then(|stored| stored.key) {
+            Some(key) => decode_key(&key).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn create_key(&self, subject: &str) -> Result<Vec<u8>, CryptoError> {
+        let _lock = self.lock.lock().unwrap();
+        match self.read(subject)? {
+            Some(StoredKey { key: Some(key) }) => decode_key(&key),
+            Some(StoredKey { key: None }) => Err(CryptoErrorKind::SubjectErasedError(subject.to_string()))?,
+            None => {
+                let key = generate_key();
+                self.write(subject, &StoredKey { key: Some(base64::encode(&key)) })?;
+                Ok(key)
+            },
+        }
+    }
+
+    fn erase_key(&self, subject: &str) -> Result<(), CryptoError> {
+        let _lock = self.lock.lock().unwrap();
+        self.write(subject, &StoredKey { key: None })
+    }
+
+    fn is_erased(&self, subject: &str) -> Result<bool, CryptoError> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(matches!(self.read(subject)?, Some(StoredKey { key: None })))
+    }
+}
+
+#[derive(Clone)]
+pub struct PiiCipher {
+    key_store: Arc<dyn KeyStore>,
+}
+
+impl PiiCipher {
+    pub fn new(key_store: Arc<dyn KeyStore>) -> Self {
+        Self {
+            key_store,
+        }
+    }
+
+    pub fn erase_subject(&self, bank_account_id: &BankAccountId) -> Result<(), CryptoError> {
+        self.key_store.erase_key(&bank_account_id.to_string())
+    }
+
+    pub fn encrypt_name(&self, bank_account_id: &BankAccountId, name: &BankAccountName)
+        -> Result<BankAccountName, CryptoError> {
+        let subject = bank_account_id.to_string();
+        let key = self.key_store.create_key(&subject)?;
+        let mut nonce = [0u8; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = cipher(&key)?
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: name.value().as_bytes(), aad: subject.as_bytes() })
+            .map_err(|err| CryptoError::from(CryptoErrorKind::CipherError(err.to_string())))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(BankAccountName::unchecked(format!("{}{}", ENCRYPTED_NAME_PREFIX, base64::encode(&sealed))))
+    }
+
+    pub fn decrypt_name(&self, bank_account_id: &BankAccountId, name: &BankAccountName)
+        -> Result<BankAccountName, CryptoError> {
+        let subject = bank_account_id.to_string();
+        let encoded = match name.value().strip_prefix(ENCRYPTED_NAME_PREFIX) {
+            Some(encoded) => encoded,
+            None if self.key_store.is_erased(&subject)? => return Ok(BankAccountName::redacted()),
+            None => return Ok(name.clone()),
+        };
+
+        let key = match self.key_store.find_key(&subject)? {
+            Some(key) => key,
+            None => return Ok(BankAccountName::redacted()),
+        };
+
+        let sealed = base64::decode(encoded)
+            .map_err(|err| CryptoError::from(CryptoErrorKind::CipherError(err.to_string())))?;
+        if sealed.len() < NONCE_SIZE {
+            return Err(CryptoErrorKind::CipherError(String::from("truncated ciphertext")))?;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+
+        let plaintext = cipher(&key)?
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: subject.as_bytes() })
+            .map_err(|err| CryptoError::from(CryptoErrorKind::CipherError(err.to_string())))?;
+        String::from_utf8(plaintext)
+            .map(BankAccountName::unchecked)
+            .map_err(|err| CryptoError::from(CryptoErrorKind::CipherError(err.to_string())))
+    }
+
+    pub fn encrypt_event(&self, event: BankAccountEvent) -> Result<BankAccountEvent, CryptoError> {
+        self.map_event_name(event, |id, name| self.encrypt_name(id, name))
+    }
+
+    pub fn decrypt_event(&self, event: BankAccountEvent) -> Result<BankAccountEvent, CryptoError> {
+        self.map_event_name(event, |id, name| self.decrypt_name(id, name))
+    }
+
+    pub fn encrypt_snapshot(&self, bank_account: BankAccount) -> Result<BankAccount, CryptoError> {
+        self.map_snapshot_name(bank_account, |id, name| {
+            if name.is_redacted() {
+                return Ok(name.clone());
+            }
+            self.encrypt_name(id, name).or_else(|err| match err.kind() {
+                CryptoErrorKind::SubjectErasedError(_) => Ok(BankAccountName::redacted()),
+                _ => Err(err),
+            })
+        })
+    }
+
+    pub fn decrypt_snapshot(&self, bank_account: BankAccount) -> Result<BankAccount, CryptoError> {
+        self.map_snapshot_name(bank_account, |id, name| self.decrypt_name(id, name))
+    }
+
+    fn map_event_name<F>(&self, event: BankAccountEvent, f: F) -> Result<BankAccountEvent, CryptoError>
+        where F: FnOnce(&BankAccountId, &BankAccountName) -> Result<BankAccountName, CryptoError> {
+        match event {
+            BankAccountEvent::Opened { bank_account_id, name, occurred_at } => {
+                let name = f(&bank_account_id, &name)?;
+                Ok(BankAccountEvent::Opened { bank_account_id, name, occurred_at })
+            },
+            BankAccountEvent::Updated { bank_account_id, name, occurred_at } => {
+                let name = f(&bank_account_id, &name)?;
+                Ok(BankAccountEvent::Updated { bank_account_id, name, occurred_at })
+            },
+            event => Ok(event),
+        }
+    }
+
+    fn map_snapshot_name<F>(&self, bank_account: BankAccount, f: F) -> Result<BankAccount, CryptoError>
+        where F: FnOnce(&BankAccountId, &BankAccountName) -> Result<BankAccountName, CryptoError> {
+        let name = f(bank_account.id(), bank_account.name())?;
+        Ok(BankAccount::new(
+                bank_account.id().clone(),
+                name,
+                bank_account.is_closed(),
+                bank_account.balance(),
+                *bank_account.created_at(),
+                *bank_account.updated_at(),
+                ))
+    }
+}
+
+fn redact_plaintext(name: BankAccountName) -> BankAccountName {
+    if name.is_encrypted() {
+        name
+    } else {
+        BankAccountName::redacted()
+    }
+}
+
+pub fn redact_plaintext_event(event: BankAccountEvent) -> BankAccountEvent {
+    match event {
+        BankAccountEvent::Opened { bank_account_id, name, occurred_at } =>
+            BankAccountEvent::Opened { bank_account_id, name: redact_plaintext(name), occurred_at },
+        BankAccountEvent::Updated { bank_account_id, name, occurred_at } =>
+            BankAccountEvent::Updated { bank_account_id, name: redact_plaintext(name), occurred_at },
+        event => event,
+    }
+}
+
+pub fn redact_plaintext_snapshot(bank_account: BankAccount) -> BankAccount {
+    let name = redact_plaintext(bank_account.name().clone());
+    bank_account.with_name(name, *bank_account.updated_at())
+}
+
+fn cipher(key: &[u8]) -> Result<Aes256Gcm, CryptoError> {
+    if key.len() != KEY_SIZE {
+        Err(CryptoErrorKind::KeyStoreError(format!("invalid key length: {}", key.len())))?;
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::Local;
+    use tempfile::TempDir;
+
+    use super::super::aggregate::{BankAccountEvent, BankAccountId, BankAccountName};
+    use super::{KeyStore, InmemoryKeyStore, FileKeyStore, PiiCipher, CryptoErrorKind, redact_plaintext_event};
+
+    fn verify_key_store(key_store: &dyn KeyStore) {
+        assert_eq!(key_store.find_key("subject-1").unwrap(), None);
+        assert!(!key_store.is_erased("subject-1").unwrap());
+
+        let key = key_store.create_key("subject-1").unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(key_store.create_key("subject-1").unwrap(), key);
+        assert_eq!(key_store.find_key("subject-1").unwrap(), Some(key.clone()));
+        assert_ne!(key_store.create_key("subject-2").unwrap(), key);
+
+        key_store.erase_key("subject-1").unwrap();
+        assert_eq!(key_store.find_key("subject-1").unwrap(), None);
+        assert!(key_store.is_erased("subject-1").unwrap());
+        assert!(!key_store.is_erased("subject-2").unwrap());
+        match key_store.create_key("subject-1") {
+            Err(err) => match err.kind() {
+                CryptoErrorKind::SubjectErasedError(_) => {},
+                kind => panic!("Unexpected error for an erased subject: {:?}", kind),
+            },
+            Ok(_) => panic!("Key was recreated for an erased subject"),
+        };
+        assert!(key_store.find_key("subject-2").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_inmemory_key_store() {
+        verify_key_store(&InmemoryKeyStore::new());
+    }
+
+    #[test]
+    fn test_file_key_store() {
+        let dir = TempDir::new().unwrap();
+        verify_key_store(&FileKeyStore::open(dir.path()).unwrap());
+
+        let key_store = FileKeyStore::open(dir.path()).unwrap();
+        assert_eq!(key_store.find_key("subject-1").unwrap(), None);
+        assert!(key_store.find_key("subject-2").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_cipher_round_trip_and_erasure() {
+        let cipher = PiiCipher::new(Arc::new(InmemoryKeyStore::new()));
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let other_id = BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap();
+        let name = BankAccountName::new(String::from("foo")).unwrap();
+
+        let encrypted = cipher.encrypt_name(&bank_account_id, &name).unwrap();
+        assert!(!encrypted.value().contains("foo"));
+        assert_ne!(cipher.encrypt_name(&bank_account_id, &name).unwrap(), encrypted);
+        assert_eq!(cipher.decrypt_name(&bank_account_id, &encrypted).unwrap(), name);
+        assert_eq!(cipher.decrypt_name(&bank_account_id, &name).unwrap(), name);
+
+        cipher.encrypt_name(&other_id, &name).unwrap();
+        assert!(cipher.decrypt_name(&other_id, &encrypted).is_err());
+
+        cipher.erase_subject(&bank_account_id).unwrap();
+        assert!(cipher.decrypt_name(&bank_account_id, &encrypted).unwrap().is_redacted());
+        assert!(cipher.decrypt_name(&bank_account_id, &name).unwrap().is_redacted());
+        assert_eq!(cipher.decrypt_name(&other_id, &name).unwrap(), name);
+        assert!(cipher.encrypt_name(&bank_account_id, &name).is_err());
+    }
+
+    #[test]
+    fn test_redact_plaintext_event() {
+        let cipher = PiiCipher::new(Arc::new(InmemoryKeyStore::new()));
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let name = BankAccountName::new(String::from("foo")).unwrap();
+        let opened = |name: BankAccountName| BankAccountEvent::Opened {
+            bank_account_id: bank_account_id.clone(),
+            name,
+            occurred_at: Local::now(),
+        };
+
+        match redact_plaintext_event(opened(name.clone())) {
+            BankAccountEvent::Opened { name, .. } => assert!(name.is_redacted()),
+            event => panic!("Unexpected event: {:?}", event),
+        };
+
+        let encrypted = cipher.encrypt_name(&bank_account_id, &name).unwrap();
+        match redact_plaintext_event(opened(encrypted.clone())) {
+            BankAccountEvent::Opened { name, .. } => assert_eq!(name, encrypted),
+            event => panic!("Unexpected event: {:?}", event),
+        };
+
+        let deposited = BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 100,
+            occurred_at: Local::now(),
+        };
+        assert_eq!(redact_plaintext_event(deposited.clone()), deposited);
+    }
+}
@@ -1,5 +1,61 @@
+use std::fmt;
+use std::sync::Arc;
 use chrono::{Local, DateTime};
+use async_trait::async_trait;
+use failure::{Fail, Context, Backtrace};
 
+#[derive(Debug)]
+pub struct DaoError {
+    inner: Context<DaoErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum DaoErrorKind {
+    #[fail(display = "Blocking task error: {}", _0)]
+    BlockingTaskError(String),
+
+    #[fail(display = "Query error: {}", _0)]
+    QueryError(String),
+
+    #[fail(display = "Read model not found: {}", _0)]
+    NotFound(String),
+}
+
+impl Fail for DaoError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for DaoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl DaoError {
+    pub fn kind(&self) -> &DaoErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<DaoErrorKind> for DaoError {
+    fn from(kind: DaoErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<DaoErrorKind>> for DaoError {
+    fn from(inner: Context<DaoErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct BankAccountRM {
     pub bank_account_id: String,
     pub name: String,
@@ -11,9 +67,48 @@ pub struct BankAccountRM {
 }
 
 pub trait BankAccountRMDao: Send + Sync {
-    fn find(&self, bank_account_id: String) -> Option<BankAccountRM>;
+    fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError>;
+
+    fn insert(&self, model: BankAccountRM) -> Result<(), DaoError>;
+
+    fn update(&self, model: BankAccountRM) -> Result<(), DaoError>;
+
+    fn overwrite(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        self.update(model)
+    }
+
+    fn list(&self) -> Result<Vec<BankAccountRM>, DaoError>;
+}
+
+impl<D: BankAccountRMDao + ?Sized> BankAccountRMDao for Arc<D> {
+    fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError> {
+        (**self).find(bank_account_id)
+    }
+
+    fn insert(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        (**self).insert(model)
+    }
+
+    fn update(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        (**self).update(model)
+    }
+
+    fn overwrite(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        (**self).overwrite(model)
+    }
+
+    fn list(&self) -> Result<Vec<BankAccountRM>, DaoError> {
+        (**self).list()
+    }
+}
+
+#[async_trait]
+pub trait AsyncBankAccountRMDao: Send + Sync {
+    async fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError>;
+
+    async fn insert(&self, model: BankAccountRM) -> Result<(), DaoError>;
 
-    fn insert(&self, model: BankAccountRM);
+    async fn update(&self, model: BankAccountRM) -> Result<(), DaoError>;
 
-    fn update(&self, model: BankAccountRM);
+    async fn list(&self) -> Result<Vec<BankAccountRM>, DaoError>;
 }
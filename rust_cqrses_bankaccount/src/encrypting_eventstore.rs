@@ -0,0 +1,185 @@
+use super::eventsourcing::{
+    EventStream,
+    Snapshot,
+    StreamMetadata,
+    EventStore,
+    EventStoreError,
+    EventStoreErrorKind,
+};
+use super::aggregate::{BankAccount, BankAccountEvent};
+use super::crypto::{CryptoError, PiiCipher};
+
+pub struct EncryptingEventStore<S> {
+    eventstore: S,
+    cipher: PiiCipher,
+}
+
+impl<S> EncryptingEventStore<S>
+    where S: EventStore<Event = BankAccountEvent, EventStream = EventStream<BankAccountEvent>, SnapshotData = BankAccount> {
+    pub fn new(eventstore: S, cipher: PiiCipher) -> Self {
+        Self {
+            eventstore,
+            cipher,
+        }
+    }
+
+    fn encrypt_events(&self, events: Vec<BankAccountEvent>) -> Result<Vec<BankAccountEvent>, EventStoreError> {
+        events.into_iter()
+            .map(|event| self.cipher.encrypt_event(event))
+            .collect::<Result<Vec<BankAccountEvent>, CryptoError>>()
+            .map_err(crypto_error)
+    }
+}
+
+fn crypto_error(err: CryptoError) -> EventStoreError {
+    EventStoreError::from(EventStoreErrorKind::EncryptionError(err.to_string()))
+}
+
+impl<S> EventStore for EncryptingEventStore<S>
+    where S: EventStore<Event = BankAccountEvent, EventStream = EventStream<BankAccountEvent>, SnapshotData = BankAccount> {
+    type Event = BankAccountEvent;
+    type EventStream = EventStream<BankAccountEvent>;
+    type SnapshotData = BankAccount;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        let events = self.encrypt_events(events)?;
+        self.eventstore.append_event_stream(stream_id, stream_version, events)
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        let stream = self.eventstore.event_stream_since(stream_id, stream_version)?;
+        let events = stream.events().iter()
+            .map(|event| self.cipher.decrypt_event(event.clone()))
+            .collect::<Result<Vec<BankAccountEvent>, CryptoError>>()
+            .map_err(crypto_error)?;
+        Ok(EventStream::new(events, stream.version()))
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        let data = self.cipher.encrypt_snapshot(snapshot.snapshot().clone()).map_err(crypto_error)?;
+        self.eventstore.record_snapshot(Snapshot::new(
+                snapshot.stream_id().to_string(), snapshot.stream_version(), data, *snapshot.created_at()))
+    }
+
+    fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        match self.eventstore.read_snapshot(stream_id)? {
+            Some(snapshot) => {
+                let data = self.cipher.decrypt_snapshot(snapshot.snapshot().clone()).map_err(crypto_error)?;
+                Ok(Some(Snapshot::new(
+                            snapshot.stream_id().to_string(), snapshot.stream_version(), data, *snapshot.created_at())))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        let events = self.encrypt_events(events)?;
+        self.eventstore.append_idempotent_event_stream(stream_id, stream_version, events, idempotency_key)
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        self.eventstore.idempotency_key_version(stream_id, idempotency_key)
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        self.eventstore.stream_metadata(stream_id)
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        self.eventstore.set_stream_metadata(stream_id, metadata)
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        self.eventstore.purge_stream(stream_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountId, BankAccountName, REDACTED_NAME};
+    use super::super::eventsourcing::EventStore;
+    use super::super::crypto::{InmemoryKeyStore, PiiCipher};
+    use super::super::dao::BankAccountRMDao;
+    use super::super::inmemory_dao::InmemoryBankAccountRMDao;
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::projector::BankAccountProjector;
+    use super::super::snapshotter::BankAccountAggregateSnapshotter;
+    use super::super::usecase::command::BankAccountAggregateUseCase;
+    use super::super::testing::verify_event_store;
+    use super::EncryptingEventStore;
+
+    fn cipher() -> PiiCipher {
+        PiiCipher::new(Arc::new(InmemoryKeyStore::new()))
+    }
+
+    #[test]
+    fn test_encrypting_store_behaviour() {
+        verify_event_store(&EncryptingEventStore::new(InmemoryBankAccountEventStore::new(), cipher()));
+    }
+
+    #[test]
+    fn test_erased_subject_is_redacted() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let cipher = cipher();
+        let encrypted = Arc::new(EncryptingEventStore::new(store.clone(), cipher.clone()));
+        let usecase = BankAccountAggregateUseCase::new(Box::new(encrypted.clone()));
+        let snapshotter = BankAccountAggregateSnapshotter::new(Box::new(encrypted.clone()));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        usecase.deposit(bank_account_id.clone(), 500).unwrap();
+        snapshotter.take_snapshot(bank_account_id.clone()).unwrap();
+        usecase.update(bank_account_id.clone(), BankAccountName::new(String::from("bar")).unwrap()).unwrap();
+        usecase.withdraw(bank_account_id.clone(), 200).unwrap();
+
+        let raw = store.event_stream_since(stream_id.clone(), 1).unwrap();
+        assert!(!format!("{:?}", raw.events()).contains("foo"));
+        assert!(!format!("{:?}", raw.events()).contains("bar"));
+        assert!(!format!("{:?}", store.read_snapshot(stream_id.clone()).unwrap()).contains("foo"));
+
+        let aggregate = usecase.get(bank_account_id.clone()).unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().name().value(), "bar");
+
+        cipher.erase_subject(&bank_account_id).unwrap();
+
+        let aggregate = usecase.get(bank_account_id.clone()).unwrap();
+        let state = aggregate.state().as_ref().unwrap();
+        assert!(state.name().is_redacted());
+        assert_eq!(state.balance(), 300);
+        assert_eq!(aggregate.version(), 4);
+
+        let snapshot = encrypted.read_snapshot(stream_id.clone()).unwrap().unwrap();
+        assert!(snapshot.snapshot().name().is_redacted());
+        assert_eq!(snapshot.snapshot().balance(), 500);
+
+        let dao = Arc::new(InmemoryBankAccountRMDao::new());
+        let projector = BankAccountProjector::new(Box::new(dao.clone()));
+        for (i, event) in encrypted.event_stream_since(stream_id.clone(), 1).unwrap().events().iter().enumerate() {
+            projector.project(i as u64 + 1, event.clone()).unwrap();
+        }
+        let rm = dao.find(bank_account_id.to_string()).unwrap().unwrap();
+        assert_eq!(rm.name, REDACTED_NAME);
+        assert_eq!(rm.balance, 300);
+
+        assert!(usecase.update(bank_account_id.clone(), BankAccountName::new(String::from("baz")).unwrap()).is_err());
+
+        snapshotter.take_snapshot(bank_account_id.clone()).unwrap();
+        let snapshot = store.read_snapshot(stream_id.clone()).unwrap().unwrap();
+        assert!(snapshot.snapshot().name().is_redacted());
+        assert_eq!(snapshot.stream_version(), 4);
+        let snapshot = encrypted.read_snapshot(stream_id).unwrap().unwrap();
+        assert!(snapshot.snapshot().name().is_redacted());
+        assert_eq!(snapshot.snapshot().balance(), 300);
+    }
+}
@@ -0,0 +1,226 @@
+use std::thread::{self, JoinHandle};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Sender};
+use std::panic::{self, AssertUnwindSafe};
+use log::error;
+
+use super::eventsourcing::{EventPublisher, EventPublisherError};
+
+pub trait EventSubscriber: Send + Sync {
+    type Event;
+
+    fn on_events(&self, stream_id: &str, stream_version: u64, events: &[Self::Event])
+        -> Result<(), EventPublisherError>;
+}
+
+impl<S: EventSubscriber + ?Sized> EventSubscriber for Arc<S> {
+    type Event = S::Event;
+
+    fn on_events(&self, stream_id: &str, stream_version: u64, events: &[Self::Event])
+        -> Result<(), EventPublisherError> {
+        (**self).on_events(stream_id, stream_version, events)
+    }
+}
+
+enum Subscription<E> {
+    Sync {
+        name: String,
+        subscriber: Box<dyn EventSubscriber<Event = E>>,
+    },
+    Channel {
+        name: String,
+        sender: Sender<(String, u64, Vec<E>)>,
+        worker: JoinHandle<()>,
+    },
+}
+
+fn deliver<E>(name: &str, subscriber: &dyn EventSubscriber<Event = E>, stream_id: &str, stream_version: u64,
+              events: &[E]) {
+    match panic::catch_unwind(AssertUnwindSafe(|| subscriber.on_events(stream_id, stream_version, events))) {
+        Ok(Ok(_)) => (),
+        Ok(Err(err)) => error!("Subscriber {} failed on {}: {}", name, stream_id, err),
+        Err(_) => error!("Subscriber {} panicked on {}", name, stream_id),
+    }
+}
+
+pub struct EventBus<E> {
+    subscriptions: Mutex<Vec<Subscription<E>>>,
+}
+
+impl<E: Clone + Send + 'static> EventBus<E> {
+    pub fn new() -> Self {
+        Self {
+            subscriptions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe<S>(&self, name: &str, subscriber: S)
+        where S: EventSubscriber<Event = E> + 'static {
+        self.subscriptions.lock().unwrap().push(Subscription::Sync {
+            name: name.to_string(),
+            subscriber: Box::new(subscriber),
+        });
+    }
+
+    pub fn subscribe_channel<S>(&self, name: &str, subscriber: S)
+        where S: EventSubscriber<Event = E> + 'static {
+        let (sender, receiver) = mpsc::channel::<(String, u64, Vec<E>)>();
+        let worker_name = name.to_string();
+        let worker = thread::spawn(move || {
+            for (stream_id, stream_version, events) in receiver {
+                deliver(&worker_name, &subscriber, &stream_id, stream_version, &events);
+            }
+        });
+
+        self.subscriptions.lock().unwrap().push(Subscription::Channel {
+            name: name.to_string(),
+            sender,
+            worker,
+        });
+    }
+}
+
+impl<E: Clone + Send + 'static> Default for EventBus<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> EventBus<E> {
+    pub fn shutdown(&self) {
+        let subscriptions: Vec<Subscription<E>> = self.subscriptions.lock().unwrap().drain(..).collect();
+        for subscription in subscriptions {
+            if let Subscription::Channel { name, sender, worker } = subscription {
+                drop(sender);
+                if worker.join().is_err() {
+                    error!("Subscriber {} worker terminated abnormally", name);
+                }
+            }
+        }
+    }
+}
+
+impl<E> Drop for EventBus<E> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl<E: Clone + Send + 'static> EventPublisher for EventBus<E> {
+    type Event = E;
+
+    fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError> {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        for subscription in subscriptions.iter() {
+            match subscription {
+                Subscription::Sync { name, subscriber } => {
+                    deliver(name, &**subscriber, &stream_id, stream_version, &events);
+                },
+                Subscription::Channel { name, sender, .. } => {
+                    if sender.send((stream_id.clone(), stream_version, events.clone())).is_err() {
+                        error!("Subscriber {} is no longer receiving events", name);
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::eventsourcing::{EventStore, EventPublisherError, EventPublisherErrorKind};
+    use super::super::dao::BankAccountRMDao;
+    use super::super::inmemory_dao::InmemoryBankAccountRMDao;
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::projector::BankAccountProjector;
+    use super::super::publishing_eventstore::PublishingEventStore;
+    use super::super::snapshotter::BankAccountAggregateSnapshotter;
+    use super::super::usecase::command::BankAccountAggregateUseCase;
+    use super::{EventBus, EventSubscriber};
+
+    struct Recorder {
+        received: Mutex<Vec<(String, usize)>>,
+    }
+
+    impl EventSubscriber for Recorder {
+        type Event = BankAccountEvent;
+
+        fn on_events(&self, stream_id: &str, _stream_version: u64, events: &[BankAccountEvent])
+            -> Result<(), EventPublisherError> {
+            self.received.lock().unwrap().push((stream_id.to_string(), events.len()));
+            Ok(())
+        }
+    }
+
+    struct Failing {
+        panic: bool,
+    }
+
+    impl EventSubscriber for Failing {
+        type Event = BankAccountEvent;
+
+        fn on_events(&self, _stream_id: &str, _stream_version: u64, _events: &[BankAccountEvent])
+            -> Result<(), EventPublisherError> {
+            if self.panic {
+                panic!("subscriber panicked");
+            }
+            Err(EventPublisherErrorKind::SubscriberError(String::from("subscriber failed")))?
+        }
+    }
+
+    #[test]
+    fn test_event_bus_full_loop() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let dao = Arc::new(InmemoryBankAccountRMDao::new());
+        let recorder = Arc::new(Recorder { received: Mutex::new(Vec::new()) });
+        let bus = Arc::new(EventBus::new());
+
+        bus.subscribe("failing", Failing { panic: false });
+        bus.subscribe("panicking", Failing { panic: true });
+        bus.subscribe("recorder", recorder.clone());
+        bus.subscribe("snapshotter", BankAccountAggregateSnapshotter::new(Box::new(store.clone())));
+        bus.subscribe_channel("projector", BankAccountProjector::new(Box::new(dao.clone())));
+
+        let usecase = BankAccountAggregateUseCase::new(Box::new(
+                PublishingEventStore::new(store.clone(), bus.clone())));
+
+        let ids: Vec<BankAccountId> = vec![
+            BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+            BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap(),
+        ];
+        for id in ids.iter() {
+            usecase.open(id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        }
+        for _ in 0..10 {
+            for id in ids.iter() {
+                usecase.deposit(id.clone(), 100).unwrap();
+            }
+        }
+        usecase.withdraw(ids[0].clone(), 50).unwrap();
+
+        bus.shutdown();
+
+        let received = recorder.received.lock().unwrap();
+        assert_eq!(received.len(), 23);
+        for id in ids.iter() {
+            let stream_id = BankAccountAggregate::stream_id(id);
+            assert_eq!(received.iter().filter(|(received_id, _)| received_id == &stream_id).count(),
+                       usecase.get(id.clone()).unwrap().version() as usize);
+        }
+
+        let rm = dao.find(ids[0].to_string()).unwrap().unwrap();
+        assert_eq!(rm.balance, 950);
+        assert_eq!(rm.version, 12);
+        let rm = dao.find(ids[1].to_string()).unwrap().unwrap();
+        assert_eq!(rm.balance, 1000);
+        assert_eq!(rm.version, 11);
+
+        let snapshot = store.read_snapshot(BankAccountAggregate::stream_id(&ids[0])).unwrap().unwrap();
+        assert_eq!(snapshot.stream_version(), 12);
+    }
+}
@@ -1,6 +1,9 @@
 use std::fmt;
+use std::sync::Arc;
 use failure::{Fail, Context, Backtrace};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Duration, Local};
+use serde::{Serialize, Deserialize};
+use async_trait::async_trait;
 
 #[derive(Debug, Clone)]
 pub struct EventStream<Event> {
@@ -60,6 +63,62 @@ impl<Data> Snapshot<Data> {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EventMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PurgedEvent {
+    pub stream_version: u64,
+    pub event_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StreamMetadata {
+    pub deleted: bool,
+    pub truncate_before: Option<u64>,
+    pub max_age: Option<u64>,
+    #[serde(default)]
+    pub last_purged: Option<PurgedEvent>,
+}
+
+impl StreamMetadata {
+    pub fn truncated_before(&self, snapshot_version: u64) -> u64 {
+        std::cmp::min(self.truncate_before.unwrap_or(0), snapshot_version + 1)
+    }
+
+    pub fn first_readable_version(&self, stream_version: u64, snapshot_version: u64) -> u64 {
+        std::cmp::max(stream_version, self.truncated_before(snapshot_version))
+    }
+
+    pub fn expired_before(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.max_age.map(|max_age| now - Duration::seconds(max_age as i64))
+    }
+
+    pub fn is_readable(&self, stream_version: u64, occurred_at: &DateTime<Local>, snapshot_version: u64,
+                       now: DateTime<Local>) -> bool {
+        if self.deleted || stream_version < self.truncated_before(snapshot_version) {
+            return false;
+        }
+        match self.expired_before(now) {
+            Some(expired_before) => stream_version > snapshot_version || occurred_at >= &expired_before,
+            None => true,
+        }
+    }
+
+    pub fn check_truncation(&self, snapshot_version: u64) -> Result<(), EventStoreError> {
+        match self.truncate_before {
+            Some(truncate_before) if truncate_before > snapshot_version + 1 => {
+                Err(EventStoreErrorKind::InvalidStreamMetadataError(
+                        format!("truncate_before {} is past the snapshot at {}", truncate_before, snapshot_version)))?
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct EventStoreError {
     inner: Context<EventStoreErrorKind>,
@@ -81,6 +140,27 @@ pub enum EventStoreErrorKind {
 
     #[fail(display = "Query error: {:?}", _0)]
     QueryError(String),
+
+    #[fail(display = "Corrupted event error: {:?}", _0)]
+    CorruptedEventError(String),
+
+    #[fail(display = "Blocking task error: {:?}", _0)]
+    BlockingTaskError(String),
+
+    #[fail(display = "Publish event stream error: {:?}", _0)]
+    PublishEventStreamError(String),
+
+    #[fail(display = "Event stream is deleted: {}", _0)]
+    StreamDeletedError(String),
+
+    #[fail(display = "Unsupported operation: {:?}", _0)]
+    UnsupportedOperationError(String),
+
+    #[fail(display = "Invalid stream metadata: {:?}", _0)]
+    InvalidStreamMetadataError(String),
+
+    #[fail(display = "Encryption error: {:?}", _0)]
+    EncryptionError(String),
 }
 
 impl Fail for EventStoreError {
@@ -117,6 +197,57 @@ impl From<Context<EventStoreErrorKind>> for EventStoreError {
     }
 }
 
+#[derive(Debug)]
+pub struct EventPublisherError {
+    inner: Context<EventPublisherErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum EventPublisherErrorKind {
+    #[fail(display = "Connection error: {:?}", _0)]
+    ConnectionError(String),
+
+    #[fail(display = "Publish error: {:?}", _0)]
+    PublishError(String),
+
+    #[fail(display = "Subscriber error: {:?}", _0)]
+    SubscriberError(String),
+}
+
+impl Fail for EventPublisherError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for EventPublisherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl EventPublisherError {
+    pub fn kind(&self) -> &EventPublisherErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<EventPublisherErrorKind> for EventPublisherError {
+    fn from(kind: EventPublisherErrorKind) -> EventPublisherError {
+        EventPublisherError { inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<EventPublisherErrorKind>> for EventPublisherError {
+    fn from(inner: Context<EventPublisherErrorKind>) -> EventPublisherError {
+        EventPublisherError { inner }
+    }
+}
+
 pub trait EventStore: Send + Sync {
     type Event;
     type EventStream;
@@ -133,10 +264,158 @@ pub trait EventStore: Send + Sync {
 
     fn read_snapshot(&self, stream_id: String)
         -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError>;
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      _idempotency_key: String) -> Result<(), EventStoreError> {
+        self.append_event_stream(stream_id, stream_version, events)
+    }
+
+    fn idempotency_key_version(&self, _stream_id: String, _idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("idempotency_key_version")))?
+    }
+
+    fn stream_metadata(&self, _stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("stream_metadata")))?
+    }
+
+    fn set_stream_metadata(&self, _stream_id: String, _metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("set_stream_metadata")))?
+    }
+
+    fn purge_stream(&self, _stream_id: String) -> Result<u64, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("purge_stream")))?
+    }
+}
+
+#[async_trait]
+pub trait AsyncEventStore: Send + Sync {
+    type Event;
+    type EventStream;
+    type SnapshotData;
+
+    async fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError>;
+
+    async fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError>;
+
+    async fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError>;
+
+    async fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError>;
+
+    async fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                            _idempotency_key: String) -> Result<(), EventStoreError>
+        where Self::Event: Send + 'async_trait {
+        self.append_event_stream(stream_id, stream_version, events).await
+    }
+
+    async fn idempotency_key_version(&self, _stream_id: String, _idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("idempotency_key_version")))?
+    }
+
+    async fn stream_metadata(&self, _stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("stream_metadata")))?
+    }
+
+    async fn set_stream_metadata(&self, _stream_id: String, _metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("set_stream_metadata")))?
+    }
+
+    async fn purge_stream(&self, _stream_id: String) -> Result<u64, EventStoreError> {
+        Err(EventStoreErrorKind::UnsupportedOperationError(String::from("purge_stream")))?
+    }
+}
+
+impl<S: EventStore + ?Sized> EventStore for Arc<S> {
+    type Event = S::Event;
+    type EventStream = S::EventStream;
+    type SnapshotData = S::SnapshotData;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        (**self).append_event_stream(stream_id, stream_version, events)
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        (**self).event_stream_since(stream_id, stream_version)
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        (**self).record_snapshot(snapshot)
+    }
+
+    fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        (**self).read_snapshot(stream_id)
+    }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        (**self).append_idempotent_event_stream(stream_id, stream_version, events, idempotency_key)
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        (**self).idempotency_key_version(stream_id, idempotency_key)
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        (**self).stream_metadata(stream_id)
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        (**self).set_stream_metadata(stream_id, metadata)
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        (**self).purge_stream(stream_id)
+    }
+}
+
+pub trait Aggregate: Sized {
+    type Command;
+    type Event;
+    type Error;
+
+    fn handle_command(aggregate: &Self, command: Self::Command)
+        -> Result<Vec<Self::Event>, Self::Error>;
+
+    fn apply_event(aggregate: &Self, event: Self::Event)
+        -> Result<Self, Self::Error>;
+
+    fn load_from_history(aggregate: &Self, history: Vec<Self::Event>, version: u64)
+        -> Result<Self, Self::Error>;
 }
 
 pub trait EventPublisher {
     type Event;
 
-    fn publish(&self, event: Self::Event);
+    fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError>;
+}
+
+impl<P: EventPublisher + ?Sized> EventPublisher for Arc<P> {
+    type Event = P::Event;
+
+    fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError> {
+        (**self).publish(stream_id, stream_version, events)
+    }
+}
+
+#[async_trait]
+pub trait AsyncEventPublisher: Send + Sync {
+    type Event;
+
+    async fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError>;
 }
@@ -0,0 +1,584 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::collections::HashMap;
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+
+use super::eventsourcing::{EventStream, Snapshot, EventStoreError, EventStoreErrorKind, EventStore};
+use super::inmemory_eventstore::StoredEvent;
+use super::aggregate::{BankAccountEvent, BankAccount};
+use super::hashchain::{GENESIS_HASH, ChainHead, HashChainedEventStore, event_hash};
+
+const HEADER_SIZE: u64 = 8;
+
+pub const DEFAULT_MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct EventPosition {
+    stream_version: u64,
+    segment: u64,
+    offset: u64,
+}
+
+struct State {
+    segment: u64,
+    writer: File,
+    size: u64,
+    index: HashMap<String, Vec<EventPosition>>,
+    heads: HashMap<String, ChainHead>,
+}
+
+pub struct FileEventStore {
+    directory: PathBuf,
+    max_segment_size: u64,
+    state: Mutex<State>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotRecord {
+    stream_id: String,
+    stream_version: u64,
+    data: BankAccount,
+    created_at: DateTime<Local>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeadRecord {
+    stream_id: String,
+    head: ChainHead,
+}
+
+fn io_error(err: std::io::Error) -> EventStoreError {
+    EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string()))
+}
+
+fn corrupted(segment: u64, offset: u64, reason: &str) -> EventStoreError {
+    EventStoreError::from(EventStoreErrorKind::CorruptedEventError(
+            format!("segment {} at offset {}: {}", segment, offset, reason)))
+}
+
+fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+fn encode_record(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER_SIZE as usize + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+enum Record {
+    Complete(Vec<u8>),
+    Torn,
+    End,
+}
+
+fn read_record<R: Read>(reader: &mut R, remaining: u64) -> Result<Record, String> {
+    if remaining == 0 {
+        return Ok(Record::End);
+    }
+    if remaining < HEADER_SIZE {
+        return Ok(Record::Torn);
+    }
+    let mut header = [0u8; HEADER_SIZE as usize];
+    reader.read_exact(&mut header).map_err(|err| err.to_string())?;
+    let mut len = [0u8; 4];
+    let mut checksum = [0u8; 4];
+    len.copy_from_slice(&header[0..4]);
+    checksum.copy_from_slice(&header[4..8]);
+
+    let len = u32::from_le_bytes(len) as u64;
+    if len > remaining - HEADER_SIZE {
+        return Ok(Record::Torn);
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).map_err(|err| err.to_string())?;
+    if crc32fast::hash(&payload) != u32::from_le_bytes(checksum) {
+        return Err(String::from("checksum mismatch"));
+    }
+    Ok(Record::Complete(payload))
+}
+
+impl FileEventStore {
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, EventStoreError> {
+        Self::with_max_segment_size(directory, DEFAULT_MAX_SEGMENT_SIZE)
+    }
+
+    pub fn with_max_segment_size<P: AsRef<Path>>(directory: P, max_segment_size: u64)
+        -> Result<Self, EventStoreError> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(directory.join("segments")).map_err(io_error)?;
+        fs::create_dir_all(directory.join("snapshots")).map_err(io_error)?;
+        fs::create_dir_all(directory.join("heads")).map_err(io_error)?;
+
+        let mut segments: Vec<u64> = fs::read_dir(directory.join("segments")).map_err(io_error)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.strip_suffix(".log")?.parse().ok()
+            })
+            .collect();
+        segments.sort();
+        if segments.is_empty() {
+            segments.push(1);
+        }
+
+        let mut index: HashMap<String, Vec<EventPosition>> = HashMap::new();
+        let last = *segments.last().unwrap();
+        let mut size = 0;
+        for segment in segments {
+            size = Self::recover_segment(&directory, segment, segment == last, &mut index)?;
+        }
+
+        let writer = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::segment_path(&directory, last))
+            .map_err(io_error)?;
+        let heads = Self::load_heads(&directory)?;
+
+        Ok(Self {
+            directory,
+            max_segment_size,
+            state: Mutex::new(State {
+                segment: last,
+                writer,
+                size,
+                index,
+                heads,
+            }),
+        })
+    }
+
+    fn segment_path(directory: &Path, segment: u64) -> PathBuf {
+        directory.join("segments").join(format!("{:016}.log", segment))
+    }
+
+    fn snapshot_path(&self, stream_id: &str) -> PathBuf {
+        let name: String = stream_id.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.directory.join("snapshots").join(format!("{}.json", name))
+    }
+
+    fn head_path(&self, stream_id: &str) -> PathBuf {
+        let name: String = stream_id.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.directory.join("heads").join(format!("{}.json", name))
+    }
+
+    fn load_heads(directory: &Path) -> Result<HashMap<String, ChainHead>, EventStoreError> {
+        let mut heads = HashMap::new();
+        for entry in fs::read_dir(directory.join("heads")).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let record: HeadRecord = serde_json::from_slice(&fs::read(&path).map_err(io_error)?)
+                .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+            heads.insert(record.stream_id, record.head);
+        }
+        Ok(heads)
+    }
+
+    fn recover_segment(directory: &Path, segment: u64, is_last: bool,
+                       index: &mut HashMap<String, Vec<EventPosition>>) -> Result<u64, EventStoreError> {
+        let path = Self::segment_path(directory, segment);
+        let mut file = match OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(io_error(err)),
+        };
+        let len = file.metadata().map_err(io_error)?.len();
+
+        let mut offset = 0;
+        let mut reader = std::io::BufReader::new(&mut file);
+        loop {
+            let record = match read_record(&mut reader, len - offset) {
+                Ok(Record::Complete(payload)) => payload,
+                Ok(Record::End) => break,
+                Ok(Record::Torn) if is_last => break,
+                Ok(Record::Torn) => return Err(corrupted(segment, offset, "record runs past the end of the segment")),
+                Err(reason) => return Err(corrupted(segment, offset, &reason)),
+            };
+            let stored: StoredEvent = match serde_json::from_slice(&record) {
+                Ok(stored) => stored,
+                Err(err) => return Err(corrupted(segment, offset, &err.to_string())),
+            };
+            index.entry(stored.stream_id().to_string())
+                .or_default()
+                .push(EventPosition {
+                    stream_version: stored.stream_version(),
+                    segment,
+                    offset,
+                });
+            offset += HEADER_SIZE + record.len() as u64;
+        }
+        drop(reader);
+
+        if offset < len {
+            if !is_last {
+                return Err(corrupted(segment, offset, "trailing bytes"));
+            }
+            file.set_len(offset).map_err(io_error)?;
+            file.sync_all().map_err(io_error)?;
+        }
+        Ok(offset)
+    }
+
+    fn rotate(&self, state: &mut State) -> Result<(), EventStoreError> {
+        let segment = state.segment + 1;
+        let writer = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::segment_path(&self.directory, segment))
+            .map_err(io_error)?;
+        if let Ok(dir) = File::open(self.directory.join("segments")) {
+            let _ = dir.sync_all();
+        }
+        state.segment = segment;
+        state.writer = writer;
+        state.size = 0;
+        Ok(())
+    }
+
+    fn read_event(&self, position: &EventPosition) -> Result<StoredEvent, EventStoreError> {
+        let mut file = File::open(Self::segment_path(&self.directory, position.segment)).map_err(io_error)?;
+        let len = file.metadata().map_err(io_error)?.len();
+        file.seek(SeekFrom::Start(position.offset)).map_err(io_error)?;
+        match read_record(&mut file, len.saturating_sub(position.offset)) {
+            Ok(Record::Complete(payload)) => serde_json::from_slice(&payload)
+                .map_err(|err| corrupted(position.segment, position.offset, &err.to_string())),
+            Ok(_) => Err(corrupted(position.segment, position.offset, "truncated record")),
+            Err(reason) => Err(corrupted(position.segment, position.offset, &reason)),
+        }
+    }
+}
+
+impl EventStore for FileEventStore {
+    type Event = BankAccountEvent;
+    type EventStream = EventStream<Self::Event>;
+    type SnapshotData = BankAccount;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        let mut state = self.state.lock().unwrap();
+
+        let last_version = state.index.get(&stream_id)
+            .and_then(|positions| positions.last())
+            .map(|position| position.stream_version)
+            .unwrap_or(0);
+        if stream_version <= last_version {
+            return Err(EventStoreErrorKind::DuplicateEntryError(format!("{}:{}", stream_id, stream_version)))?;
+        }
+
+        if state.size >= self.max_segment_size {
+            self.rotate(&mut state)?;
+        }
+
+        let mut previous_hash = match state.index.get(&stream_id).and_then(|positions| positions.last()) {
+            Some(position) => self.read_event(position)?.event_hash().to_string(),
+            None => GENESIS_HASH.to_string(),
+        };
+
+        let mut buffer = vec![];
+        let mut positions = vec![];
+        for (i, event) in events.iter().enumerate() {
+            let event_type = event.event_type().to_string();
+            let event_body = serde_json::to_string(event).unwrap();
+            let version = stream_version + i as u64;
+            let hash = event_hash(&previous_hash, &stream_id, version, &event_type, &event_body, None);
+            let stored = StoredEvent::new(
+                event_type,
+                event_body,
+                event.occurred_at(),
+                stream_id.clone(),
+                version,
+                hash.clone(),
+                );
+            previous_hash = hash;
+            let payload = serde_json::to_vec(&stored).unwrap();
+            positions.push(EventPosition {
+                stream_version: stored.stream_version(),
+                segment: state.segment,
+                offset: state.size + buffer.len() as u64,
+            });
+            buffer.extend(encode_record(&payload));
+        }
+
+        let written = state.writer.write_all(&buffer)
+            .and_then(|_| state.writer.sync_data());
+        if let Err(err) = written {
+            let _ = state.writer.set_len(state.size);
+            return Err(EventStoreErrorKind::AppendEventStreamError(err.to_string()))?;
+        }
+
+        let head = positions.last().map(|position| HeadRecord {
+            stream_id: stream_id.clone(),
+            head: ChainHead {
+                stream_version: position.stream_version,
+                event_hash: previous_hash,
+            },
+        });
+        state.size += buffer.len() as u64;
+        state.index.entry(stream_id).or_default().extend(positions);
+
+        if let Some(record) = head {
+            write_file(&self.head_path(&record.stream_id), &serde_json::to_vec(&record).unwrap())
+                .map_err(|err| EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())))?;
+            state.heads.insert(record.stream_id, record.head);
+        }
+        Ok(())
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        let positions: Vec<EventPosition> = {
+            let state = self.state.lock().unwrap();
+            match state.index.get(&stream_id) {
+                Some(positions) => positions.iter()
+                    .filter(|position| position.stream_version >= stream_version)
+                    .cloned()
+                    .collect(),
+                None => vec![],
+            }
+        };
+        if positions.is_empty() {
+            return Err(EventStoreErrorKind::NoEventStreamError(stream_id, stream_version))?;
+        }
+
+        let mut events = vec![];
+        for position in positions.iter() {
+            let stored = self.read_event(position)?;
+            let event = serde_json::from_str(stored.event_body())
+                .map_err(|err| corrupted(position.segment, position.offset, &err.to_string()))?;
+            events.push(event);
+        }
+        Ok(Self::EventStream::new(events, positions.last().unwrap().stream_version))
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>) -> Result<(), EventStoreError> {
+        let path = self.snapshot_path(snapshot.stream_id());
+        let record = SnapshotRecord {
+            stream_id: snapshot.stream_id().to_string(),
+            stream_version: snapshot.stream_version(),
+            data: snapshot.snapshot().clone(),
+            created_at: *snapshot.created_at(),
+        };
+
+        write_file(&path, &serde_json::to_vec(&record).unwrap())
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::AppendEventStreamError(err.to_string())))
+    }
+
+    fn read_snapshot(&self, stream_id: String) -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        let data = match fs::read(self.snapshot_path(&stream_id)) {
+            Ok(data) => data,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(io_error(err)),
+        };
+        let record: SnapshotRecord = serde_json::from_slice(&data)
+            .map_err(|err| EventStoreError::from(EventStoreErrorKind::QueryError(err.to_string())))?;
+        Ok(Some(Snapshot::new(record.stream_id, record.stream_version, record.data, record.created_at)))
+    }
+}
+
+impl HashChainedEventStore for FileEventStore {
+    fn stream_ids(&self) -> Result<Vec<String>, EventStoreError> {
+        let state = self.state.lock().unwrap();
+        let mut stream_ids: Vec<String> = state.index.keys().chain(state.heads.keys()).cloned().collect();
+        stream_ids.sort();
+        stream_ids.dedup();
+        Ok(stream_ids)
+    }
+
+    fn stored_events(&self, stream_id: String) -> Result<Vec<StoredEvent>, EventStoreError> {
+        let positions = self.state.lock().unwrap().index.get(&stream_id).cloned().unwrap_or_default();
+        positions.iter().map(|position| self.read_event(position)).collect()
+    }
+
+    fn chain_head(&self, stream_id: String) -> Result<Option<ChainHead>, EventStoreError> {
+        Ok(self.state.lock().unwrap().heads.get(&stream_id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::{Seek, SeekFrom, Write};
+    use chrono::Local;
+    use tempfile::TempDir;
+
+    use super::FileEventStore;
+    use super::super::eventsourcing::{EventStore, EventStoreErrorKind};
+    use super::super::aggregate::{BankAccountEvent, BankAccountId, BankAccountAggregate};
+    use super::super::testing::verify_event_store;
+    use super::super::hashchain::{BrokenLink, HashChainedEventStore, verify_store};
+
+    fn deposited(deposit: i32) -> BankAccountEvent {
+        BankAccountEvent::Deposited {
+            bank_account_id: BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+            deposit,
+            occurred_at: Local::now(),
+        }
+    }
+
+    fn stream_id() -> String {
+        BankAccountAggregate::stream_id(&BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap())
+    }
+
+    #[test]
+    fn test_file_store_behaviour() {
+        let dir = TempDir::new().unwrap();
+        verify_event_store(&FileEventStore::open(dir.path()).unwrap());
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert_eq!(store.event_stream_since(stream_id(), 1).unwrap().version(), 2);
+        assert!(store.read_snapshot(stream_id()).unwrap().is_some());
+
+        for result in [
+            store.stream_metadata(stream_id()).map(|_| ()),
+            store.purge_stream(stream_id()).map(|_| ()),
+            store.idempotency_key_version(stream_id(), String::from("deposit")).map(|_| ()),
+        ] {
+            match result.unwrap_err().kind() {
+                EventStoreErrorKind::UnsupportedOperationError(_) => (),
+                kind => panic!("unexpected error: {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn test_file_store_rotates_segments() {
+        let dir = TempDir::new().unwrap();
+        let store = FileEventStore::with_max_segment_size(dir.path(), 1).unwrap();
+        let events: Vec<BankAccountEvent> = (1..=5).map(deposited).collect();
+        for (i, event) in events.iter().enumerate() {
+            store.append_event_stream(stream_id(), i as u64 + 1, vec![event.clone()]).unwrap();
+        }
+        assert_eq!(std::fs::read_dir(dir.path().join("segments")).unwrap().count(), 5);
+
+        let store = FileEventStore::with_max_segment_size(dir.path(), 1).unwrap();
+        let stream = store.event_stream_since(stream_id(), 3).unwrap();
+        assert_eq!(stream.events(), &events[2..].to_vec());
+        assert_eq!(stream.version(), 5);
+
+        let verification = verify_store(&store).unwrap();
+        assert_eq!(verification.events, 5);
+        assert!(verification.broken_link.is_none());
+    }
+
+    #[test]
+    fn test_file_store_recovers_from_torn_write() {
+        let dir = TempDir::new().unwrap();
+        {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store.append_event_stream(stream_id(), 1, vec![deposited(100), deposited(200)]).unwrap();
+        }
+
+        let segment = dir.path().join("segments").join(format!("{:016}.log", 1));
+        let len = std::fs::metadata(&segment).unwrap().len();
+        {
+            let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+            file.write_all(&[42, 0, 0, 0, 1, 2]).unwrap();
+        }
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert_eq!(std::fs::metadata(&segment).unwrap().len(), len);
+        assert_eq!(store.event_stream_since(stream_id(), 1).unwrap().version(), 2);
+
+        store.append_event_stream(stream_id(), 3, vec![deposited(300)]).unwrap();
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert_eq!(store.event_stream_since(stream_id(), 1).unwrap().events().len(), 3);
+    }
+
+    #[test]
+    fn test_file_store_detects_truncated_tail() {
+        let dir = TempDir::new().unwrap();
+        let segment = dir.path().join("segments").join(format!("{:016}.log", 1));
+        let len = {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store.append_event_stream(stream_id(), 1, vec![deposited(100), deposited(200)]).unwrap();
+            let len = std::fs::metadata(&segment).unwrap().len();
+            store.append_event_stream(stream_id(), 3, vec![deposited(300)]).unwrap();
+            assert_eq!(store.chain_head(stream_id()).unwrap().unwrap().stream_version, 3);
+            len
+        };
+        OpenOptions::new().write(true).open(&segment).unwrap().set_len(len).unwrap();
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        let verification = verify_store(&store).unwrap();
+        assert_eq!(verification.events, 2);
+        match verification.broken_link {
+            Some(BrokenLink::TruncatedTail { head_version, last_version, .. }) => {
+                assert_eq!(head_version, 3);
+                assert_eq!(last_version, 2);
+            },
+            broken_link => panic!("unexpected result: {:?}", broken_link),
+        }
+    }
+
+    #[test]
+    fn test_file_store_refuses_corrupted_record() {
+        let dir = TempDir::new().unwrap();
+        {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store.append_event_stream(stream_id(), 1, vec![deposited(100), deposited(200)]).unwrap();
+        }
+
+        let segment = dir.path().join("segments").join(format!("{:016}.log", 1));
+        let len = std::fs::metadata(&segment).unwrap().len();
+        {
+            let mut file = OpenOptions::new().write(true).open(&segment).unwrap();
+            file.seek(SeekFrom::Start(super::HEADER_SIZE + 2)).unwrap();
+            file.write_all(b"#").unwrap();
+        }
+
+        assert!(FileEventStore::open(dir.path()).is_err());
+        assert_eq!(std::fs::metadata(&segment).unwrap().len(), len);
+    }
+
+    #[test]
+    fn test_file_store_refuses_corrupted_final_record() {
+        let dir = TempDir::new().unwrap();
+        {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store.append_event_stream(stream_id(), 1, vec![deposited(100), deposited(200)]).unwrap();
+        }
+
+        let segment = dir.path().join("segments").join(format!("{:016}.log", 1));
+        let len = std::fs::metadata(&segment).unwrap().len();
+        {
+            let mut file = OpenOptions::new().write(true).open(&segment).unwrap();
+            file.seek(SeekFrom::Start(len - 2)).unwrap();
+            file.write_all(b"#").unwrap();
+        }
+
+        assert!(FileEventStore::open(dir.path()).is_err());
+        assert_eq!(std::fs::metadata(&segment).unwrap().len(), len);
+    }
+
+    #[test]
+    fn test_file_store_refuses_oversized_record() {
+        let dir = TempDir::new().unwrap();
+        let store = FileEventStore::with_max_segment_size(dir.path(), 1).unwrap();
+        store.append_event_stream(stream_id(), 1, vec![deposited(100)]).unwrap();
+        store.append_event_stream(stream_id(), 2, vec![deposited(200)]).unwrap();
+
+        let segment = dir.path().join("segments").join(format!("{:016}.log", 1));
+        {
+            let mut file = OpenOptions::new().write(true).open(&segment).unwrap();
+            file.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        }
+
+        match store.event_stream_since(stream_id(), 1).unwrap_err().kind() {
+            EventStoreErrorKind::CorruptedEventError(_) => (),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+        assert!(FileEventStore::with_max_segment_size(dir.path(), 1).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt;
+use sha2::{Digest, Sha256};
+use serde::{Serialize, Deserialize};
+
+use super::eventsourcing::{StreamMetadata, EventStoreError, EventStoreErrorKind, EventStore};
+use super::inmemory_eventstore::StoredEvent;
+
+pub const GENESIS_HASH: &str = "";
+
+pub fn event_hash(previous_hash: &str, stream_id: &str, stream_version: u64, event_type: &str, event_body: &str,
+                  event_metadata: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    for field in [previous_hash.as_bytes(), stream_id.as_bytes(), event_type.as_bytes(), event_body.as_bytes()].iter() {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field);
+    }
+    hasher.update(stream_version.to_le_bytes());
+    if let Some(event_metadata) = event_metadata {
+        hasher.update((event_metadata.len() as u64).to_le_bytes());
+        hasher.update(event_metadata.as_bytes());
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChainHead {
+    pub stream_version: u64,
+    pub event_hash: String,
+}
+
+pub trait HashChainedEventStore: EventStore {
+    fn stream_ids(&self) -> Result<Vec<String>, EventStoreError>;
+
+    fn stored_events(&self, stream_id: String) -> Result<Vec<StoredEvent>, EventStoreError>;
+
+    fn chain_head(&self, stream_id: String) -> Result<Option<ChainHead>, EventStoreError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrokenLink {
+    MissingVersion {
+        stream_id: String,
+        expected_version: u64,
+        actual_version: u64,
+    },
+    HashMismatch {
+        stream_id: String,
+        stream_version: u64,
+        expected_hash: String,
+        actual_hash: String,
+    },
+    TruncatedTail {
+        stream_id: String,
+        head_version: u64,
+        last_version: u64,
+    },
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BrokenLink::MissingVersion { stream_id, expected_version, actual_version } =>
+                write!(f, "{}: expected version {} but found {}", stream_id, expected_version, actual_version),
+            BrokenLink::HashMismatch { stream_id, stream_version, expected_hash, actual_hash } =>
+                write!(f, "{}@{}: expected hash {} but found {}", stream_id, stream_version, expected_hash, actual_hash),
+            BrokenLink::TruncatedTail { stream_id, head_version, last_version } =>
+                write!(f, "{}: head is at version {} but the last event is {}", stream_id, head_version, last_version),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainVerification {
+    pub streams: u64,
+    pub events: u64,
+    pub broken_link: Option<BrokenLink>,
+}
+
+pub fn verify_events(events: &[StoredEvent], metadata: &StreamMetadata) -> Result<u64, BrokenLink> {
+    match walk_chain(events, metadata) {
+        (verified, None) => Ok(verified),
+        (_, Some(broken_link)) => Err(broken_link),
+    }
+}
+
+fn walk_chain(events: &[StoredEvent], metadata: &StreamMetadata) -> (u64, Option<BrokenLink>) {
+    let mut previous: Option<(&str, u64)> = None;
+    for (verified, event) in events.iter().enumerate() {
+        let verified = verified as u64;
+        let previous_hash = match previous {
+            Some((hash, version)) => {
+                if event.stream_version() != version + 1 {
+                    return (verified, Some(BrokenLink::MissingVersion {
+                        stream_id: event.stream_id().to_string(),
+                        expected_version: version + 1,
+                        actual_version: event.stream_version(),
+                    }));
+                }
+                hash
+            },
+            None => {
+                let (expected_version, previous_hash) = match metadata.last_purged {
+                    Some(ref purged) => (purged.stream_version + 1, purged.event_hash.as_str()),
+                    None => (1, GENESIS_HASH),
+                };
+                if event.stream_version() != expected_version {
+                    return (verified, Some(BrokenLink::MissingVersion {
+                        stream_id: event.stream_id().to_string(),
+                        expected_version,
+                        actual_version: event.stream_version(),
+                    }));
+                }
+                previous_hash
+            },
+        };
+
+        let expected_hash = event_hash(
+            previous_hash, event.stream_id(), event.stream_version(), event.event_type(), event.event_body(),
+            event.event_metadata());
+        if expected_hash != event.event_hash() {
+            return (verified, Some(BrokenLink::HashMismatch {
+                stream_id: event.stream_id().to_string(),
+                stream_version: event.stream_version(),
+                expected_hash,
+                actual_hash: event.event_hash().to_string(),
+            }));
+        }
+        previous = Some((event.event_hash(), event.stream_version()));
+    }
+    (events.len() as u64, None)
+}
+
+pub fn verify_head(stream_id: &str, events: &[StoredEvent], metadata: &StreamMetadata, head: &ChainHead)
+    -> Result<(), BrokenLink> {
+    let purged_version = metadata.last_purged.as_ref().map_or(0, |purged| purged.stream_version);
+    let anchored = events.iter()
+        .find(|event| event.stream_version() == head.stream_version)
+        .map(|event| event.event_hash())
+        .or_else(|| metadata.last_purged.as_ref()
+                 .filter(|purged| purged.stream_version == head.stream_version)
+                 .map(|purged| purged.event_hash.as_str()));
+    match anchored {
+        Some(hash) if hash == head.event_hash => Ok(()),
+        Some(hash) => Err(BrokenLink::HashMismatch {
+            stream_id: stream_id.to_string(),
+            stream_version: head.stream_version,
+            expected_hash: head.event_hash.clone(),
+            actual_hash: hash.to_string(),
+        }),
+        None if head.stream_version < purged_version => Ok(()),
+        None => Err(BrokenLink::TruncatedTail {
+            stream_id: stream_id.to_string(),
+            head_version: head.stream_version,
+            last_version: events.last().map_or(purged_version, |event| event.stream_version()),
+        }),
+    }
+}
+
+pub fn verify_stream<S>(store: &S, stream_id: String) -> Result<ChainVerification, EventStoreError>
+    where S: HashChainedEventStore + ?Sized {
+    let metadata = match store.stream_metadata(stream_id.clone()) {
+        Ok(metadata) => metadata,
+        Err(ref err) if matches!(err.kind(), EventStoreErrorKind::UnsupportedOperationError(_)) =>
+            StreamMetadata::default(),
+        Err(err) => return Err(err),
+    };
+    let events = store.stored_events(stream_id.clone())?;
+    let (verified, broken_link) = walk_chain(&events, &metadata);
+    let broken_link = match (broken_link, store.chain_head(stream_id.clone())?) {
+        (None, Some(head)) => verify_head(&stream_id, &events, &metadata, &head).err(),
+        (broken_link, _) => broken_link,
+    };
+    Ok(ChainVerification {
+        streams: 1,
+        events: verified,
+        broken_link,
+    })
+}
+
+pub fn verify_store<S>(store: &S) -> Result<ChainVerification, EventStoreError>
+    where S: HashChainedEventStore + ?Sized {
+    let mut verification = ChainVerification::default();
+    for stream_id in store.stream_ids()? {
+        let stream = verify_stream(store, stream_id)?;
+        verification.streams += stream.streams;
+        verification.events += stream.events;
+        if stream.broken_link.is_some() {
+            verification.broken_link = stream.broken_link;
+            break;
+        }
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::eventsourcing::{EventStore, PurgedEvent, Snapshot, StreamMetadata};
+    use super::super::inmemory_eventstore::{InmemoryBankAccountEventStore, StoredEvent};
+    use super::{HashChainedEventStore, BrokenLink, event_hash, verify_events, verify_head, verify_store, walk_chain};
+
+    fn deposited(id: &BankAccountId, deposit: i32) -> BankAccountEvent {
+        BankAccountEvent::Deposited {
+            bank_account_id: id.clone(),
+            deposit,
+            occurred_at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_hash_chain_detects_tampering() {
+        let store = InmemoryBankAccountEventStore::new();
+        let ids = [
+            BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+            BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap(),
+        ];
+        for id in ids.iter() {
+            let stream_id = BankAccountAggregate::stream_id(id);
+            store.append_event_stream(stream_id.clone(), 1, vec![deposited(id, 100), deposited(id, 200)]).unwrap();
+            store.append_event_stream(stream_id.clone(), 3, vec![deposited(id, 300)]).unwrap();
+        }
+
+        let verification = verify_store(&store).unwrap();
+        assert_eq!(verification.streams, 2);
+        assert_eq!(verification.events, 6);
+        assert!(verification.broken_link.is_none());
+
+        let stream_id = BankAccountAggregate::stream_id(&ids[0]);
+        let mut events = store.stored_events(stream_id.clone()).unwrap();
+        let forged = serde_json::to_string(&deposited(&ids[0], 20000)).unwrap();
+        events[1] = StoredEvent::new(
+            events[1].event_type().to_string(),
+            forged,
+            *events[1].event_occurred_at(),
+            stream_id.clone(),
+            2,
+            events[1].event_hash().to_string(),
+            );
+        match walk_chain(&events, &StreamMetadata::default()) {
+            (1, Some(BrokenLink::HashMismatch { stream_version, .. })) => assert_eq!(stream_version, 2),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        events.remove(1);
+        match verify_events(&events, &StreamMetadata::default()) {
+            Err(BrokenLink::MissingVersion { expected_version, actual_version, .. }) => {
+                assert_eq!(expected_version, 2);
+                assert_eq!(actual_version, 3);
+            },
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_event_hash_covers_metadata() {
+        let hash = event_hash("", "bank_account-1", 1, "Deposited", "{}", None);
+        assert_eq!(hash, "5ef8c5cb496a8f5202faa7c413ec0a57382e34445e45eeec3054aaafb6a622f2");
+        assert_ne!(event_hash("", "bank_account-1", 1, "Deposited", "{}", Some("")), hash);
+        assert_ne!(event_hash("", "bank_account-1", 1, "Deposited", "{}", Some(r#"{"idempotency_key":"a"}"#)),
+                   event_hash("", "bank_account-1", 1, "Deposited", "{}", Some(r#"{"idempotency_key":"b"}"#)));
+
+        let store = InmemoryBankAccountEventStore::new();
+        let id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&id);
+        store.append_idempotent_event_stream(stream_id.clone(), 1, vec![deposited(&id, 100)], String::from("a")).unwrap();
+        let events = store.stored_events(stream_id.clone()).unwrap();
+        assert!(verify_events(&events, &StreamMetadata::default()).is_ok());
+
+        let forged = vec![events[0].clone().with_event_metadata(Some(String::from(r#"{"idempotency_key":"b"}"#)))];
+        match verify_events(&forged, &StreamMetadata::default()) {
+            Err(BrokenLink::HashMismatch { stream_version, .. }) => assert_eq!(stream_version, 1),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_hash_chain_detects_tail_truncation() {
+        let store = InmemoryBankAccountEventStore::new();
+        let id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&id);
+        store.append_event_stream(stream_id.clone(), 1, vec![deposited(&id, 100), deposited(&id, 200)]).unwrap();
+        store.append_event_stream(stream_id.clone(), 3, vec![deposited(&id, 300)]).unwrap();
+
+        let head = store.chain_head(stream_id.clone()).unwrap().unwrap();
+        assert_eq!(head.stream_version, 3);
+        let mut events = store.stored_events(stream_id.clone()).unwrap();
+        assert!(verify_head(&stream_id, &events, &StreamMetadata::default(), &head).is_ok());
+
+        events.pop();
+        assert!(verify_events(&events, &StreamMetadata::default()).is_ok());
+        match verify_head(&stream_id, &events, &StreamMetadata::default(), &head) {
+            Err(BrokenLink::TruncatedTail { head_version, last_version, .. }) => {
+                assert_eq!(head_version, 3);
+                assert_eq!(last_version, 2);
+            },
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match verify_head(&stream_id, &[], &StreamMetadata::default(), &head) {
+            Err(BrokenLink::TruncatedTail { last_version, .. }) => assert_eq!(last_version, 0),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_hash_chain_survives_truncation() {
+        let store = InmemoryBankAccountEventStore::new();
+        let id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&id);
+        let opened = BankAccountEvent::Opened {
+            bank_account_id: id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+            occurred_at: Local::now(),
+        };
+        store.append_event_stream(stream_id.clone(), 1,
+                                  vec![opened.clone(), deposited(&id, 200), deposited(&id, 300)]).unwrap();
+        let aggregate = BankAccountAggregate::load_from_history(
+            &BankAccountAggregate::new(), vec![opened, deposited(&id, 200)], 2).unwrap();
+        store.record_snapshot(Snapshot::new(stream_id.clone(), 2, aggregate.state().clone().unwrap(), Local::now())).unwrap();
+        store.set_stream_metadata(stream_id.clone(), StreamMetadata {
+            truncate_before: Some(3),
+            ..StreamMetadata::default()
+        }).unwrap();
+        assert_eq!(store.purge_stream(stream_id.clone()).unwrap(), 2);
+
+        let metadata = store.stream_metadata(stream_id.clone()).unwrap();
+        assert_eq!(metadata.last_purged.as_ref().unwrap().stream_version, 2);
+        let verification = verify_store(&store).unwrap();
+        assert_eq!(verification.events, 1);
+        assert!(verification.broken_link.is_none());
+
+        store.set_stream_metadata(stream_id.clone(), StreamMetadata::default()).unwrap();
+        assert_eq!(store.stream_metadata(stream_id.clone()).unwrap().last_purged, metadata.last_purged);
+
+        let events = store.stored_events(stream_id.clone()).unwrap();
+        match verify_events(&events, &StreamMetadata::default()) {
+            Err(BrokenLink::MissingVersion { expected_version, .. }) => assert_eq!(expected_version, 1),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let forged = StreamMetadata {
+            last_purged: Some(PurgedEvent {
+                stream_version: 2,
+                event_hash: events[0].event_hash().to_string(),
+            }),
+            ..StreamMetadata::default()
+        };
+        match verify_events(&events, &forged) {
+            Err(BrokenLink::HashMismatch { stream_version, .. }) => assert_eq!(stream_version, 3),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_hash_chain_continues_after_purging_every_event() {
+        let store = InmemoryBankAccountEventStore::new();
+        let id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&id);
+        let opened = BankAccountEvent::Opened {
+            bank_account_id: id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+            occurred_at: Local::now(),
+        };
+        store.append_event_stream(stream_id.clone(), 1, vec![opened.clone(), deposited(&id, 200)]).unwrap();
+        let aggregate = BankAccountAggregate::load_from_history(
+            &BankAccountAggregate::new(), vec![opened, deposited(&id, 200)], 2).unwrap();
+        store.record_snapshot(Snapshot::new(stream_id.clone(), 2, aggregate.state().clone().unwrap(), Local::now())).unwrap();
+        store.set_stream_metadata(stream_id.clone(), StreamMetadata {
+            truncate_before: Some(3),
+            ..StreamMetadata::default()
+        }).unwrap();
+        assert_eq!(store.purge_stream(stream_id.clone()).unwrap(), 2);
+
+        store.append_event_stream(stream_id.clone(), 3, vec![deposited(&id, 300)]).unwrap();
+        let verification = verify_store(&store).unwrap();
+        assert_eq!(verification.events, 1);
+        assert!(verification.broken_link.is_none());
+    }
+}
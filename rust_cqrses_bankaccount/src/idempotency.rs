@@ -0,0 +1,237 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use failure::{Fail, Context, Backtrace};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+use super::aggregate::{BankAccountCommand, ErrorKind as BankAccountErrorKind};
+
+#[derive(Debug)]
+pub struct IdempotencyError {
+    inner: Context<IdempotencyErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum IdempotencyErrorKind {
+    #[fail(display = "Load idempotency key error: {}", _0)]
+    LoadError(String),
+
+    #[fail(display = "Save idempotency key error: {}", _0)]
+    SaveError(String),
+}
+
+impl Fail for IdempotencyError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for IdempotencyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl IdempotencyError {
+    pub fn kind(&self) -> &IdempotencyErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<IdempotencyErrorKind> for IdempotencyError {
+    fn from(kind: IdempotencyErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<IdempotencyErrorKind>> for IdempotencyError {
+    fn from(inner: Context<IdempotencyErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CommandOutcome {
+    Appended(u64),
+    Rejected(BankAccountErrorKind),
+    NotFound,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reservation {
+    Reserved,
+    Completed(CommandOutcome),
+    InProgress,
+    Conflict,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyRecord {
+    pub fingerprint: String,
+    pub outcome: Option<CommandOutcome>,
+    pub created_at: DateTime<Local>,
+}
+
+impl IdempotencyRecord {
+    pub fn pending(fingerprint: &str, created_at: DateTime<Local>) -> Self {
+        Self {
+            fingerprint: fingerprint.to_string(),
+            outcome: None,
+            created_at,
+        }
+    }
+
+    pub fn reservation(&self, fingerprint: &str) -> Reservation {
+        if self.fingerprint != fingerprint {
+            return Reservation::Conflict;
+        }
+        match self.outcome {
+            Some(ref outcome) => Reservation::Completed(outcome.clone()),
+            None => Reservation::InProgress,
+        }
+    }
+}
+
+pub fn command_fingerprint(command: &BankAccountCommand) -> String {
+    let digest = Sha256::digest(format!("{:?}", command).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub trait IdempotencyStore: Send + Sync {
+    fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+               expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError>;
+
+    fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError>;
+
+    fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError>;
+
+    fn purge_expired(&self, expired_before: DateTime<Local>) -> Result<u64, IdempotencyError>;
+}
+
+impl<S: IdempotencyStore + ?Sized> IdempotencyStore for Arc<S> {
+    fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+               expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError> {
+        (**self).reserve(bank_account_id, idempotency_key, fingerprint, expired_before)
+    }
+
+    fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError> {
+        (**self).complete(bank_account_id, idempotency_key, outcome)
+    }
+
+    fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError> {
+        (**self).release(bank_account_id, idempotency_key)
+    }
+
+    fn purge_expired(&self, expired_before: DateTime<Local>) -> Result<u64, IdempotencyError> {
+        (**self).purge_expired(expired_before)
+    }
+}
+
+#[async_trait]
+pub trait AsyncIdempotencyStore: Send + Sync {
+    async fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+                     expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError>;
+
+    async fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError>;
+
+    async fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError>;
+}
+
+pub struct InmemoryIdempotencyStore {
+    records: Mutex<HashMap<(String, String), IdempotencyRecord>>,
+}
+
+impl InmemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InmemoryIdempotencyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdempotencyStore for InmemoryIdempotencyStore {
+    fn reserve(&self, bank_account_id: &str, idempotency_key: &str, fingerprint: &str,
+               expired_before: DateTime<Local>) -> Result<Reservation, IdempotencyError> {
+        let mut records = self.records.lock().unwrap();
+        let key = (bank_account_id.to_string(), idempotency_key.to_string());
+        if let Some(record) = records.get(&key) {
+            if record.created_at >= expired_before {
+                return Ok(record.reservation(fingerprint));
+            }
+        }
+        records.insert(key, IdempotencyRecord::pending(fingerprint, Local::now()));
+        Ok(Reservation::Reserved)
+    }
+
+    fn complete(&self, bank_account_id: &str, idempotency_key: &str, outcome: CommandOutcome)
+        -> Result<(), IdempotencyError> {
+        let mut records = self.records.lock().unwrap();
+        match records.get_mut(&(bank_account_id.to_string(), idempotency_key.to_string())) {
+            Some(record) => {
+                record.outcome = Some(outcome);
+                Ok(())
+            },
+            None => Err(IdempotencyErrorKind::SaveError(
+                    format!("{}:{} is not reserved", bank_account_id, idempotency_key)))?,
+        }
+    }
+
+    fn release(&self, bank_account_id: &str, idempotency_key: &str) -> Result<(), IdempotencyError> {
+        self.records.lock().unwrap().remove(&(bank_account_id.to_string(), idempotency_key.to_string()));
+        Ok(())
+    }
+
+    fn purge_expired(&self, expired_before: DateTime<Local>) -> Result<u64, IdempotencyError> {
+        let mut records = self.records.lock().unwrap();
+        let count = records.len();
+        records.retain(|_, record| record.created_at >= expired_before);
+        Ok((count - records.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+
+    use super::{CommandOutcome, IdempotencyStore, InmemoryIdempotencyStore, Reservation};
+
+    #[test]
+    fn test_inmemory_idempotency_store() {
+        let store = InmemoryIdempotencyStore::new();
+        let expired_before = Local::now() - Duration::hours(1);
+
+        assert_eq!(store.reserve("account-1", "key-1", "deposit-100", expired_before).unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("account-1", "key-1", "deposit-100", expired_before).unwrap(), Reservation::InProgress);
+        store.complete("account-1", "key-1", CommandOutcome::Appended(3)).unwrap();
+        assert_eq!(store.reserve("account-1", "key-1", "deposit-100", expired_before).unwrap(),
+                   Reservation::Completed(CommandOutcome::Appended(3)));
+        assert_eq!(store.reserve("account-1", "key-1", "deposit-200", expired_before).unwrap(), Reservation::Conflict);
+        assert_eq!(store.reserve("account-2", "key-1", "deposit-200", expired_before).unwrap(), Reservation::Reserved);
+
+        store.release("account-2", "key-1").unwrap();
+        assert_eq!(store.reserve("account-2", "key-1", "deposit-300", expired_before).unwrap(), Reservation::Reserved);
+        assert!(store.complete("account-3", "key-1", CommandOutcome::Appended(1)).is_err());
+
+        let later = Local::now() + Duration::seconds(1);
+        assert_eq!(store.reserve("account-1", "key-1", "deposit-200", later).unwrap(), Reservation::Reserved);
+        assert_eq!(store.purge_expired(later).unwrap(), 2);
+        assert_eq!(store.purge_expired(later).unwrap(), 0);
+    }
+}
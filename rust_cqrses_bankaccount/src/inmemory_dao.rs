@@ -0,0 +1,109 @@
+use std::sync::RwLock;
+use std::collections::BTreeMap;
+
+use super::dao::{BankAccountRM, BankAccountRMDao, DaoError};
+
+pub struct InmemoryBankAccountRMDao {
+    records: RwLock<BTreeMap<String, BankAccountRM>>,
+}
+
+impl InmemoryBankAccountRMDao {
+    pub fn new() -> Self {
+        Self {
+            records: RwLock::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Default for InmemoryBankAccountRMDao {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankAccountRMDao for InmemoryBankAccountRMDao {
+    fn find(&self, bank_account_id: String) -> Result<Option<BankAccountRM>, DaoError> {
+        Ok(self.records.read().unwrap().get(&bank_account_id).cloned())
+    }
+
+    fn insert(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        self.records.write().unwrap().insert(model.bank_account_id.clone(), model);
+        Ok(())
+    }
+
+    fn update(&self, model: BankAccountRM) -> Result<(), DaoError> {
+        let mut records = self.records.write().unwrap();
+        if let Some(record) = records.get_mut(&model.bank_account_id) {
+            *record = model;
+        }
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<BankAccountRM>, DaoError> {
+        Ok(self.records.read().unwrap().values().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::Local;
+
+    use super::super::aggregate::{BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::dao::BankAccountRMDao;
+    use super::super::projector::BankAccountProjector;
+    use super::InmemoryBankAccountRMDao;
+
+    #[test]
+    fn test_inmemory_dao_with_projector() {
+        let dao = Arc::new(InmemoryBankAccountRMDao::new());
+        let projector = BankAccountProjector::new(Box::new(dao.clone()));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let other_id = BankAccountId::new(String::from("0c5f9e44-3ab1-4d2e-9d0d-8b2e1c7a3f10")).unwrap();
+
+        projector.project(1, BankAccountEvent::Opened {
+            bank_account_id: bank_account_id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+            occurred_at: Local::now(),
+        }).unwrap();
+        projector.project(2, BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 1000,
+            occurred_at: Local::now(),
+        }).unwrap();
+        projector.project(3, BankAccountEvent::Withdrawn {
+            bank_account_id: bank_account_id.clone(),
+            withdraw: 300,
+            occurred_at: Local::now(),
+        }).unwrap();
+        projector.project(1, BankAccountEvent::Opened {
+            bank_account_id: other_id.clone(),
+            name: BankAccountName::new(String::from("bar")).unwrap(),
+            occurred_at: Local::now(),
+        }).unwrap();
+        projector.project(2, BankAccountEvent::Closed {
+            bank_account_id: other_id.clone(),
+            occurred_at: Local::now(),
+        }).unwrap();
+
+        assert_eq!(projector.project(2, BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 1000,
+            occurred_at: Local::now(),
+        }).unwrap(), 3);
+
+        let record = dao.find(bank_account_id.to_string()).unwrap().unwrap();
+        assert_eq!(record.name, "foo");
+        assert_eq!(record.balance, 700);
+        assert_eq!(record.version, 3);
+        assert!(!record.is_closed);
+
+        let record = dao.find(other_id.to_string()).unwrap().unwrap();
+        assert!(record.is_closed);
+        assert_eq!(record.version, 2);
+
+        assert_eq!(dao.list().unwrap().len(), 2);
+        assert!(dao.find(String::from("unknown")).unwrap().is_none());
+    }
+}
@@ -1,17 +1,34 @@
 use std::sync::Mutex;
 use std::collections::HashMap;
 use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+use async_trait::async_trait;
 
-use super::eventsourcing::{EventStream, Snapshot, EventStoreError, EventStoreErrorKind, EventStore};
+use super::eventsourcing::{
+    EventStream,
+    Snapshot,
+    StreamMetadata,
+    EventMetadata,
+    PurgedEvent,
+    EventStoreError,
+    EventStoreErrorKind,
+    EventStore,
+    AsyncEventStore,
+};
 use super::aggregate::{BankAccountEvent, BankAccount};
+use super::hashchain::{GENESIS_HASH, ChainHead, HashChainedEventStore, event_hash};
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StoredEvent {
     event_type: String,
     event_body: String,
     event_occurred_at: DateTime<Local>,
     stream_id: String,
     stream_version: u64,
+    #[serde(default)]
+    event_hash: String,
+    #[serde(default)]
+    event_metadata: Option<String>,
 }
 
 impl StoredEvent {
@@ -21,6 +38,7 @@ impl StoredEvent {
         event_occurred_at: DateTime<Local>,
         stream_id: String,
         stream_version: u64,
+        event_hash: String,
         ) -> Self {
         Self {
             event_type,
@@ -28,9 +46,16 @@ impl StoredEvent {
             event_occurred_at,
             stream_id,
             stream_version,
+            event_hash,
+            event_metadata: None,
         }
     }
 
+    pub fn with_event_metadata(mut self, event_metadata: Option<String>) -> Self {
+        self.event_metadata = event_metadata;
+        self
+    }
+
     pub fn event_type(&self) -> &str {
         &self.event_type
     }
@@ -50,11 +75,21 @@ impl StoredEvent {
     pub fn stream_version(&self) -> u64 {
         self.stream_version
     }
+
+    pub fn event_hash(&self) -> &str {
+        &self.event_hash
+    }
+
+    pub fn event_metadata(&self) -> Option<&str> {
+        self.event_metadata.as_deref()
+    }
 }
 
 pub struct InmemoryBankAccountEventStore {
     events: Mutex<Vec<StoredEvent>>,
     snapshots: Mutex<HashMap<String, Snapshot<BankAccount>>>,
+    metadata: Mutex<HashMap<String, StreamMetadata>>,
+    heads: Mutex<HashMap<String, ChainHead>>,
 }
 
 impl InmemoryBankAccountEventStore {
@@ -62,8 +97,57 @@ impl InmemoryBankAccountEventStore {
         Self {
             events: Mutex::new(vec![]),
             snapshots: Mutex::new(HashMap::new()),
+            metadata: Mutex::new(HashMap::new()),
+            heads: Mutex::new(HashMap::new()),
         }
     }
+
+    fn snapshot_version(&self, stream_id: &str) -> u64 {
+        self.snapshots.lock().unwrap().get(stream_id).map_or(0, |snapshot| snapshot.stream_version())
+    }
+
+    fn append(&self, stream_id: String, stream_version: u64, events: Vec<BankAccountEvent>,
+              event_metadata: Option<String>) -> Result<(), EventStoreError> {
+        let metadata = EventStore::stream_metadata(self, stream_id.clone())?;
+        if metadata.deleted {
+            return Err(EventStoreErrorKind::StreamDeletedError(stream_id))?;
+        }
+        let snapshot_version = self.snapshot_version(&stream_id);
+        let mut guard = self.events.lock().unwrap();
+        if stream_version <= snapshot_version
+            || guard.iter().any(|event| event.stream_id() == stream_id && event.stream_version() >= stream_version) {
+            return Err(EventStoreErrorKind::DuplicateEntryError(format!("{}:{}", stream_id, stream_version)))?;
+        }
+        let mut previous_hash = guard.iter()
+            .rev()
+            .find(|event| event.stream_id() == stream_id)
+            .map(|event| event.event_hash().to_string())
+            .or_else(|| metadata.last_purged.map(|purged| purged.event_hash))
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+        for (i, event) in events.into_iter().enumerate() {
+            let event_type = event.event_type().to_string();
+            let event_body = serde_json::to_string(&event).unwrap();
+            let version = stream_version + i as u64;
+            let hash = event_hash(&previous_hash, &stream_id, version, &event_type, &event_body,
+                                  event_metadata.as_deref());
+            guard.push(StoredEvent::new(
+                    event_type,
+                    event_body,
+                    event.occurred_at(),
+                    stream_id.clone(),
+                    version,
+                    hash.clone(),
+                    ).with_event_metadata(event_metadata.clone()));
+            previous_hash = hash;
+        }
+        if let Some(event) = guard.last().filter(|event| event.stream_id() == stream_id) {
+            self.heads.lock().unwrap().insert(stream_id, ChainHead {
+                stream_version: event.stream_version(),
+                event_hash: event.event_hash().to_string(),
+            });
+        }
+        Ok(())
+    }
 }
 
 impl EventStore for InmemoryBankAccountEventStore {
@@ -73,33 +157,25 @@ impl EventStore for InmemoryBankAccountEventStore {
 
     fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
         -> Result<(), EventStoreError> {
-        let mut guard = self.events.lock().unwrap();
-        let mut i: u64 = 0;
-        let mut stream_version = stream_version;
-        for event in events {
-            stream_version = stream_version + i;
-            guard.push(StoredEvent::new(
-                    event.event_type().to_string(),
-                    serde_json::to_string(&event).unwrap(),
-                    event.occurred_at(),
-                    stream_id.clone(),
-                    stream_version,
-                    ));
-            i = i + 1;
-        }
-        Ok(())
+        self.append(stream_id, stream_version, events, None)
     }
 
     fn event_stream_since(&self, stream_id: String, stream_version: u64)
         -> Result<Self::EventStream, EventStoreError> {
+        let metadata = EventStore::stream_metadata(self, stream_id.clone())?;
+        if metadata.deleted {
+            return Err(EventStoreErrorKind::StreamDeletedError(stream_id))?;
+        }
+        let snapshot_version = self.snapshot_version(&stream_id);
+        let now = Local::now();
         let stored_events: Vec<StoredEvent> = self.events.lock().unwrap()
             .iter()
             .filter(|event| event.stream_id() == &stream_id && event.stream_version() >= stream_version)
+            .filter(|event| metadata.is_readable(event.stream_version(), event.event_occurred_at(), snapshot_version, now))
             .cloned()
             .collect();
         if stored_events.is_empty() {
-            Err(EventStoreErrorKind::QueryError(
-                    format!("There is no such event stream: {}:{}", &stream_id, stream_version)))?
+            Err(EventStoreErrorKind::NoEventStreamError(stream_id, stream_version))?
         } else {
             let events: Vec<BankAccountEvent> = stored_events.iter()
                 .map(|event| serde_json::from_str(event.event_body()).unwrap())
@@ -122,6 +198,148 @@ impl EventStore for InmemoryBankAccountEventStore {
             None => Ok(None),
         }
     }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        let metadata = EventMetadata {
+            idempotency_key: Some(idempotency_key),
+        };
+        self.append(stream_id, stream_version, events, Some(serde_json::to_string(&metadata).unwrap()))
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        Ok(self.events.lock().unwrap()
+           .iter()
+           .filter(|event| event.stream_id() == stream_id)
+           .filter(|event| {
+               event.event_metadata()
+                   .and_then(|metadata| serde_json::from_str::<EventMetadata>(metadata).ok())
+                   .and_then(|metadata| metadata.idempotency_key)
+                   .is_some_and(|key| key == idempotency_key)
+           })
+           .map(|event| event.stream_version())
+           .max())
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        Ok(self.metadata.lock().unwrap().get(&stream_id).cloned().unwrap_or_default())
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        metadata.check_truncation(self.snapshot_version(&stream_id))?;
+        let mut guard = self.metadata.lock().unwrap();
+        let last_purged = guard.get(&stream_id).and_then(|metadata| metadata.last_purged.clone());
+        guard.insert(stream_id, StreamMetadata {
+            last_purged,
+            ..metadata
+        });
+        Ok(())
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        let metadata = EventStore::stream_metadata(self, stream_id.clone())?;
+        if metadata.deleted {
+            self.snapshots.lock().unwrap().remove(&stream_id);
+            self.heads.lock().unwrap().remove(&stream_id);
+        }
+        let snapshot_version = self.snapshot_version(&stream_id);
+        let now = Local::now();
+        let mut guard = self.events.lock().unwrap();
+        let (purged, retained): (Vec<StoredEvent>, Vec<StoredEvent>) = guard.drain(..)
+            .partition(|event| {
+                event.stream_id() == stream_id
+                    && !metadata.is_readable(event.stream_version(), event.event_occurred_at(), snapshot_version, now)
+            });
+        *guard = retained;
+
+        if !metadata.deleted {
+            if let Some(event) = purged.iter().max_by_key(|event| event.stream_version()) {
+                self.metadata.lock().unwrap().insert(stream_id, StreamMetadata {
+                    last_purged: Some(PurgedEvent {
+                        stream_version: event.stream_version(),
+                        event_hash: event.event_hash().to_string(),
+                    }),
+                    ..metadata
+                });
+            }
+        }
+        Ok(purged.len() as u64)
+    }
+}
+
+impl HashChainedEventStore for InmemoryBankAccountEventStore {
+    fn stream_ids(&self) -> Result<Vec<String>, EventStoreError> {
+        let mut stream_ids: Vec<String> = self.events.lock().unwrap()
+            .iter()
+            .map(|event| event.stream_id().to_string())
+            .collect();
+        stream_ids.extend(self.heads.lock().unwrap().keys().cloned());
+        stream_ids.sort();
+        stream_ids.dedup();
+        Ok(stream_ids)
+    }
+
+    fn stored_events(&self, stream_id: String) -> Result<Vec<StoredEvent>, EventStoreError> {
+        Ok(self.events.lock().unwrap()
+           .iter()
+           .filter(|event| event.stream_id() == stream_id)
+           .cloned()
+           .collect())
+    }
+
+    fn chain_head(&self, stream_id: String) -> Result<Option<ChainHead>, EventStoreError> {
+        Ok(self.heads.lock().unwrap().get(&stream_id).cloned())
+    }
+}
+
+#[async_trait]
+impl AsyncEventStore for InmemoryBankAccountEventStore {
+    type Event = BankAccountEvent;
+    type EventStream = EventStream<Self::Event>;
+    type SnapshotData = BankAccount;
+
+    async fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        EventStore::append_event_stream(self, stream_id, stream_version, events)
+    }
+
+    async fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        EventStore::event_stream_since(self, stream_id, stream_version)
+    }
+
+    async fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>) -> Result<(), EventStoreError> {
+        EventStore::record_snapshot(self, snapshot)
+    }
+
+    async fn read_snapshot(&self, stream_id: String) -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        EventStore::read_snapshot(self, stream_id)
+    }
+
+    async fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                            idempotency_key: String) -> Result<(), EventStoreError> {
+        EventStore::append_idempotent_event_stream(self, stream_id, stream_version, events, idempotency_key)
+    }
+
+    async fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        EventStore::idempotency_key_version(self, stream_id, idempotency_key)
+    }
+
+    async fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        EventStore::stream_metadata(self, stream_id)
+    }
+
+    async fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        EventStore::set_stream_metadata(self, stream_id, metadata)
+    }
+
+    async fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        EventStore::purge_stream(self, stream_id)
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +349,7 @@ mod tests {
 
     use super::InmemoryBankAccountEventStore;
     use super::super::eventsourcing::EventStore;
+    use super::super::testing::{verify_event_store, verify_stream_metadata};
     use super::super::aggregate::{BankAccountEvent, BankAccountId, BankAccountName};
 
     #[test]
@@ -158,4 +377,14 @@ mod tests {
         let stream = result.unwrap();
         assert_eq!(stream.events().len(), 1);
     }
+
+    #[test]
+    fn test_inmemory_store_behaviour() {
+        verify_event_store(&InmemoryBankAccountEventStore::new());
+    }
+
+    #[test]
+    fn test_inmemory_store_stream_metadata() {
+        verify_stream_metadata(&InmemoryBankAccountEventStore::new());
+    }
 }
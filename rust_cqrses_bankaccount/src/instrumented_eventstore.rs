@@ -0,0 +1,179 @@
+use std::time::Instant;
+use metrics::{counter, histogram};
+use tracing::info_span;
+
+use super::eventsourcing::{
+    Snapshot,
+    StreamMetadata,
+    EventStore,
+    EventStoreError,
+};
+
+fn outcome<T>(result: &Result<T, EventStoreError>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(_) => "error",
+    }
+}
+
+pub struct InstrumentedEventStore<S> {
+    eventstore: S,
+    name: &'static str,
+}
+
+impl<S: EventStore> InstrumentedEventStore<S> {
+    pub fn new(eventstore: S, name: &'static str) -> Self {
+        Self {
+            eventstore,
+            name,
+        }
+    }
+
+    fn record_append<F>(&self, count: usize, append: F) -> Result<(), EventStoreError>
+        where F: FnOnce() -> Result<(), EventStoreError> {
+        let started_at = Instant::now();
+        let result = append();
+        histogram!("bankaccount_eventstore_append_duration_seconds", "store" => self.name, "outcome" => outcome(&result))
+            .record(started_at.elapsed().as_secs_f64());
+        if result.is_ok() {
+            histogram!("bankaccount_eventstore_events_per_append", "store" => self.name).record(count as f64);
+        }
+        result
+    }
+}
+
+impl<S: EventStore> EventStore for InstrumentedEventStore<S> {
+    type Event = S::Event;
+    type EventStream = S::EventStream;
+    type SnapshotData = S::SnapshotData;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        let count = events.len();
+        let _span = info_span!("append_event_stream", store = self.name, stream_id = %stream_id, stream_version, count)
+            .entered();
+        self.record_append(count, || self.eventstore.append_event_stream(stream_id, stream_version, events))
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        let _span = info_span!("event_stream_since", store = self.name, stream_id = %stream_id, stream_version)
+            .entered();
+        let started_at = Instant::now();
+        let result = self.eventstore.event_stream_since(stream_id, stream_version);
+        histogram!("bankaccount_eventstore_read_duration_seconds", "store" => self.name, "outcome" => outcome(&result))
+            .record(started_at.elapsed().as_secs_f64());
+        if stream_version <= 1 {
+            counter!("bankaccount_eventstore_full_replays_total", "store" => self.name).increment(1);
+        }
+        result
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        self.eventstore.record_snapshot(snapshot)
+    }
+
+    fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        let _span = info_span!("read_snapshot", store = self.name, stream_id = %stream_id).entered();
+        let result = self.eventstore.read_snapshot(stream_id);
+        if let Ok(snapshot) = &result {
+            let hit = if snapshot.is_some() { "hit" } else { "miss" };
+            counter!("bankaccount_eventstore_snapshot_reads_total", "store" => self.name, "result" => hit).increment(1);
+        }
+        result
+    }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        let count = events.len();
+        let _span = info_span!("append_event_stream", store = self.name, stream_id = %stream_id, stream_version, count)
+            .entered();
+        self.record_append(count, || {
+            self.eventstore.append_idempotent_event_stream(stream_id, stream_version, events, idempotency_key)
+        })
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        self.eventstore.idempotency_key_version(stream_id, idempotency_key)
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        self.eventstore.stream_metadata(stream_id)
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        self.eventstore.set_stream_metadata(stream_id, metadata)
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        self.eventstore.purge_stream(stream_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::super::aggregate::{BankAccountId, BankAccountName};
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::snapshotter::BankAccountAggregateSnapshotter;
+    use super::super::usecase::command::BankAccountAggregateUseCase;
+    use super::super::testing::verify_event_store;
+    use super::InstrumentedEventStore;
+
+    type Metric = (String, Vec<(String, String)>, DebugValue);
+
+    #[test]
+    fn test_instrumented_store_behaviour() {
+        verify_event_store(&InstrumentedEventStore::new(InmemoryBankAccountEventStore::new(), "inmemory"));
+    }
+
+    #[test]
+    fn test_instrumented_store_records_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let store = std::sync::Arc::new(InstrumentedEventStore::new(InmemoryBankAccountEventStore::new(), "inmemory"));
+            let usecase = BankAccountAggregateUseCase::new(Box::new(store.clone()));
+            let id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+            usecase.open(id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+            BankAccountAggregateSnapshotter::new(Box::new(store.clone())).take_snapshot(id.clone()).unwrap();
+            usecase.deposit(id.clone(), 100).unwrap();
+            assert!(usecase.withdraw(id.clone(), 1000).is_err());
+        });
+
+        let metrics: Vec<Metric> = snapshotter.snapshot().into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let labels = key.key().labels()
+                    .map(|label| (label.key().to_string(), label.value().to_string()))
+                    .collect();
+                (key.key().name().to_string(), labels, value)
+            })
+            .collect();
+        let counter = |name: &str, labels: &[(&str, &str)]| -> u64 {
+            metrics.iter()
+                .filter(|(key, key_labels, _)| key == name && labels.iter().all(|(k, v)| {
+                    key_labels.iter().any(|(key_label, value)| key_label == k && value == v)
+                }))
+                .map(|(_, _, value)| match value {
+                    DebugValue::Counter(count) => *count,
+                    value => panic!("unexpected value: {:?}", value),
+                })
+                .sum()
+        };
+
+        assert_eq!(counter("bankaccount_commands_total", &[("command", "OpenBankAccount"), ("outcome", "ok")]), 1);
+        assert_eq!(counter("bankaccount_commands_total", &[("command", "WithdrawBankAccount"), ("outcome", "rejected")]), 1);
+        assert_eq!(counter("bankaccount_eventstore_snapshot_reads_total", &[("result", "miss")]), 1);
+        assert_eq!(counter("bankaccount_eventstore_snapshot_reads_total", &[("result", "hit")]), 2);
+        assert!(metrics.iter().any(|(name, _, value)| name == "bankaccount_eventstore_events_per_append"
+                                   && *value == DebugValue::Histogram(vec![1.0.into(), 1.0.into()])));
+    }
+}
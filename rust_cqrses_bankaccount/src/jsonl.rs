@@ -0,0 +1,345 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::collections::HashMap;
+use chrono::{DateTime, Local};
+use failure::{Fail, Context, Backtrace};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::eventsourcing::EventStoreErrorKind;
+use super::aggregate::{BankAccountAggregate, BankAccountEvent};
+use super::BankAccountEventStore;
+
+#[derive(Debug)]
+pub struct JsonlError {
+    inner: Context<JsonlErrorKind>,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum JsonlErrorKind {
+    #[fail(display = "IO error: {}", _0)]
+    IoError(String),
+
+    #[fail(display = "Parse error at line {}: {}", _0, _1)]
+    ParseError(usize, String),
+
+    #[fail(display = "Event store error: {}", _0)]
+    EventStoreError(String),
+}
+
+impl Fail for JsonlError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for JsonlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl JsonlError {
+    pub fn kind(&self) -> &JsonlErrorKind {
+        self.inner.get_context()
+    }
+}
+
+impl From<JsonlErrorKind> for JsonlError {
+    fn from(kind: JsonlErrorKind) -> Self {
+        Self{ inner: Context::new(kind) }
+    }
+}
+
+impl From<Context<JsonlErrorKind>> for JsonlError {
+    fn from(inner: Context<JsonlErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+fn io_error(err: std::io::Error) -> JsonlError {
+    JsonlError::from(JsonlErrorKind::IoError(err.to_string()))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedEvent {
+    pub stream_id: String,
+    pub stream_version: u64,
+    pub event_type: String,
+    pub occurred_at: DateTime<Local>,
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportSummary {
+    pub streams: u64,
+    pub events: u64,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub streams: u64,
+    pub imported: u64,
+    pub skipped: u64,
+    pub upcasted: u64,
+    pub failed: Vec<(String, String)>,
+}
+
+pub fn export_streams<W: Write>(store: &BankAccountEventStore, stream_ids: &[String], writer: &mut W)
+    -> Result<ExportSummary, JsonlError> {
+    let mut summary = ExportSummary::default();
+    for stream_id in stream_ids {
+        let stream = match store.event_stream_since(stream_id.clone(), 1) {
+            Ok(stream) => stream,
+            Err(err) => match err.kind() {
+                EventStoreErrorKind::NoEventStreamError(_, _) | EventStoreErrorKind::StreamDeletedError(_) => {
+                    summary.missing.push(stream_id.clone());
+                    continue;
+                },
+                _ => return Err(JsonlErrorKind::EventStoreError(err.to_string()))?,
+            },
+        };
+
+        let first_version = stream.version() + 1 - stream.events().len() as u64;
+        for (i, event) in stream.events().iter().enumerate() {
+            let exported = ExportedEvent {
+                stream_id: stream_id.clone(),
+                stream_version: first_version + i as u64,
+                event_type: event.event_type().to_string(),
+                occurred_at: event.occurred_at(),
+                body: serde_json::to_value(event).unwrap(),
+            };
+            serde_json::to_writer(&mut *writer, &exported).map_err(|err| io_error(err.into()))?;
+            writer.write_all(b"\n").map_err(io_error)?;
+            summary.events += 1;
+        }
+        summary.streams += 1;
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(summary)
+}
+
+fn variant_of(event_type: &str) -> Option<&'static str> {
+    match event_type {
+        "BankAccountOpened" | "Opened" => Some("Opened"),
+        "BankAccountUpdated" | "Updated" => Some("Updated"),
+        "BankAccountDeposited" | "Deposited" => Some("Deposited"),
+        "BankAccountWithdrawn" | "Withdrawn" => Some("Withdrawn"),
+        "BankAccountClosed" | "Closed" => Some("Closed"),
+        _ => None,
+    }
+}
+
+pub fn upcast(exported: &ExportedEvent) -> Result<(BankAccountEvent, bool), String> {
+    let variant = variant_of(&exported.event_type)
+        .ok_or_else(|| format!("unknown event type {}", exported.event_type))?;
+
+    let mut upcasted = false;
+    let mut fields = match &exported.body {
+        Value::Object(map) if map.len() == 1 && map.contains_key(variant) => map[variant].clone(),
+        Value::Object(map) => {
+            upcasted = true;
+            Value::Object(map.clone())
+        },
+        body => return Err(format!("unexpected body {}", body)),
+    };
+    if let Value::Object(map) = &mut fields {
+        if !map.contains_key("occurred_at") {
+            upcasted = true;
+            map.insert(String::from("occurred_at"), serde_json::to_value(exported.occurred_at).unwrap());
+        }
+    }
+
+    let mut body = serde_json::Map::new();
+    body.insert(variant.to_string(), fields);
+    serde_json::from_value(Value::Object(body))
+        .map(|event| (event, upcasted))
+        .map_err(|err| err.to_string())
+}
+
+fn current_version(store: &BankAccountEventStore, stream_id: &str) -> Result<u64, String> {
+    match store.event_stream_since(stream_id.to_string(), 1) {
+        Ok(stream) => Ok(stream.version()),
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::NoEventStreamError(_, _) => Ok(0),
+            _ => Err(err.to_string()),
+        },
+    }
+}
+
+fn import_stream(store: &BankAccountEventStore, stream_id: &str, lines: Vec<ExportedEvent>, dry_run: bool,
+                 summary: &mut ImportSummary) -> Result<(), String> {
+    let current = current_version(store, stream_id)?;
+
+    let mut events = vec![];
+    let mut upcasted = 0;
+    let mut skipped = 0;
+    let mut expected_version = None;
+    for line in lines {
+        if let Some(expected) = expected_version {
+            if line.stream_version != expected {
+                return Err(format!("expected version {} but found {}", expected, line.stream_version));
+            }
+        }
+        expected_version = Some(line.stream_version + 1);
+
+        if line.stream_version <= current {
+            skipped += 1;
+            continue;
+        }
+        if events.is_empty() && line.stream_version != current + 1 {
+            return Err(format!("stream is at version {} but the import continues at {}", current, line.stream_version));
+        }
+
+        let (event, is_upcasted) = upcast(&line)
+            .map_err(|err| format!("version {}: {}", line.stream_version, err))?;
+        if BankAccountAggregate::stream_id(event.bank_account_id()) != stream_id {
+            return Err(format!("version {}: event belongs to {}", line.stream_version, event.bank_account_id()));
+        }
+        if is_upcasted {
+            upcasted += 1;
+        }
+        events.push(event);
+    }
+
+    let imported = events.len() as u64;
+    if !dry_run && !events.is_empty() {
+        store.append_event_stream(stream_id.to_string(), current + 1, events)
+            .map_err(|err| err.to_string())?;
+    }
+    summary.imported += imported;
+    summary.skipped += skipped;
+    summary.upcasted += upcasted;
+    Ok(())
+}
+
+pub fn import_streams<R: BufRead>(store: &BankAccountEventStore, reader: R, dry_run: bool)
+    -> Result<ImportSummary, JsonlError> {
+    let mut stream_ids: Vec<String> = vec![];
+    let mut streams: HashMap<String, Vec<ExportedEvent>> = HashMap::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let exported: ExportedEvent = serde_json::from_str(&line)
+            .map_err(|err| JsonlError::from(JsonlErrorKind::ParseError(i + 1, err.to_string())))?;
+        if !streams.contains_key(&exported.stream_id) {
+            stream_ids.push(exported.stream_id.clone());
+        }
+        streams.entry(exported.stream_id.clone()).or_default().push(exported);
+    }
+
+    let mut summary = ImportSummary::default();
+    for stream_id in stream_ids {
+        let lines = streams.remove(&stream_id).unwrap();
+        summary.streams += 1;
+        if let Err(reason) = import_stream(store, &stream_id, lines, dry_run, &mut summary) {
+            summary.failed.push((stream_id, reason));
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use chrono::{DateTime, Local};
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::eventsourcing::EventStore;
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::{export_streams, import_streams};
+
+    fn bank_account_id(value: &str) -> BankAccountId {
+        BankAccountId::new(String::from(value)).unwrap()
+    }
+
+    fn history(id: &BankAccountId) -> Vec<BankAccountEvent> {
+        vec![
+            BankAccountEvent::Opened {
+                bank_account_id: id.clone(),
+                name: BankAccountName::new(String::from("foo")).unwrap(),
+                occurred_at: Local::now(),
+            },
+            BankAccountEvent::Deposited {
+                bank_account_id: id.clone(),
+                deposit: 100,
+                occurred_at: Local::now(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let source = InmemoryBankAccountEventStore::new();
+        let ids = [
+            bank_account_id("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            bank_account_id("a3bb189e-8bf9-3888-9912-ace4e6543002"),
+        ];
+        let stream_ids: Vec<String> = ids.iter().map(BankAccountAggregate::stream_id).collect();
+        for (id, stream_id) in ids.iter().zip(stream_ids.iter()) {
+            source.append_event_stream(stream_id.clone(), 1, history(id)).unwrap();
+        }
+
+        let mut exported = vec![];
+        let mut requested = stream_ids.clone();
+        requested.push(String::from("bank_account:missing"));
+        let summary = export_streams(&source, &requested, &mut exported).unwrap();
+        assert_eq!(summary.streams, 2);
+        assert_eq!(summary.events, 4);
+        assert_eq!(summary.missing, vec![String::from("bank_account:missing")]);
+
+        let target = InmemoryBankAccountEventStore::new();
+        let summary = import_streams(&target, Cursor::new(&exported), true).unwrap();
+        assert_eq!(summary.imported, 4);
+        assert!(target.event_stream_since(stream_ids[0].clone(), 1).is_err());
+
+        let summary = import_streams(&target, Cursor::new(&exported), false).unwrap();
+        assert_eq!(summary.streams, 2);
+        assert_eq!(summary.imported, 4);
+        assert!(summary.failed.is_empty());
+        for stream_id in stream_ids.iter() {
+            assert_eq!(target.event_stream_since(stream_id.clone(), 1).unwrap().events(),
+                       source.event_stream_since(stream_id.clone(), 1).unwrap().events());
+        }
+
+        let summary = import_streams(&target, Cursor::new(&exported), false).unwrap();
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.skipped, 4);
+    }
+
+    #[test]
+    fn test_import_upcasts_and_validates_versions() {
+        let id = bank_account_id("67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let other = bank_account_id("a3bb189e-8bf9-3888-9912-ace4e6543002");
+        let lines = [
+            format!(r#"{{"stream_id":"bank_account:{0}","stream_version":1,"event_type":"BankAccountOpened","occurred_at":"2019-12-01T10:00:00+09:00","body":{{"bank_account_id":{{"value":"{0}"}},"name":{{"value":"foo"}}}}}}"#, id),
+            format!(r#"{{"stream_id":"bank_account:{0}","stream_version":2,"event_type":"BankAccountDeposited","occurred_at":"2019-12-01T10:00:00+09:00","body":{{"bank_account_id":{{"value":"{0}"}},"deposit":100}}}}"#, id),
+            format!(r#"{{"stream_id":"bank_account:{0}","stream_version":2,"event_type":"BankAccountDeposited","occurred_at":"2019-12-01T10:00:00+09:00","body":{{"bank_account_id":{{"value":"{0}"}},"deposit":100}}}}"#, other),
+        ];
+
+        let store = InmemoryBankAccountEventStore::new();
+        let summary = import_streams(&store, Cursor::new(lines.join("\n")), false).unwrap();
+        assert_eq!(summary.streams, 2);
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.upcasted, 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, BankAccountAggregate::stream_id(&other));
+
+        let stream = store.event_stream_since(BankAccountAggregate::stream_id(&id), 1).unwrap();
+        assert_eq!(stream.version(), 2);
+        match &stream.events()[1] {
+            BankAccountEvent::Deposited { deposit, occurred_at, .. } => {
+                assert_eq!(*deposit, 100);
+                assert_eq!(*occurred_at, DateTime::parse_from_rfc3339("2019-12-01T10:00:00+09:00").unwrap());
+            },
+            event => panic!("unexpected event: {:?}", event),
+        }
+    }
+}
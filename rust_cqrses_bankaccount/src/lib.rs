@@ -2,15 +2,39 @@ pub mod eventsourcing;
 pub mod aggregate;
 pub mod usecase;
 pub mod inmemory_eventstore;
+pub mod file_eventstore;
+pub mod publishing_eventstore;
+pub mod encrypting_eventstore;
+pub mod instrumented_eventstore;
+pub mod hashchain;
+pub mod jsonl;
 pub mod snapshotter;
 pub mod dao;
+pub mod inmemory_dao;
 pub mod projector;
+pub mod reconciler;
+pub mod checkpoint;
+pub mod idempotency;
+pub mod subscription;
+pub mod crypto;
+pub mod eventbus;
+pub mod blocking;
+pub mod testing;
+
+#[cfg(test)]
+mod proptests;
 
 use aggregate::{BankAccountEvent, BankAccount};
-use eventsourcing::{EventStream, EventStore, EventPublisher};
+use eventsourcing::{EventStream, EventStore, EventPublisher, AsyncEventStore, AsyncEventPublisher};
 
 pub type BankAccountEventStore = dyn EventStore<Event = BankAccountEvent,
                                                 EventStream = EventStream<BankAccountEvent>,
                                                 SnapshotData = BankAccount>;
 
 pub type BankAccountEventPublisher = dyn EventPublisher<Event = BankAccountEvent>;
+
+pub type AsyncBankAccountEventStore = dyn AsyncEventStore<Event = BankAccountEvent,
+                                                          EventStream = EventStream<BankAccountEvent>,
+                                                          SnapshotData = BankAccount>;
+
+pub type AsyncBankAccountEventPublisher = dyn AsyncEventPublisher<Event = BankAccountEvent>;
@@ -1,6 +1,8 @@
 use chrono::{Local, DateTime};
-use super::dao::{BankAccountRM, BankAccountRMDao};
+use super::dao::{BankAccountRM, BankAccountRMDao, DaoError, DaoErrorKind};
 use super::aggregate::{BankAccountEvent, BankAccountId, BankAccountName};
+use super::eventsourcing::{EventPublisherError, EventPublisherErrorKind};
+use super::eventbus::EventSubscriber;
 
 pub struct BankAccountProjector {
     dao: Box<dyn BankAccountRMDao>,
@@ -11,17 +13,42 @@ impl BankAccountProjector {
         Self { dao }
     }
 
-    pub fn project(&self, event: BankAccountEvent) {
+    pub fn project(&self, stream_version: u64, event: BankAccountEvent) -> Result<u64, DaoError> {
         match event {
-            BankAccountEvent::Opened{ bank_account_id, name, occurred_at } => self.create(bank_account_id, name, occurred_at),
-            BankAccountEvent::Updated{ bank_account_id, name, occurred_at } => self.update(bank_account_id, name, occurred_at),
-            BankAccountEvent::Deposited{ bank_account_id, deposit, occurred_at } => self.deposit(bank_account_id, deposit, occurred_at),
-            BankAccountEvent::Withdrawn{ bank_account_id, withdraw, occurred_at } => self.withdraw(bank_account_id, withdraw, occurred_at),
-            BankAccountEvent::Closed{ bank_account_id, occurred_at } => self.close(bank_account_id, occurred_at),
-        };
+            BankAccountEvent::Opened{ bank_account_id, name, occurred_at } => {
+                self.create(bank_account_id, name, occurred_at, stream_version)
+            },
+            BankAccountEvent::Updated{ bank_account_id, name, occurred_at } => {
+                self.modify(bank_account_id, occurred_at, stream_version, |record| record.name = name.to_string())
+            },
+            BankAccountEvent::Deposited{ bank_account_id, deposit, occurred_at } => {
+                self.modify(bank_account_id, occurred_at, stream_version, |record| record.balance += deposit)
+            },
+            BankAccountEvent::Withdrawn{ bank_account_id, withdraw, occurred_at } => {
+                self.modify(bank_account_id, occurred_at, stream_version, |record| record.balance -= withdraw)
+            },
+            BankAccountEvent::Closed{ bank_account_id, occurred_at } => {
+                self.modify(bank_account_id, occurred_at, stream_version, |record| record.is_closed = true)
+            },
+        }
     }
 
-    fn create(&self, id: BankAccountId, name: BankAccountName, occurred_at: DateTime<Local>) {
+    pub fn redact(&self, id: BankAccountId) -> Result<bool, DaoError> {
+        match self.dao.find(id.to_string())? {
+            Some(mut record) => {
+                record.name = BankAccountName::redacted().to_string();
+                self.dao.overwrite(record)?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    fn create(&self, id: BankAccountId, name: BankAccountName, occurred_at: DateTime<Local>, version: u64)
+        -> Result<u64, DaoError> {
+        if let Some(record) = self.dao.find(id.to_string())? {
+            return Ok(record.version);
+        }
         self.dao.insert(BankAccountRM {
             bank_account_id: id.to_string(),
             name: name.to_string(),
@@ -29,39 +56,36 @@ impl BankAccountProjector {
             balance: 0,
             created_at: occurred_at.clone(),
             updated_at: occurred_at.clone(),
-            version: 1,
-        });
-    }
-
-    fn update(&self, id: BankAccountId, name: BankAccountName, occurred_at: DateTime<Local>) {
-        let mut record = self.dao.find(id.to_string()).unwrap();
-        record.name = name.to_string();
-        record.updated_at = occurred_at;
-        record.version = record.version + 1;
-        self.dao.update(record);
+            version,
+        })?;
+        Ok(version)
     }
 
-    fn deposit(&self, id: BankAccountId, deposit: i32, occurred_at: DateTime<Local>) {
-        let mut record = self.dao.find(id.to_string()).unwrap();
-        record.balance = record.balance + deposit;
+    fn modify<F>(&self, id: BankAccountId, occurred_at: DateTime<Local>, version: u64, apply: F)
+        -> Result<u64, DaoError>
+        where F: FnOnce(&mut BankAccountRM) {
+        let mut record = self.dao.find(id.to_string())?
+            .ok_or_else(|| DaoError::from(DaoErrorKind::NotFound(id.to_string())))?;
+        if record.version >= version {
+            return Ok(record.version);
+        }
+        apply(&mut record);
         record.updated_at = occurred_at;
-        record.version = record.version + 1;
-        self.dao.update(record);
+        record.version = version;
+        self.dao.update(record)?;
+        Ok(version)
     }
+}
 
-    fn withdraw(&self, id: BankAccountId, withdraw: i32, occurred_at: DateTime<Local>) {
-        let mut record = self.dao.find(id.to_string()).unwrap();
-        record.balance = record.balance - withdraw;
-        record.updated_at = occurred_at;
-        record.version = record.version + 1;
-        self.dao.update(record);
-    }
+impl EventSubscriber for BankAccountProjector {
+    type Event = BankAccountEvent;
 
-    fn close(&self, id: BankAccountId, occurred_at: DateTime<Local>) {
-        let mut record = self.dao.find(id.to_string()).unwrap();
-        record.is_closed = true;
-        record.updated_at = occurred_at;
-        record.version = record.version + 1;
-        self.dao.update(record);
+    fn on_events(&self, _stream_id: &str, stream_version: u64, events: &[BankAccountEvent])
+        -> Result<(), EventPublisherError> {
+        for (i, event) in events.iter().enumerate() {
+            self.project(stream_version + i as u64, event.clone())
+                .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::SubscriberError(err.to_string())))?;
+        }
+        Ok(())
     }
 }
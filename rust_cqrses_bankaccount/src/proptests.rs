@@ -0,0 +1,157 @@
+use chrono::Local;
+use uuid::Uuid;
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use super::eventsourcing::Snapshot;
+use super::aggregate::{
+    BankAccount,
+    BankAccountAggregate,
+    BankAccountCommand,
+    BankAccountEvent,
+    BankAccountId,
+    BankAccountName,
+};
+
+fn arb_bank_account_id() -> impl Strategy<Value = BankAccountId> {
+    any::<[u8; 16]>()
+        .prop_map(|bytes| BankAccountId::new(Uuid::from_bytes(bytes).to_string()).unwrap())
+}
+
+fn arb_bank_account_name() -> impl Strategy<Value = BankAccountName> {
+    "[a-zA-Z0-9 ]{1,254}".prop_map(|name| BankAccountName::new(name).unwrap())
+}
+
+fn arb_command(bank_account_id: BankAccountId) -> impl Strategy<Value = BankAccountCommand> {
+    let open_id = bank_account_id.clone();
+    let update_id = bank_account_id.clone();
+    let deposit_id = bank_account_id.clone();
+    let withdraw_id = bank_account_id.clone();
+    let close_id = bank_account_id;
+    prop_oneof![
+        1 => arb_bank_account_name().prop_map(move |name| BankAccountCommand::Open {
+            bank_account_id: open_id.clone(),
+            name,
+        }),
+        1 => arb_bank_account_name().prop_map(move |name| BankAccountCommand::Update {
+            bank_account_id: update_id.clone(),
+            name,
+        }),
+        4 => (-1000..1000i32).prop_map(move |deposit| BankAccountCommand::Deposit {
+            bank_account_id: deposit_id.clone(),
+            deposit,
+        }),
+        4 => (-1000..1000i32).prop_map(move |withdraw| BankAccountCommand::Withdraw {
+            bank_account_id: withdraw_id.clone(),
+            withdraw,
+        }),
+        1 => Just(BankAccountCommand::Close {
+            bank_account_id: close_id,
+        }),
+    ]
+}
+
+fn arb_commands() -> impl Strategy<Value = Vec<BankAccountCommand>> {
+    arb_bank_account_id().prop_flat_map(|bank_account_id| {
+        let open = arb_bank_account_name().prop_map({
+            let bank_account_id = bank_account_id.clone();
+            move |name| BankAccountCommand::Open {
+                bank_account_id: bank_account_id.clone(),
+                name,
+            }
+        });
+        (open, prop::collection::vec(arb_command(bank_account_id), 0..50))
+            .prop_map(|(open, mut commands)| {
+                commands.insert(0, open);
+                commands
+            })
+    })
+}
+
+fn arb_history() -> impl Strategy<Value = Vec<BankAccountEvent>> {
+    arb_commands().prop_map(|commands| execute(commands).1)
+}
+
+fn execute(commands: Vec<BankAccountCommand>) -> (BankAccountAggregate, Vec<BankAccountEvent>) {
+    let mut aggregate = BankAccountAggregate::new();
+    let mut history = vec![];
+    for command in commands {
+        if let Some((next, events)) = try_command(&aggregate, command) {
+            aggregate = next;
+            history.extend(events);
+        }
+    }
+    (aggregate, history)
+}
+
+fn try_command(aggregate: &BankAccountAggregate, command: BankAccountCommand)
+    -> Option<(BankAccountAggregate, Vec<BankAccountEvent>)> {
+    let events = BankAccountAggregate::handle_command(aggregate, command).ok()?;
+    let mut next = aggregate.clone();
+    for event in events.iter() {
+        next = BankAccountAggregate::apply_event(&next, event.clone()).unwrap();
+    }
+    next.set_version(aggregate.version() + events.len() as u64);
+    Some((next, events))
+}
+
+proptest! {
+    #[test]
+    fn test_balance_never_negative_and_closed_never_mutated(commands in arb_commands()) {
+        let mut aggregate = BankAccountAggregate::new();
+        for command in commands {
+            let was_closed = aggregate.state().as_ref().map(|ba| ba.is_closed()).unwrap_or(false);
+            if let Some((next, _)) = try_command(&aggregate, command) {
+                prop_assert!(!was_closed, "closed account was mutated: {:?}", next.state());
+                aggregate = next;
+            }
+            if let Some(ba) = aggregate.state() {
+                prop_assert!(ba.balance() >= 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_equals_in_memory_state(commands in arb_commands()) {
+        let (aggregate, history) = execute(commands);
+        let version = history.len() as u64;
+        let replayed = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), history, version).unwrap();
+        prop_assert_eq!(replayed.state(), aggregate.state());
+        prop_assert_eq!(replayed.version(), aggregate.version());
+    }
+
+    #[test]
+    fn test_snapshot_and_tail_equals_full_replay(history in arb_history(), index in any::<Index>()) {
+        let version = history.len() as u64;
+        let full = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), history.clone(), version).unwrap();
+
+        let split = 1 + index.index(history.len());
+        let (head, tail) = history.split_at(split);
+        let partial = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), head.to_vec(), split as u64).unwrap();
+        let snapshot = Snapshot::new(
+            BankAccountAggregate::stream_id(partial.id()),
+            partial.version(),
+            partial.state().as_ref().unwrap().clone(),
+            Local::now(),
+            );
+        let restored = BankAccountAggregate::load_from_history(
+            &BankAccountAggregate::load_from_snapshot(snapshot), tail.to_vec(), version).unwrap();
+
+        prop_assert_eq!(restored.state(), full.state());
+        prop_assert_eq!(restored.version(), full.version());
+    }
+
+    #[test]
+    fn test_json_round_trip_is_lossless(history in arb_history()) {
+        for event in history.iter() {
+            let json = serde_json::to_string(event).unwrap();
+            prop_assert_eq!(&serde_json::from_str::<BankAccountEvent>(&json).unwrap(), event);
+        }
+
+        let version = history.len() as u64;
+        let aggregate = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), history, version).unwrap();
+        let state = aggregate.state().as_ref().unwrap();
+        let json = serde_json::to_string(state).unwrap();
+        prop_assert_eq!(&serde_json::from_str::<BankAccount>(&json).unwrap(), state);
+    }
+}
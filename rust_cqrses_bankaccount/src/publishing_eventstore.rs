@@ -0,0 +1,256 @@
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use log::error;
+use serde::Deserialize;
+
+use super::eventsourcing::{
+    Snapshot,
+    StreamMetadata,
+    EventStore,
+    EventStoreError,
+    EventStoreErrorKind,
+    EventPublisher,
+};
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishFailurePolicy {
+    Fail,
+    #[default]
+    LogAndContinue,
+}
+
+pub struct PublishingEventStore<S, P> {
+    eventstore: S,
+    publisher: P,
+    policy: PublishFailurePolicy,
+    stream_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl<S, P> PublishingEventStore<S, P>
+    where S: EventStore,
+          P: EventPublisher<Event = S::Event> + Send + Sync {
+    pub fn new(eventstore: S, publisher: P) -> Self {
+        Self {
+            eventstore,
+            publisher,
+            policy: PublishFailurePolicy::default(),
+            stream_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: PublishFailurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn append_and_publish<F>(&self, stream_id: String, stream_version: u64, events: Vec<S::Event>, append: F)
+        -> Result<(), EventStoreError>
+        where F: FnOnce(Vec<S::Event>) -> Result<(), EventStoreError>,
+              S::Event: Clone {
+        let lock = self.stream_locks.lock().unwrap()
+            .entry(stream_id.clone())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        let result = {
+            let _guard = lock.lock().unwrap_or_else(|err| err.into_inner());
+            append(events.clone()).and_then(|_| self.publish(stream_id.clone(), stream_version, events))
+        };
+
+        let mut stream_locks = self.stream_locks.lock().unwrap();
+        if Arc::strong_count(&lock) == 2 {
+            stream_locks.remove(&stream_id);
+        }
+        result
+    }
+
+    fn publish(&self, stream_id: String, stream_version: u64, events: Vec<S::Event>) -> Result<(), EventStoreError> {
+        match self.publisher.publish(stream_id.clone(), stream_version, events) {
+            Ok(_) => Ok(()),
+            Err(err) => match self.policy {
+                PublishFailurePolicy::Fail => {
+                    Err(EventStoreErrorKind::PublishEventStreamError(err.to_string()))?
+                },
+                PublishFailurePolicy::LogAndContinue => {
+                    error!("Publish {}@{} error occurred: {}", stream_id, stream_version, err);
+                    Ok(())
+                },
+            },
+        }
+    }
+}
+
+impl<S, P> EventStore for PublishingEventStore<S, P>
+    where S: EventStore,
+          S::Event: Clone,
+          P: EventPublisher<Event = S::Event> + Send + Sync {
+    type Event = S::Event;
+    type EventStream = S::EventStream;
+    type SnapshotData = S::SnapshotData;
+
+    fn append_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+        -> Result<(), EventStoreError> {
+        self.append_and_publish(stream_id.clone(), stream_version, events, |events| {
+            self.eventstore.append_event_stream(stream_id.clone(), stream_version, events)
+        })
+    }
+
+    fn append_idempotent_event_stream(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>,
+                                      idempotency_key: String) -> Result<(), EventStoreError> {
+        self.append_and_publish(stream_id.clone(), stream_version, events, |events| {
+            self.eventstore.append_idempotent_event_stream(stream_id.clone(), stream_version, events, idempotency_key)
+        })
+    }
+
+    fn idempotency_key_version(&self, stream_id: String, idempotency_key: String)
+        -> Result<Option<u64>, EventStoreError> {
+        self.eventstore.idempotency_key_version(stream_id, idempotency_key)
+    }
+
+    fn event_stream_since(&self, stream_id: String, stream_version: u64)
+        -> Result<Self::EventStream, EventStoreError> {
+        self.eventstore.event_stream_since(stream_id, stream_version)
+    }
+
+    fn record_snapshot(&self, snapshot: Snapshot<Self::SnapshotData>)
+        -> Result<(), EventStoreError> {
+        self.eventstore.record_snapshot(snapshot)
+    }
+
+    fn read_snapshot(&self, stream_id: String)
+        -> Result<Option<Snapshot<Self::SnapshotData>>, EventStoreError> {
+        self.eventstore.read_snapshot(stream_id)
+    }
+
+    fn stream_metadata(&self, stream_id: String) -> Result<StreamMetadata, EventStoreError> {
+        self.eventstore.stream_metadata(stream_id)
+    }
+
+    fn set_stream_metadata(&self, stream_id: String, metadata: StreamMetadata)
+        -> Result<(), EventStoreError> {
+        self.eventstore.set_stream_metadata(stream_id, metadata)
+    }
+
+    fn purge_stream(&self, stream_id: String) -> Result<u64, EventStoreError> {
+        self.eventstore.purge_stream(stream_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::eventsourcing::{
+        EventStore,
+        EventPublisher,
+        EventPublisherError,
+        EventPublisherErrorKind,
+    };
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::usecase::command::{BankAccountAggregateUseCase, ErrorKind};
+    use super::super::testing::verify_event_store;
+    use super::{PublishingEventStore, PublishFailurePolicy};
+
+    struct RecordingPublisher {
+        published: Mutex<Vec<(String, u64, Vec<BankAccountEvent>)>>,
+        fail: bool,
+    }
+
+    impl RecordingPublisher {
+        fn new(fail: bool) -> Self {
+            Self {
+                published: Mutex::new(Vec::new()),
+                fail,
+            }
+        }
+    }
+
+    impl EventPublisher for RecordingPublisher {
+        type Event = BankAccountEvent;
+
+        fn publish(&self, stream_id: String, stream_version: u64, events: Vec<Self::Event>)
+            -> Result<(), EventPublisherError> {
+            if self.fail {
+                return Err(EventPublisherErrorKind::ConnectionError(String::from("broker unavailable")))?;
+            }
+            self.published.lock().unwrap().push((stream_id, stream_version, events));
+            Ok(())
+        }
+    }
+
+    fn bank_account_id() -> BankAccountId {
+        BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap()
+    }
+
+    #[test]
+    fn test_publishing_store_behaviour() {
+        verify_event_store(&PublishingEventStore::new(
+                InmemoryBankAccountEventStore::new(), RecordingPublisher::new(false)));
+    }
+
+    #[test]
+    fn test_publishing_store_publishes_after_commit() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let publisher = Arc::new(RecordingPublisher::new(false));
+        let usecase = BankAccountAggregateUseCase::new(Box::new(
+                PublishingEventStore::new(store.clone(), publisher.clone())));
+
+        usecase.open(bank_account_id(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        usecase.deposit(bank_account_id(), 100).unwrap();
+        assert!(usecase.withdraw(bank_account_id(), 1000).is_err());
+
+        let published = publisher.published.lock().unwrap();
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0].0, BankAccountAggregate::stream_id(&bank_account_id()));
+        assert_eq!(store.event_stream_since(published[0].0.clone(), 1).unwrap().version(), 2);
+    }
+
+    #[test]
+    fn test_publishing_store_publishes_each_stream_in_order() {
+        let publisher = Arc::new(RecordingPublisher::new(false));
+        let usecase = Arc::new(BankAccountAggregateUseCase::new(Box::new(
+                PublishingEventStore::new(InmemoryBankAccountEventStore::new(), publisher.clone()))));
+        usecase.open(bank_account_id(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+
+        let workers: Vec<_> = (0..4).map(|_| {
+            let usecase = usecase.clone();
+            thread::spawn(move || {
+                for _ in 0..25 {
+                    while usecase.deposit(bank_account_id(), 10).is_err() {}
+                }
+            })
+        }).collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let versions: Vec<u64> = publisher.published.lock().unwrap().iter()
+            .map(|(_, stream_version, _)| *stream_version)
+            .collect();
+        assert_eq!(versions, (1..=101).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_publishing_store_failure_policy() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let usecase = BankAccountAggregateUseCase::new(Box::new(
+                PublishingEventStore::new(store.clone(), RecordingPublisher::new(true))));
+
+        usecase.open(bank_account_id(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        assert_eq!(usecase.get(bank_account_id()).unwrap().version(), 1);
+
+        let usecase = BankAccountAggregateUseCase::new(Box::new(
+                PublishingEventStore::new(store.clone(), RecordingPublisher::new(true))
+                .with_policy(PublishFailurePolicy::Fail)));
+        let err = usecase.deposit(bank_account_id(), 100).unwrap_err();
+        match err.kind() {
+            ErrorKind::EventStoreError => (),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+        let cause = failure::Fail::cause(&err).unwrap().to_string();
+        assert!(cause.starts_with("Publish event stream error"));
+        assert_eq!(store.event_stream_since(BankAccountAggregate::stream_id(&bank_account_id()), 1).unwrap().version(), 2);
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeSet;
+use serde::Serialize;
+
+use super::aggregate::{BankAccount, BankAccountId};
+use super::dao::{BankAccountRM, BankAccountRMDao};
+use super::usecase::command::{BankAccountAggregateUseCase, ErrorKind};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldMismatch {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    MissingReadModel {
+        version: u64,
+    },
+    OrphanReadModel {
+        version: u64,
+    },
+    Mismatch {
+        fields: Vec<FieldMismatch>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub bank_account_id: String,
+    pub kind: DiscrepancyKind,
+    pub repaired: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ReconciliationReport {
+    pub checked: u64,
+    pub discrepancies: Vec<Discrepancy>,
+    pub errors: Vec<(String, String)>,
+}
+
+pub struct BankAccountReconciler {
+    usecase: BankAccountAggregateUseCase,
+    dao: Box<dyn BankAccountRMDao>,
+}
+
+fn read_model_of(state: &BankAccount, version: u64) -> BankAccountRM {
+    BankAccountRM {
+        bank_account_id: state.id().to_string(),
+        name: state.name().to_string(),
+        is_closed: state.is_closed(),
+        balance: state.balance(),
+        created_at: *state.created_at(),
+        updated_at: *state.updated_at(),
+        version,
+    }
+}
+
+fn compare(expected: &BankAccountRM, actual: &BankAccountRM) -> Vec<FieldMismatch> {
+    let fields = vec![
+        ("balance", expected.balance.to_string(), actual.balance.to_string()),
+        ("name", expected.name.clone(), actual.name.clone()),
+        ("is_closed", expected.is_closed.to_string(), actual.is_closed.to_string()),
+        ("version", expected.version.to_string(), actual.version.to_string()),
+    ];
+    fields.into_iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(field, expected, actual)| FieldMismatch {
+            field: field.to_string(),
+            expected,
+            actual,
+        })
+        .collect()
+}
+
+impl BankAccountReconciler {
+    pub fn new(usecase: BankAccountAggregateUseCase, dao: Box<dyn BankAccountRMDao>) -> Self {
+        Self {
+            usecase,
+            dao,
+        }
+    }
+
+    pub fn reconcile(&self, bank_account_ids: &[BankAccountId], repair: bool) -> ReconciliationReport {
+        let mut report = ReconciliationReport::default();
+        let mut ids: BTreeSet<String> = bank_account_ids.iter().map(|id| id.to_string()).collect();
+        match self.dao.list() {
+            Ok(records) => ids.extend(records.into_iter().map(|record| record.bank_account_id)),
+            Err(err) => report.errors.push((String::from("*"), err.to_string())),
+        }
+
+        for id in ids {
+            report.checked += 1;
+            match self.reconcile_one(&id, repair) {
+                Ok(Some(discrepancy)) => report.discrepancies.push(discrepancy),
+                Ok(None) => (),
+                Err(reason) => report.errors.push((id, reason)),
+            }
+        }
+        report
+    }
+
+    fn reconcile_one(&self, id: &str, repair: bool) -> Result<Option<Discrepancy>, String> {
+        let bank_account_id = BankAccountId::new(id.to_string()).map_err(|err| err.to_string())?;
+        let expected = match self.usecase.get(bank_account_id) {
+            Ok(aggregate) => aggregate.state().as_ref().map(|state| read_model_of(state, aggregate.version())),
+            Err(err) => match err.kind() {
+                ErrorKind::BankAccountNotFound(_) | ErrorKind::BankAccountDeleted(_) => None,
+                _ => return Err(err.to_string()),
+            },
+        };
+        let actual = self.dao.find(id.to_string()).map_err(|err| err.to_string())?;
+
+        let kind = match (&expected, &actual) {
+            (None, None) => return Ok(None),
+            (Some(expected), None) => DiscrepancyKind::MissingReadModel { version: expected.version },
+            (None, Some(actual)) => DiscrepancyKind::OrphanReadModel { version: actual.version },
+            (Some(expected), Some(actual)) => {
+                let fields = compare(expected, actual);
+                if fields.is_empty() {
+                    return Ok(None);
+                }
+                DiscrepancyKind::Mismatch { fields }
+            },
+        };
+
+        let repaired = match (repair, expected, actual) {
+            (true, Some(expected), None) => {
+                self.dao.insert(expected.clone()).map_err(|err| err.to_string())?;
+                self.verify_repair(&expected)?
+            },
+            (true, Some(expected), Some(_)) => {
+                self.dao.overwrite(expected.clone()).map_err(|err| err.to_string())?;
+                self.verify_repair(&expected)?
+            },
+            _ => false,
+        };
+
+        Ok(Some(Discrepancy {
+            bank_account_id: id.to_string(),
+            kind,
+            repaired,
+        }))
+    }
+
+    fn verify_repair(&self, expected: &BankAccountRM) -> Result<bool, String> {
+        self.dao.find(expected.bank_account_id.clone())
+            .map(|record| record.is_some_and(|record| compare(expected, &record).is_empty()))
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::aggregate::{BankAccountId, BankAccountName};
+    use super::super::dao::BankAccountRMDao;
+    use super::super::eventbus::EventBus;
+    use super::super::inmemory_dao::InmemoryBankAccountRMDao;
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::projector::BankAccountProjector;
+    use super::super::publishing_eventstore::PublishingEventStore;
+    use super::super::usecase::command::BankAccountAggregateUseCase;
+    use super::{BankAccountReconciler, DiscrepancyKind};
+
+    #[test]
+    fn test_reconciler_reports_and_repairs_drift() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let dao = Arc::new(InmemoryBankAccountRMDao::new());
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe("projector", BankAccountProjector::new(Box::new(dao.clone())));
+        let usecase = BankAccountAggregateUseCase::new(Box::new(PublishingEventStore::new(store.clone(), bus.clone())));
+
+        let ids = vec![
+            BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap(),
+            BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap(),
+            BankAccountId::new(String::from("0c5f9e44-3ab1-4d2e-9d0d-8b2e1c7a3f10")).unwrap(),
+        ];
+        for id in ids.iter() {
+            usecase.open(id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+            usecase.deposit(id.clone(), 100).unwrap();
+        }
+
+        let reconciler = BankAccountReconciler::new(
+            BankAccountAggregateUseCase::new(Box::new(store.clone())), Box::new(dao.clone()));
+        let report = reconciler.reconcile(&ids, false);
+        assert_eq!(report.checked, 3);
+        assert!(report.discrepancies.is_empty());
+
+        let mut drifted = dao.find(ids[0].to_string()).unwrap().unwrap();
+        drifted.balance = 200;
+        drifted.version = 3;
+        dao.update(drifted).unwrap();
+        let unprojected = BankAccountAggregateUseCase::new(Box::new(store.clone()));
+        unprojected.deposit(ids[1].clone(), 50).unwrap();
+
+        let report = reconciler.reconcile(&ids, false);
+        assert_eq!(report.discrepancies.len(), 2);
+        match &report.discrepancies[0].kind {
+            DiscrepancyKind::Mismatch { fields } => {
+                let names: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+                assert_eq!(names, vec!["balance", "version"]);
+                assert_eq!(fields[0].expected, "100");
+                assert_eq!(fields[0].actual, "200");
+            },
+            kind => panic!("unexpected discrepancy: {:?}", kind),
+        }
+        assert_eq!(report.discrepancies[1].bank_account_id, ids[1].to_string());
+        assert!(!report.discrepancies[1].repaired);
+
+        let report = reconciler.reconcile(&ids, true);
+        assert!(report.discrepancies.iter().all(|discrepancy| discrepancy.repaired));
+        assert_eq!(dao.find(ids[0].to_string()).unwrap().unwrap().balance, 100);
+        assert_eq!(dao.find(ids[1].to_string()).unwrap().unwrap().balance, 150);
+        assert!(reconciler.reconcile(&ids, false).discrepancies.is_empty());
+    }
+}
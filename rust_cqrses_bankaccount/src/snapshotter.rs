@@ -2,8 +2,9 @@ use std::fmt;
 use chrono::Local;
 use failure::{Fail, Context, Backtrace};
 
-use super::eventsourcing::{Snapshot, EventStoreError};
-use super::aggregate::{BankAccountId, BankAccountAggregate, Error as BankAccountError};
+use super::eventsourcing::{Snapshot, EventStoreError, EventPublisherError, EventPublisherErrorKind};
+use super::eventbus::EventSubscriber;
+use super::aggregate::{BankAccountId, BankAccountAggregate, BankAccountEvent, Error as BankAccountError};
 
 use super::BankAccountEventStore;
 
@@ -78,7 +79,7 @@ impl BankAccountAggregateSnapshotter {
         }
     }
 
-    pub fn take_snapshot(&self, bank_account_id: BankAccountId) -> Result<(), Error> {
+    pub fn take_snapshot(&self, bank_account_id: BankAccountId) -> Result<u64, Error> {
         let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
         self.eventstore.event_stream_since(stream_id, 1)
             .map_err(|err| Error::from(err))
@@ -89,6 +90,7 @@ impl BankAccountAggregateSnapshotter {
                     .map_err(|err| Error::from(err))
             })
             .and_then(|aggregate| {
+                let version = aggregate.version();
                 let snapshot = Snapshot::new(
                     BankAccountAggregate::stream_id(aggregate.id()),
                     aggregate.version(),
@@ -96,8 +98,35 @@ impl BankAccountAggregateSnapshotter {
                     Local::now(),
                     );
                 self.eventstore.record_snapshot(snapshot)
+                    .map(|_| version)
                     .map_err(|err| Error::from(err))
             })
-            .map(|_| ())
+    }
+
+    pub fn truncate_before_snapshot(&self, bank_account_id: BankAccountId) -> Result<Option<u64>, Error> {
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        match self.eventstore.read_snapshot(stream_id.clone())? {
+            Some(snapshot) => {
+                let mut metadata = self.eventstore.stream_metadata(stream_id.clone())?;
+                metadata.truncate_before = Some(snapshot.stream_version() + 1);
+                self.eventstore.set_stream_metadata(stream_id, metadata.clone())?;
+                Ok(metadata.truncate_before)
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+impl EventSubscriber for BankAccountAggregateSnapshotter {
+    type Event = BankAccountEvent;
+
+    fn on_events(&self, _stream_id: &str, _stream_version: u64, events: &[BankAccountEvent])
+        -> Result<(), EventPublisherError> {
+        match events.first() {
+            Some(event) => self.take_snapshot(event.bank_account_id().clone())
+                .map(|_| ())
+                .map_err(|err| EventPublisherError::from(EventPublisherErrorKind::SubscriberError(err.to_string()))),
+            None => Ok(()),
+        }
     }
 }
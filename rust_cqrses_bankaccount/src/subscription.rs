@@ -0,0 +1,95 @@
+use std::marker::PhantomData;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::eventsourcing::{EventPublisher, EventPublisherError};
+
+pub struct StreamNotifier<E> {
+    sender: broadcast::Sender<String>,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E> StreamNotifier<E> {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            _event: PhantomData,
+        }
+    }
+
+    pub fn watch(&self) -> StreamWatcher {
+        StreamWatcher {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl<E> EventPublisher for StreamNotifier<E> {
+    type Event = E;
+
+    fn publish(&self, stream_id: String, _stream_version: u64, _events: Vec<Self::Event>)
+        -> Result<(), EventPublisherError> {
+        let _ = self.sender.send(stream_id);
+        Ok(())
+    }
+}
+
+pub struct StreamWatcher {
+    receiver: broadcast::Receiver<String>,
+}
+
+impl StreamWatcher {
+    pub async fn changed(&mut self, stream_id: &str) -> bool {
+        loop {
+            match self.receiver.recv().await {
+                Ok(changed) if changed == stream_id => return true,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => return true,
+                Err(RecvError::Closed) => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::aggregate::{BankAccountAggregate, BankAccountEvent, BankAccountId, BankAccountName};
+    use super::super::blocking::BlockingEventStore;
+    use super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::publishing_eventstore::PublishingEventStore;
+    use super::super::usecase::command::AsyncBankAccountAggregateUseCase;
+    use super::StreamNotifier;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_notifier() {
+        let notifier = Arc::new(StreamNotifier::new(16));
+        let usecase = AsyncBankAccountAggregateUseCase::new(Box::new(BlockingEventStore::new(
+                PublishingEventStore::new(InmemoryBankAccountEventStore::new(), notifier.clone()))));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let other_id = BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+
+        let mut watcher = notifier.watch();
+        assert!(usecase.events_since(bank_account_id.clone(), 1).await.unwrap().is_empty());
+
+        usecase.open(other_id.clone(), BankAccountName::new(String::from("bar")).unwrap()).await.unwrap();
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).await.unwrap();
+        usecase.deposit(bank_account_id.clone(), 100).await.unwrap();
+        assert!(watcher.changed(&stream_id).await);
+
+        let events = usecase.events_since(bank_account_id.clone(), 2).await.unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            (2, BankAccountEvent::Deposited { deposit, .. }) => assert_eq!(*deposit, 100),
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        assert!(watcher.changed(&stream_id).await);
+        drop(usecase);
+        drop(notifier);
+        assert!(!watcher.changed(&stream_id).await);
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+use chrono::{Duration, Local};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::eventsourcing::{Aggregate, Snapshot, StreamMetadata, EventStoreErrorKind};
+use super::aggregate::{
+    BankAccountAggregate,
+    BankAccountEvent,
+    BankAccountId,
+    BankAccountName,
+    Error as BankAccountError,
+    ErrorKind as BankAccountErrorKind,
+};
+use super::BankAccountEventStore;
+
+pub const DEFAULT_IGNORED_FIELDS: &[&str] = &["occurred_at"];
+
+pub trait ErrorKindOf {
+    type Kind: PartialEq + fmt::Debug;
+
+    fn error_kind(&self) -> &Self::Kind;
+}
+
+impl ErrorKindOf for BankAccountError {
+    type Kind = BankAccountErrorKind;
+
+    fn error_kind(&self) -> &Self::Kind {
+        self.kind()
+    }
+}
+
+pub fn given<A>(history: Vec<A::Event>) -> Given<A>
+    where A: Aggregate + Default,
+          A::Error: fmt::Display {
+    let version = history.len() as u64;
+    let aggregate = match A::load_from_history(&A::default(), history, version) {
+        Ok(aggregate) => aggregate,
+        Err(err) => panic!("Failed to replay the given history: {}", err),
+    };
+    Given {
+        aggregate,
+        ignored_fields: DEFAULT_IGNORED_FIELDS.to_vec(),
+    }
+}
+
+pub struct Given<A> {
+    aggregate: A,
+    ignored_fields: Vec<&'static str>,
+}
+
+impl<A: Aggregate> Given<A> {
+    pub fn ignoring_fields(self, fields: &[&'static str]) -> Self {
+        Self {
+            ignored_fields: fields.to_vec(),
+            .. self
+        }
+    }
+
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+
+    pub fn when(self, command: A::Command) -> Then<A> {
+        let result = A::handle_command(&self.aggregate, command);
+        Then {
+            aggregate: self.aggregate,
+            result,
+            ignored_fields: self.ignored_fields,
+        }
+    }
+}
+
+pub struct Then<A: Aggregate> {
+    aggregate: A,
+    result: Result<Vec<A::Event>, A::Error>,
+    ignored_fields: Vec<&'static str>,
+}
+
+impl<A> Then<A>
+    where A: Aggregate,
+          A::Event: Serialize + Clone + fmt::Debug,
+          A::Error: fmt::Display {
+    pub fn then_expect_events(self, expected: Vec<A::Event>) -> A {
+        let events = match self.result {
+            Ok(events) => events,
+            Err(err) => panic!("Expected events but got error: {}", err),
+        };
+
+        assert_eq!(
+            strip_fields(&events, &self.ignored_fields),
+            strip_fields(&expected, &self.ignored_fields),
+            "Unexpected events: {:?}", events);
+
+        let mut aggregate = self.aggregate;
+        for event in events {
+            aggregate = match A::apply_event(&aggregate, event.clone()) {
+                Ok(aggregate) => aggregate,
+                Err(err) => panic!("Failed to apply emitted event {:?}: {}", event, err),
+            };
+        }
+        aggregate
+    }
+
+    pub fn then_expect_error<K>(self, kind: K)
+        where A::Error: ErrorKindOf<Kind = K>,
+              K: PartialEq + fmt::Debug {
+        match self.result {
+            Ok(events) => panic!("Expected error {:?} but got events: {:?}", kind, events),
+            Err(err) => assert_eq!(err.error_kind(), &kind),
+        }
+    }
+}
+
+fn strip_fields<E: Serialize>(events: &[E], fields: &[&str]) -> Vec<Value> {
+    events.iter()
+        .map(|event| {
+            let mut value = serde_json::to_value(event).unwrap();
+            strip_value(&mut value, fields);
+            value
+        })
+        .collect()
+}
+
+fn strip_value(value: &mut Value, fields: &[&str]) {
+    match value {
+        Value::Object(map) => {
+            for field in fields {
+                map.remove(*field);
+            }
+            for (_, v) in map.iter_mut() {
+                strip_value(v, fields);
+            }
+        },
+        Value::Array(values) => {
+            for v in values.iter_mut() {
+                strip_value(v, fields);
+            }
+        },
+        _ => {},
+    }
+}
+
+pub fn verify_event_store(store: &BankAccountEventStore) {
+    let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+    let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+
+    let events = vec![
+        BankAccountEvent::Opened {
+            bank_account_id: bank_account_id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+            occurred_at: Local::now(),
+        },
+        BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 500,
+            occurred_at: Local::now(),
+        },
+    ];
+
+    match store.event_stream_since(stream_id.clone(), 1) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::NoEventStreamError(_, _) => {},
+            kind => panic!("Unexpected error for a missing stream: {:?}", kind),
+        },
+        Ok(stream) => panic!("Unexpected stream: {:?}", stream),
+    };
+
+    store.append_event_stream(stream_id.clone(), 1, events.clone()).unwrap();
+
+    let stream = store.event_stream_since(stream_id.clone(), 1).unwrap();
+    assert_eq!(stream.events(), &events);
+    assert_eq!(stream.version(), 2);
+
+    let stream = store.event_stream_since(stream_id.clone(), 2).unwrap();
+    assert_eq!(stream.events(), &events[1..].to_vec());
+    assert_eq!(stream.version(), 2);
+
+    match store.event_stream_since(stream_id.clone(), 3) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::NoEventStreamError(_, _) => {},
+            kind => panic!("Unexpected error past the end of a stream: {:?}", kind),
+        },
+        Ok(stream) => panic!("Unexpected stream: {:?}", stream),
+    };
+
+    match store.append_event_stream(stream_id.clone(), 2, events[1..].to_vec()) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::DuplicateEntryError(_) => {},
+            kind => panic!("Unexpected error for a conflicting append: {:?}", kind),
+        },
+        Ok(_) => panic!("Conflicting append was accepted"),
+    };
+    assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().version(), 2);
+
+    assert!(store.read_snapshot(stream_id.clone()).unwrap().is_none());
+    let aggregate = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), events, 2).unwrap();
+    let state = aggregate.state().clone().unwrap();
+    store.record_snapshot(Snapshot::new(stream_id.clone(), 2, state.clone(), Local::now())).unwrap();
+    let snapshot = store.read_snapshot(stream_id.clone()).unwrap().unwrap();
+    assert_eq!(snapshot.stream_id(), stream_id);
+    assert_eq!(snapshot.stream_version(), 2);
+    assert_eq!(snapshot.snapshot(), &state);
+}
+
+pub fn verify_stream_metadata(store: &BankAccountEventStore) {
+    let bank_account_id = BankAccountId::new(String::from("a3bb189e-8bf9-3888-9912-ace4e6543002")).unwrap();
+    let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+    let expired = Local::now() - Duration::days(2);
+
+    let events = vec![
+        BankAccountEvent::Opened {
+            bank_account_id: bank_account_id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+            occurred_at: expired,
+        },
+        BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 100,
+            occurred_at: expired,
+        },
+        BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 200,
+            occurred_at: Local::now(),
+        },
+        BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 300,
+            occurred_at: Local::now(),
+        },
+    ];
+
+    assert_eq!(store.stream_metadata(stream_id.clone()).unwrap(), StreamMetadata::default());
+    store.append_event_stream(stream_id.clone(), 1, events.clone()).unwrap();
+
+    let truncated = StreamMetadata {
+        truncate_before: Some(3),
+        ..StreamMetadata::default()
+    };
+    let expiring = StreamMetadata {
+        max_age: Some(60 * 60 * 24),
+        ..StreamMetadata::default()
+    };
+    match store.set_stream_metadata(stream_id.clone(), truncated.clone()) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::InvalidStreamMetadataError(_) => {},
+            kind => panic!("Unexpected error for a truncation past the snapshot: {:?}", kind),
+        },
+        Ok(_) => panic!("Truncation past the snapshot was accepted"),
+    };
+    store.set_stream_metadata(stream_id.clone(), expiring.clone()).unwrap();
+    assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().events(), &events);
+    assert_eq!(store.purge_stream(stream_id.clone()).unwrap(), 0);
+
+    let aggregate = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), events[..2].to_vec(), 2).unwrap();
+    store.record_snapshot(Snapshot::new(stream_id.clone(), 2, aggregate.state().clone().unwrap(), Local::now())).unwrap();
+
+    store.set_stream_metadata(stream_id.clone(), truncated).unwrap();
+    let stream = store.event_stream_since(stream_id.clone(), 1).unwrap();
+    assert_eq!(stream.events(), &events[2..].to_vec());
+    assert_eq!(stream.version(), 4);
+    assert!(store.set_stream_metadata(stream_id.clone(), StreamMetadata {
+        truncate_before: Some(4),
+        ..StreamMetadata::default()
+    }).is_err());
+
+    store.set_stream_metadata(stream_id.clone(), expiring).unwrap();
+    assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().events(), &events[2..].to_vec());
+
+    store.set_stream_metadata(stream_id.clone(), StreamMetadata::default()).unwrap();
+    assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().events(), &events);
+
+    store.set_stream_metadata(stream_id.clone(), StreamMetadata {
+        truncate_before: Some(2),
+        ..StreamMetadata::default()
+    }).unwrap();
+    assert_eq!(store.purge_stream(stream_id.clone()).unwrap(), 1);
+    let last_purged = store.stream_metadata(stream_id.clone()).unwrap().last_purged;
+    assert_eq!(last_purged.as_ref().map(|purged| purged.stream_version), Some(1));
+    store.set_stream_metadata(stream_id.clone(), StreamMetadata::default()).unwrap();
+    assert_eq!(store.stream_metadata(stream_id.clone()).unwrap().last_purged, last_purged);
+    assert_eq!(store.event_stream_since(stream_id.clone(), 1).unwrap().events(), &events[1..].to_vec());
+    match store.append_event_stream(stream_id.clone(), 1, events[..1].to_vec()) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::DuplicateEntryError(_) => {},
+            kind => panic!("Unexpected error for a purged version: {:?}", kind),
+        },
+        Ok(_) => panic!("Append over a purged version was accepted"),
+    };
+
+    let aggregate = BankAccountAggregate::load_from_history(&BankAccountAggregate::new(), events.clone(), 4).unwrap();
+    store.record_snapshot(Snapshot::new(stream_id.clone(), 4, aggregate.state().clone().unwrap(), Local::now())).unwrap();
+
+    let deleted = StreamMetadata {
+        deleted: true,
+        ..StreamMetadata::default()
+    };
+    store.set_stream_metadata(stream_id.clone(), deleted.clone()).unwrap();
+    assert!(store.stream_metadata(stream_id.clone()).unwrap().deleted);
+    match store.event_stream_since(stream_id.clone(), 1) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::StreamDeletedError(_) => {},
+            kind => panic!("Unexpected error for a deleted stream: {:?}", kind),
+        },
+        Ok(stream) => panic!("Unexpected stream: {:?}", stream),
+    };
+    match store.append_event_stream(stream_id.clone(), 5, events[3..].to_vec()) {
+        Err(err) => match err.kind() {
+            EventStoreErrorKind::StreamDeletedError(_) => {},
+            kind => panic!("Unexpected error for a deleted stream: {:?}", kind),
+        },
+        Ok(_) => panic!("Append to a deleted stream was accepted"),
+    };
+
+    assert_eq!(store.purge_stream(stream_id.clone()).unwrap(), 3);
+    assert!(store.read_snapshot(stream_id.clone()).unwrap().is_none());
+    assert!(store.stream_metadata(stream_id.clone()).unwrap().deleted);
+}
@@ -1,17 +1,33 @@
 use std::fmt;
+use std::time::Instant;
+use chrono::{Duration, Local};
 use failure::{Fail, Context, Backtrace};
+use log::warn;
+use metrics::{counter, histogram};
+use tracing::instrument;
 
 use super::super::aggregate::{
+    BankAccount,
     BankAccountCommand,
+    BankAccountEvent,
     BankAccountId,
     BankAccountName,
     BankAccountAggregate,
     Error as BankAccountError,
 };
 
-use super::super::eventsourcing::{EventStoreError, EventStoreErrorKind};
+use super::super::eventsourcing::{EventStream, EventStoreError, EventStoreErrorKind, Snapshot};
 
-use super::super::BankAccountEventStore;
+use super::super::idempotency::{
+    command_fingerprint,
+    AsyncIdempotencyStore,
+    CommandOutcome,
+    IdempotencyError,
+    IdempotencyStore,
+    Reservation,
+};
+
+use super::super::{BankAccountEventStore, AsyncBankAccountEventStore};
 
 #[derive(Debug)]
 pub struct Error {
@@ -23,11 +39,23 @@ pub enum ErrorKind {
     #[fail(display = "BankAccount does not exits: {:?}", _0)]
     BankAccountNotFound(BankAccountId),
 
+    #[fail(display = "BankAccount is deleted: {:?}", _0)]
+    BankAccountDeleted(BankAccountId),
+
     #[fail(display = "Event store error")]
     EventStoreError,
 
     #[fail(display = "Bank account error")]
     BankAccountError,
+
+    #[fail(display = "Idempotency key is reused with a different command: {}", _0)]
+    IdempotencyKeyReused(String),
+
+    #[fail(display = "Command with the idempotency key is in progress: {}", _0)]
+    CommandInProgress(String),
+
+    #[fail(display = "Idempotency store error")]
+    IdempotencyStoreError,
 }
 
 impl Fail for Error {
@@ -76,30 +104,204 @@ impl From<EventStoreError> for Error {
     }
 }
 
+impl From<IdempotencyError> for Error {
+    fn from(error: IdempotencyError) -> Self {
+        Self { inner: error.context(ErrorKind::IdempotencyStoreError) }
+    }
+}
+
+fn record_command(command_type: &'static str, started_at: Instant, result: &Result<(), Error>) {
+    let outcome = match result {
+        Ok(_) => "ok",
+        Err(err) => match err.kind() {
+            ErrorKind::BankAccountNotFound(_) => "not_found",
+            ErrorKind::BankAccountDeleted(_) => "deleted",
+            ErrorKind::EventStoreError => "event_store_error",
+            ErrorKind::BankAccountError => "rejected",
+            ErrorKind::IdempotencyKeyReused(_) => "idempotency_key_reused",
+            ErrorKind::CommandInProgress(_) => "in_progress",
+            ErrorKind::IdempotencyStoreError => "idempotency_store_error",
+        },
+    };
+    counter!("bankaccount_commands_total", "command" => command_type, "outcome" => outcome).increment(1);
+    histogram!("bankaccount_command_duration_seconds", "command" => command_type, "outcome" => outcome)
+        .record(started_at.elapsed().as_secs_f64());
+}
+
+fn versioned_events(stream: EventStream<BankAccountEvent>) -> Vec<(u64, BankAccountEvent)> {
+    let first_version = stream.version() + 1 - stream.events().len() as u64;
+    stream.events().iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, event)| (first_version + i as u64, event))
+        .collect()
+}
+
+fn events_since_stream(bank_account_id: BankAccountId, result: Result<EventStream<BankAccountEvent>, EventStoreError>)
+    -> Result<Vec<(u64, BankAccountEvent)>, Error> {
+    match result {
+        Ok(stream) => Ok(versioned_events(stream)),
+        Err(e) => match e.kind() {
+            EventStoreErrorKind::NoEventStreamError(_, _) => Ok(Vec::new()),
+            EventStoreErrorKind::StreamDeletedError(_) => Err(ErrorKind::BankAccountDeleted(bank_account_id))?,
+            _ => Err(e)?,
+        },
+    }
+}
+
+fn restore_from_snapshot(snapshot: Option<Snapshot<BankAccount>>) -> (Option<BankAccountAggregate>, u64) {
+    match snapshot {
+        Some(snapshot) => {
+            let stream_version = snapshot.stream_version() + 1;
+            (Some(BankAccountAggregate::load_from_snapshot(snapshot)), stream_version)
+        },
+        None => (None, 1),
+    }
+}
+
+fn restore_from_stream(id: &BankAccountId, aggregate: Option<BankAccountAggregate>,
+                       result: Result<EventStream<BankAccountEvent>, EventStoreError>)
+    -> Result<Option<BankAccountAggregate>, Error> {
+    match result {
+        Ok(stream) => {
+            let aggregate = aggregate.unwrap_or_default();
+            let history = stream.events().clone();
+            Ok(Some(BankAccountAggregate::load_from_history(&aggregate, history, stream.version())?))
+        },
+        Err(e) => match e.kind() {
+            EventStoreErrorKind::NoEventStreamError(_, _) => Ok(aggregate),
+            EventStoreErrorKind::StreamDeletedError(_) => Err(ErrorKind::BankAccountDeleted(id.clone()))?,
+            _ => Err(e)?,
+        },
+    }
+}
+
+struct Decision {
+    stream_id: String,
+    stream_version: u64,
+    events: Vec<BankAccountEvent>,
+}
+
+impl Decision {
+    fn last_version(&self) -> u64 {
+        self.stream_version + self.events.len() as u64 - 1
+    }
+}
+
+fn decide(aggregate: Option<BankAccountAggregate>, command: BankAccountCommand) -> Result<Decision, Error> {
+    let mut aggregate = match (aggregate, &command) {
+        (Some(aggregate), _) => aggregate,
+        (None, BankAccountCommand::Open { .. }) => BankAccountAggregate::new(),
+        (None, command) => Err(ErrorKind::BankAccountNotFound(command.bank_account_id().clone()))?,
+    };
+
+    let events = BankAccountAggregate::handle_command(&aggregate, command)?;
+    for event in events.iter() {
+        aggregate = BankAccountAggregate::apply_event(&aggregate, event.clone())?;
+    }
+
+    Ok(Decision {
+        stream_id: BankAccountAggregate::stream_id(aggregate.id()),
+        stream_version: aggregate.version() + 1,
+        events,
+    })
+}
+
+enum ReservationStep {
+    Execute,
+    Replay(CommandOutcome),
+    LookupVersion,
+}
+
+fn reservation_step(reservation: Reservation, idempotency_key: &str) -> Result<ReservationStep, Error> {
+    match reservation {
+        Reservation::Reserved => Ok(ReservationStep::Execute),
+        Reservation::Completed(outcome) => Ok(ReservationStep::Replay(outcome)),
+        Reservation::InProgress => Ok(ReservationStep::LookupVersion),
+        Reservation::Conflict => Err(ErrorKind::IdempotencyKeyReused(idempotency_key.to_string()))?,
+    }
+}
+
+fn in_progress_version(result: Result<Option<u64>, EventStoreError>, idempotency_key: &str) -> Result<u64, Error> {
+    let recorded = match result {
+        Err(ref err) if matches!(err.kind(), EventStoreErrorKind::UnsupportedOperationError(_)) => None,
+        result => result?,
+    };
+    match recorded {
+        Some(stream_version) => Ok(stream_version),
+        None => Err(ErrorKind::CommandInProgress(idempotency_key.to_string()))?,
+    }
+}
+
+fn command_outcome(result: &Result<u64, Error>) -> Option<CommandOutcome> {
+    match result {
+        Ok(stream_version) => Some(CommandOutcome::Appended(*stream_version)),
+        Err(err) => match err.kind() {
+            ErrorKind::BankAccountError => err.cause()
+                .and_then(|cause| cause.downcast_ref::<BankAccountError>())
+                .map(|cause| CommandOutcome::Rejected(cause.kind().clone())),
+            ErrorKind::BankAccountNotFound(_) => Some(CommandOutcome::NotFound),
+            ErrorKind::BankAccountDeleted(_) => Some(CommandOutcome::Deleted),
+            _ => None,
+        },
+    }
+}
+
+fn replay(id: BankAccountId, outcome: CommandOutcome) -> Result<(), Error> {
+    match outcome {
+        CommandOutcome::Appended(_) => Ok(()),
+        CommandOutcome::Rejected(kind) => Err(BankAccountError::from(kind))?,
+        CommandOutcome::NotFound => Err(ErrorKind::BankAccountNotFound(id))?,
+        CommandOutcome::Deleted => Err(ErrorKind::BankAccountDeleted(id))?,
+    }
+}
+
+fn warn_on_failure(action: &str, result: Result<(), IdempotencyError>, bank_account_id: &str, idempotency_key: &str) {
+    if let Err(err) = result {
+        warn!("Failed to {} idempotency key {} of {}: {}", action, idempotency_key, bank_account_id, err);
+    }
+}
+
 pub struct BankAccountAggregateUseCase {
     eventstore: Box<BankAccountEventStore>,
+    idempotency_store: Option<Box<dyn IdempotencyStore>>,
+    idempotency_ttl: Duration,
 }
 
 impl BankAccountAggregateUseCase {
     pub fn new(eventstore: Box<BankAccountEventStore>) -> Self {
         Self {
             eventstore: eventstore,
+            idempotency_store: None,
+            idempotency_ttl: Duration::days(1),
         }
     }
 
+    pub fn with_idempotency_store(mut self, store: Box<dyn IdempotencyStore>, ttl: Duration) -> Self {
+        self.idempotency_store = Some(store);
+        self.idempotency_ttl = ttl;
+        self
+    }
+
     pub fn get(&self, bank_account_id: BankAccountId) -> Result<BankAccountAggregate, Error> {
-        match self.load_aggregate(&bank_account_id) {
+        match self.load_aggregate(&bank_account_id)? {
             Some(aggregate) => Ok(aggregate),
             None => Err(ErrorKind::BankAccountNotFound(bank_account_id.clone()))?,
         }
     }
 
+    pub fn events_since(&self, bank_account_id: BankAccountId, from_version: u64)
+        -> Result<Vec<(u64, BankAccountEvent)>, Error> {
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        events_since_stream(bank_account_id, self.eventstore.event_stream_since(stream_id, from_version))
+    }
+
     pub fn open(&self, bank_account_id: BankAccountId, name: BankAccountName)
         -> Result<(), Error> {
         self.handle_command(bank_account_id.clone(), BankAccountCommand::Open {
             bank_account_id: bank_account_id.clone(),
             name: name.clone(),
-        })
+        }, None)
     }
 
     pub fn update(&self, bank_account_id: BankAccountId, name: BankAccountName)
@@ -107,7 +309,7 @@ impl BankAccountAggregateUseCase {
         self.handle_command(bank_account_id.clone(), BankAccountCommand::Update {
             bank_account_id: bank_account_id.clone(),
             name: name.clone(),
-        })
+        }, None)
     }
 
     pub fn deposit(&self, bank_account_id: BankAccountId, deposit: i32)
@@ -115,7 +317,7 @@ impl BankAccountAggregateUseCase {
         self.handle_command(bank_account_id.clone(), BankAccountCommand::Deposit {
             bank_account_id: bank_account_id.clone(),
             deposit: deposit,
-        })
+        }, None)
     }
 
     pub fn withdraw(&self, bank_account_id: BankAccountId, withdraw: i32)
@@ -123,80 +325,462 @@ impl BankAccountAggregateUseCase {
         self.handle_command(bank_account_id.clone(), BankAccountCommand::Withdraw {
             bank_account_id: bank_account_id.clone(),
             withdraw: withdraw,
-        })
+        }, None)
     }
 
     pub fn close(&self, bank_account_id: BankAccountId)
         -> Result<(), Error> {
         self.handle_command(bank_account_id.clone(), BankAccountCommand::Close {
             bank_account_id: bank_account_id.clone(),
-        })
+        }, None)
+    }
+
+    pub fn handle(&self, command: BankAccountCommand, idempotency_key: Option<&str>)
+        -> Result<(), Error> {
+        self.handle_command(command.bank_account_id().clone(), command, idempotency_key)
     }
 
-    fn handle_command(&self, id: BankAccountId, command: BankAccountCommand)
+    #[instrument(skip_all, fields(bank_account_id = %id, command = command.command_type()))]
+    fn handle_command(&self, id: BankAccountId, command: BankAccountCommand, idempotency_key: Option<&str>)
         -> Result<(), Error> {
-        let mut aggregate = match self.load_aggregate(&id) {
-            Some(aggregate) => aggregate,
-            None => BankAccountAggregate::new(),
+        let command_type = command.command_type();
+        let started_at = Instant::now();
+        let result = match (&self.idempotency_store, idempotency_key) {
+            (Some(store), Some(key)) => self.execute_idempotent(store.as_ref(), id, command, key),
+            _ => self.execute_command(id, command, None).map(|_| ()),
         };
+        record_command(command_type, started_at, &result);
+        result
+    }
 
-        BankAccountAggregate::handle_command(&aggregate, command)
-            .map_err(|err| Error::from(err))
-            .and_then(|events| {
-                for event in events.iter() {
-                    aggregate = match BankAccountAggregate::apply_event(&aggregate, event.clone()) {
-                        Ok(aggregate) => aggregate,
-                        Err(err) => {
-                            return Err(err)?;
-                        },
-                    };
-                }
-                Ok((aggregate, events))
-            })
-            .and_then(|(aggregate, events)| {
-                let stream_id = BankAccountAggregate::stream_id(aggregate.id());
-                let stream_version = aggregate.version() + 1;
-
-                self.eventstore
-                    .append_event_stream(stream_id, stream_version, events.clone())
-                    .map_err(|err| Error::from(err))
-                    .map(|_| events)
-            })
-            .map(|_| ())
-    }
-
-    fn load_aggregate(&self, id: &BankAccountId) -> Option<BankAccountAggregate> {
-        match self.eventstore.read_snapshot(BankAccountAggregate::stream_id(id)).unwrap() {
-            Some(snapshot) => {
-                let stream_id = BankAccountAggregate::stream_id(id);
-                let stream_version = snapshot.stream_version() + 1;
-                match self.eventstore.event_stream_since(stream_id, stream_version) {
-                    Ok(stream) => {
-                        let aggregate = BankAccountAggregate::load_from_snapshot(snapshot);
-                        let history = stream.events().clone();
-                        Some(BankAccountAggregate::load_from_history(&aggregate, history, stream.version()).unwrap())
-                    },
-                    Err(e) => match e.kind() {
-                        EventStoreErrorKind::NoEventStreamError(_, _) => Some(BankAccountAggregate::load_from_snapshot(snapshot)),
-                        _ => panic!(e.to_string()),
-                    },
-                }
+    fn execute_idempotent(&self, store: &dyn IdempotencyStore, id: BankAccountId, command: BankAccountCommand,
+                          idempotency_key: &str) -> Result<(), Error> {
+        let bank_account_id = id.to_string();
+        let fingerprint = command_fingerprint(&command);
+        let expired_before = Local::now() - self.idempotency_ttl;
+        let reservation = store.reserve(&bank_account_id, idempotency_key, &fingerprint, expired_before)?;
+        match reservation_step(reservation, idempotency_key)? {
+            ReservationStep::Execute => (),
+            ReservationStep::Replay(outcome) => return replay(id, outcome),
+            ReservationStep::LookupVersion => {
+                let stream_id = BankAccountAggregate::stream_id(&id);
+                let stream_version = in_progress_version(
+                    self.eventstore.idempotency_key_version(stream_id, idempotency_key.to_string()), idempotency_key)?;
+                let outcome = CommandOutcome::Appended(stream_version);
+                warn_on_failure("complete", store.complete(&bank_account_id, idempotency_key, outcome),
+                                &bank_account_id, idempotency_key);
+                return Ok(());
             },
-            None => {
-                let stream_id = BankAccountAggregate::stream_id(id);
-                match self.eventstore.event_stream_since(stream_id, 1) {
-                    Ok(stream) => {
-                        let aggregate = BankAccountAggregate::new();
-                        let history = stream.events().clone();
-                        Some(BankAccountAggregate::load_from_history(&aggregate, history, stream.version()).unwrap())
-                    },
-                    Err(e) => match e.kind() {
-                        EventStoreErrorKind::NoEventStreamError(_, _) => None,
-                        _ => panic!(e.to_string()),
-                    },
-
-                }
+        }
+
+        let result = self.execute_command(id, command, Some(idempotency_key));
+        match command_outcome(&result) {
+            Some(outcome) => warn_on_failure("complete", store.complete(&bank_account_id, idempotency_key, outcome),
+                                             &bank_account_id, idempotency_key),
+            None => warn_on_failure("release", store.release(&bank_account_id, idempotency_key),
+                                    &bank_account_id, idempotency_key),
+        }
+        result.map(|_| ())
+    }
+
+    fn execute_command(&self, id: BankAccountId, command: BankAccountCommand, idempotency_key: Option<&str>)
+        -> Result<u64, Error> {
+        let decision = decide(self.load_aggregate(&id)?, command)?;
+        let last_version = decision.last_version();
+        match idempotency_key {
+            Some(key) => self.eventstore.append_idempotent_event_stream(
+                decision.stream_id, decision.stream_version, decision.events, key.to_string())?,
+            None => self.eventstore.append_event_stream(decision.stream_id, decision.stream_version, decision.events)?,
+        }
+        Ok(last_version)
+    }
+
+    #[instrument(skip_all, fields(bank_account_id = %id))]
+    fn load_aggregate(&self, id: &BankAccountId) -> Result<Option<BankAccountAggregate>, Error> {
+        let stream_id = BankAccountAggregate::stream_id(id);
+        let (aggregate, stream_version) = restore_from_snapshot(self.eventstore.read_snapshot(stream_id.clone())?);
+        restore_from_stream(id, aggregate, self.eventstore.event_stream_since(stream_id, stream_version))
+    }
+}
+
+pub struct AsyncBankAccountAggregateUseCase {
+    eventstore: Box<AsyncBankAccountEventStore>,
+    idempotency_store: Option<Box<dyn AsyncIdempotencyStore>>,
+    idempotency_ttl: Duration,
+}
+
+impl AsyncBankAccountAggregateUseCase {
+    pub fn new(eventstore: Box<AsyncBankAccountEventStore>) -> Self {
+        Self {
+            eventstore,
+            idempotency_store: None,
+            idempotency_ttl: Duration::days(1),
+        }
+    }
+
+    pub fn with_idempotency_store(mut self, store: Box<dyn AsyncIdempotencyStore>, ttl: Duration) -> Self {
+        self.idempotency_store = Some(store);
+        self.idempotency_ttl = ttl;
+        self
+    }
+
+    pub async fn get(&self, bank_account_id: BankAccountId) -> Result<BankAccountAggregate, Error> {
+        match self.load_aggregate(&bank_account_id).await? {
+            Some(aggregate) => Ok(aggregate),
+            None => Err(ErrorKind::BankAccountNotFound(bank_account_id.clone()))?,
+        }
+    }
+
+    pub async fn events_since(&self, bank_account_id: BankAccountId, from_version: u64)
+        -> Result<Vec<(u64, BankAccountEvent)>, Error> {
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        events_since_stream(bank_account_id, self.eventstore.event_stream_since(stream_id, from_version).await)
+    }
+
+    pub async fn open(&self, bank_account_id: BankAccountId, name: BankAccountName)
+        -> Result<(), Error> {
+        self.handle_command(bank_account_id.clone(), BankAccountCommand::Open {
+            bank_account_id: bank_account_id.clone(),
+            name: name.clone(),
+        }, None).await
+    }
+
+    pub async fn update(&self, bank_account_id: BankAccountId, name: BankAccountName)
+        -> Result<(), Error> {
+        self.handle_command(bank_account_id.clone(), BankAccountCommand::Update {
+            bank_account_id: bank_account_id.clone(),
+            name: name.clone(),
+        }, None).await
+    }
+
+    pub async fn deposit(&self, bank_account_id: BankAccountId, deposit: i32)
+        -> Result<(), Error> {
+        self.handle_command(bank_account_id.clone(), BankAccountCommand::Deposit {
+            bank_account_id: bank_account_id.clone(),
+            deposit,
+        }, None).await
+    }
+
+    pub async fn withdraw(&self, bank_account_id: BankAccountId, withdraw: i32)
+        -> Result<(), Error> {
+        self.handle_command(bank_account_id.clone(), BankAccountCommand::Withdraw {
+            bank_account_id: bank_account_id.clone(),
+            withdraw,
+        }, None).await
+    }
+
+    pub async fn close(&self, bank_account_id: BankAccountId)
+        -> Result<(), Error> {
+        self.handle_command(bank_account_id.clone(), BankAccountCommand::Close {
+            bank_account_id: bank_account_id.clone(),
+        }, None).await
+    }
+
+    pub async fn handle(&self, command: BankAccountCommand, idempotency_key: Option<&str>)
+        -> Result<(), Error> {
+        self.handle_command(command.bank_account_id().clone(), command, idempotency_key).await
+    }
+
+    #[instrument(skip_all, fields(bank_account_id = %id, command = command.command_type()))]
+    async fn handle_command(&self, id: BankAccountId, command: BankAccountCommand, idempotency_key: Option<&str>)
+        -> Result<(), Error> {
+        let command_type = command.command_type();
+        let started_at = Instant::now();
+        let result = match (&self.idempotency_store, idempotency_key) {
+            (Some(store), Some(key)) => self.execute_idempotent(store.as_ref(), id, command, key).await,
+            _ => self.execute_command(id, command, None).await.map(|_| ()),
+        };
+        record_command(command_type, started_at, &result);
+        result
+    }
+
+    async fn execute_idempotent(&self, store: &dyn AsyncIdempotencyStore, id: BankAccountId,
+                                command: BankAccountCommand, idempotency_key: &str) -> Result<(), Error> {
+        let bank_account_id = id.to_string();
+        let fingerprint = command_fingerprint(&command);
+        let expired_before = Local::now() - self.idempotency_ttl;
+        let reservation = store.reserve(&bank_account_id, idempotency_key, &fingerprint, expired_before).await?;
+        match reservation_step(reservation, idempotency_key)? {
+            ReservationStep::Execute => (),
+            ReservationStep::Replay(outcome) => return replay(id, outcome),
+            ReservationStep::LookupVersion => {
+                let stream_id = BankAccountAggregate::stream_id(&id);
+                let stream_version = in_progress_version(
+                    self.eventstore.idempotency_key_version(stream_id, idempotency_key.to_string()).await,
+                    idempotency_key)?;
+                let outcome = CommandOutcome::Appended(stream_version);
+                warn_on_failure("complete", store.complete(&bank_account_id, idempotency_key, outcome).await,
+                                &bank_account_id, idempotency_key);
+                return Ok(());
             },
         }
+
+        let result = self.execute_command(id, command, Some(idempotency_key)).await;
+        match command_outcome(&result) {
+            Some(outcome) => warn_on_failure("complete",
+                                             store.complete(&bank_account_id, idempotency_key, outcome).await,
+                                             &bank_account_id, idempotency_key),
+            None => warn_on_failure("release", store.release(&bank_account_id, idempotency_key).await,
+                                    &bank_account_id, idempotency_key),
+        }
+        result.map(|_| ())
+    }
+
+    async fn execute_command(&self, id: BankAccountId, command: BankAccountCommand, idempotency_key: Option<&str>)
+        -> Result<u64, Error> {
+        let decision = decide(self.load_aggregate(&id).await?, command)?;
+        let last_version = decision.last_version();
+        match idempotency_key {
+            Some(key) => self.eventstore.append_idempotent_event_stream(
+                decision.stream_id, decision.stream_version, decision.events, key.to_string()).await?,
+            None => self.eventstore
+                .append_event_stream(decision.stream_id, decision.stream_version, decision.events).await?,
+        }
+        Ok(last_version)
+    }
+
+    #[instrument(skip_all, fields(bank_account_id = %id))]
+    async fn load_aggregate(&self, id: &BankAccountId) -> Result<Option<BankAccountAggregate>, Error> {
+        let stream_id = BankAccountAggregate::stream_id(id);
+        let (aggregate, stream_version) =
+            restore_from_snapshot(self.eventstore.read_snapshot(stream_id.clone()).await?);
+        restore_from_stream(id, aggregate, self.eventstore.event_stream_since(stream_id, stream_version).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::{Duration, Local};
+    use failure::Fail;
+
+    use super::{BankAccountAggregateUseCase, AsyncBankAccountAggregateUseCase, ErrorKind};
+    use super::super::super::aggregate::{
+        BankAccountAggregate,
+        BankAccountCommand,
+        BankAccountEvent,
+        BankAccountId,
+        BankAccountName,
+    };
+    use super::super::super::eventsourcing::{AsyncEventStore, EventStore, StreamMetadata};
+    use super::super::super::idempotency::{
+        command_fingerprint,
+        CommandOutcome,
+        IdempotencyStore,
+        InmemoryIdempotencyStore,
+        Reservation,
+    };
+    use super::super::super::inmemory_eventstore::InmemoryBankAccountEventStore;
+    use super::super::super::blocking::{BlockingEventStore, BlockingIdempotencyStore};
+
+    #[tokio::test]
+    async fn test_async_usecase() {
+        let usecase = AsyncBankAccountAggregateUseCase::new(Box::new(InmemoryBankAccountEventStore::new()));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+        assert!(usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).await.is_ok());
+        assert!(usecase.deposit(bank_account_id.clone(), 500).await.is_ok());
+        assert!(usecase.withdraw(bank_account_id.clone(), 200).await.is_ok());
+        assert!(usecase.withdraw(bank_account_id.clone(), 400).await.is_err());
+
+        let aggregate = usecase.get(bank_account_id.clone()).await.unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().balance(), 300);
+        assert_eq!(aggregate.version(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_usecase_with_blocking_store() {
+        let store = BlockingEventStore::new(InmemoryBankAccountEventStore::new());
+        let usecase = AsyncBankAccountAggregateUseCase::new(Box::new(store));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+        assert!(usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).await.is_ok());
+        assert!(usecase.close(bank_account_id.clone()).await.is_ok());
+
+        let aggregate = usecase.get(bank_account_id.clone()).await.unwrap();
+        assert!(aggregate.state().as_ref().unwrap().is_closed());
+    }
+
+    #[test]
+    fn test_usecase_refuses_missing_bank_account() {
+        let usecase = BankAccountAggregateUseCase::new(Box::new(InmemoryBankAccountEventStore::new()))
+            .with_idempotency_store(Box::new(InmemoryIdempotencyStore::new()), Duration::hours(1));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let deposit = BankAccountCommand::Deposit {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 100,
+        };
+
+        for idempotency_key in [None, Some("deposit"), Some("deposit")] {
+            match usecase.handle(deposit.clone(), idempotency_key).unwrap_err().kind() {
+                ErrorKind::BankAccountNotFound(id) => assert_eq!(id, &bank_account_id),
+                kind => panic!("unexpected error: {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn test_usecase_refuses_deleted_bank_account() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let usecase = BankAccountAggregateUseCase::new(Box::new(store.clone()));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        usecase.close(bank_account_id.clone()).unwrap();
+
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        store.set_stream_metadata(stream_id.clone(), StreamMetadata {
+            deleted: true,
+            ..StreamMetadata::default()
+        }).unwrap();
+        store.purge_stream(stream_id).unwrap();
+
+        for result in [
+            usecase.get(bank_account_id.clone()).map(|_| ()),
+            usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("bar")).unwrap()),
+        ] {
+            match result.unwrap_err().kind() {
+                ErrorKind::BankAccountDeleted(id) => assert_eq!(id, &bank_account_id),
+                kind => panic!("unexpected error: {:?}", kind),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_usecase_refuses_deleted_bank_account() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let usecase = AsyncBankAccountAggregateUseCase::new(Box::new(BlockingEventStore::new(store.clone())));
+        let admin = BlockingEventStore::new(store.clone());
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).await.unwrap();
+
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        admin.set_stream_metadata(stream_id.clone(), StreamMetadata {
+            deleted: true,
+            ..StreamMetadata::default()
+        }).await.unwrap();
+        assert!(admin.stream_metadata(stream_id.clone()).await.unwrap().deleted);
+        assert_eq!(admin.purge_stream(stream_id).await.unwrap(), 1);
+
+        match usecase.get(bank_account_id.clone()).await.unwrap_err().kind() {
+            ErrorKind::BankAccountDeleted(id) => assert_eq!(id, &bank_account_id),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_usecase_with_idempotency_key() {
+        let usecase = BankAccountAggregateUseCase::new(Box::new(InmemoryBankAccountEventStore::new()))
+            .with_idempotency_store(Box::new(InmemoryIdempotencyStore::new()), Duration::hours(1));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let deposit = |deposit| BankAccountCommand::Deposit {
+            bank_account_id: bank_account_id.clone(),
+            deposit,
+        };
+        let withdraw = |withdraw| BankAccountCommand::Withdraw {
+            bank_account_id: bank_account_id.clone(),
+            withdraw,
+        };
+
+        usecase.handle(BankAccountCommand::Open {
+            bank_account_id: bank_account_id.clone(),
+            name: BankAccountName::new(String::from("foo")).unwrap(),
+        }, Some("open")).unwrap();
+        usecase.handle(deposit(100), Some("deposit")).unwrap();
+        usecase.handle(deposit(100), Some("deposit")).unwrap();
+
+        match usecase.handle(deposit(200), Some("deposit")).unwrap_err().kind() {
+            ErrorKind::IdempotencyKeyReused(key) => assert_eq!(key, "deposit"),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+
+        let rejected = usecase.handle(withdraw(150), Some("withdraw")).unwrap_err();
+        assert!(matches!(rejected.kind(), ErrorKind::BankAccountError));
+        usecase.handle(deposit(100), Some("deposit-2")).unwrap();
+
+        let replayed = usecase.handle(withdraw(150), Some("withdraw")).unwrap_err();
+        assert!(matches!(replayed.kind(), ErrorKind::BankAccountError));
+        assert_eq!(replayed.cause().unwrap().to_string(), rejected.cause().unwrap().to_string());
+
+        let aggregate = usecase.get(bank_account_id.clone()).unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().balance(), 200);
+        assert_eq!(aggregate.version(), 3);
+    }
+
+    #[test]
+    fn test_usecase_finds_idempotency_key_in_event_stream() {
+        let store = Arc::new(InmemoryBankAccountEventStore::new());
+        let idempotency_store = Arc::new(InmemoryIdempotencyStore::new());
+        let usecase = BankAccountAggregateUseCase::new(Box::new(store.clone()))
+            .with_idempotency_store(Box::new(idempotency_store.clone()), Duration::hours(1));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+        let stream_id = BankAccountAggregate::stream_id(&bank_account_id);
+        let deposit = BankAccountCommand::Deposit {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 100,
+        };
+
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).unwrap();
+        usecase.handle(deposit.clone(), Some("deposit-1")).unwrap();
+        assert_eq!(store.idempotency_key_version(stream_id.clone(), String::from("deposit-1")).unwrap(), Some(2));
+
+        let expired_before = Local::now() - Duration::hours(1);
+        let fingerprint = command_fingerprint(&deposit);
+        assert_eq!(idempotency_store.reserve(&bank_account_id.to_string(), "deposit-2", &fingerprint, expired_before)
+                   .unwrap(), Reservation::Reserved);
+        match usecase.handle(deposit.clone(), Some("deposit-2")).unwrap_err().kind() {
+            ErrorKind::CommandInProgress(key) => assert_eq!(key, "deposit-2"),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+
+        store.append_idempotent_event_stream(stream_id.clone(), 3, vec![BankAccountEvent::Deposited {
+            bank_account_id: bank_account_id.clone(),
+            deposit: 100,
+            occurred_at: Local::now(),
+        }], String::from("deposit-2")).unwrap();
+        usecase.handle(deposit.clone(), Some("deposit-2")).unwrap();
+        assert_eq!(idempotency_store.reserve(&bank_account_id.to_string(), "deposit-2", &fingerprint, expired_before)
+                   .unwrap(), Reservation::Completed(CommandOutcome::Appended(3)));
+
+        let aggregate = usecase.get(bank_account_id.clone()).unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().balance(), 200);
+        assert_eq!(aggregate.version(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_usecase_with_idempotency_key() {
+        let usecase = AsyncBankAccountAggregateUseCase::new(Box::new(InmemoryBankAccountEventStore::new()))
+            .with_idempotency_store(Box::new(BlockingIdempotencyStore::new(InmemoryIdempotencyStore::new())),
+                                    Duration::hours(1));
+
+        let bank_account_id = BankAccountId::new(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")).unwrap();
+
+        usecase.open(bank_account_id.clone(), BankAccountName::new(String::from("foo")).unwrap()).await.unwrap();
+        for _ in 0..2 {
+            usecase.handle(BankAccountCommand::Deposit {
+                bank_account_id: bank_account_id.clone(),
+                deposit: 100,
+            }, Some("deposit")).await.unwrap();
+        }
+
+        let withdraw = BankAccountCommand::Withdraw {
+            bank_account_id: bank_account_id.clone(),
+            withdraw: 150,
+        };
+        assert!(usecase.handle(withdraw.clone(), Some("withdraw")).await.is_err());
+        usecase.deposit(bank_account_id.clone(), 100).await.unwrap();
+        assert!(usecase.handle(withdraw, Some("withdraw")).await.is_err());
+
+        let aggregate = usecase.get(bank_account_id.clone()).await.unwrap();
+        assert_eq!(aggregate.state().as_ref().unwrap().balance(), 200);
+        assert_eq!(aggregate.version(), 3);
     }
 }